@@ -1,15 +1,17 @@
+use std::ops::ControlFlow;
+
 use xege::*;
 
 fn main() {
-    let xege = initgraph(640, 480, Init::Default).unwrap();
-    loop {
-        let kbmsg = xege.window.getmsg();
-        if let Some((keymsg, keyflags)) = kbmsg {
-            println!("KeyMsg: {:?}, KeyFlags: {:?}", keymsg, keyflags)
-        }
-        let ms = xege.window.getmouse();
-        if let Some(mousemsg) = ms {
-            println!("MouseMsg: {:?}", mousemsg)
+    let mut xege = initgraph(640, 480, Init::NoForceExit).unwrap();
+    xege.window.start_text_input().unwrap();
+    xege.window.set_text_input_pos(Point { x: 20, y: 20 });
+    xege.run(|_, event| {
+        match event {
+            Event::Tick { .. } => {}
+            Event::KeyDown { key: Key::Esc, .. } => return ControlFlow::Break(()),
+            event => println!("{:?}", event),
         }
-    }
+        ControlFlow::Continue(())
+    });
 }
@@ -0,0 +1,18 @@
+use xege::*;
+
+fn draw(xege: &mut impl Draw) {
+    xege.setbkcolor(color::DARKGRAY);
+    xege.clear();
+    xege.setcolor(color::BLACK);
+    xege.setfillcolor(color::CYAN);
+    xege.fillellipse(320, 240, 200, 120);
+    xege.setcolor(Color::new(255, 255, 0, 255));
+    xege.setlinewidth(10f32);
+    Draw::line(xege, 50, 60, 120, 180);
+}
+
+fn main() {
+    let mut xege = headless(640, 480);
+    draw(&mut xege);
+    xege.save("headless.png", false).unwrap();
+}
@@ -0,0 +1,560 @@
+use std::collections::BTreeMap;
+use std::ops::Index;
+#[cfg(any(feature = "json", feature = "ron"))]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "ege")]
+use crate::{GraphicsEnvironment, Image};
+use crate::{ImageView, Rect};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtlasError {
+    #[error("The sprite name `{0}` is used more than once.")]
+    DuplicateName(String),
+    #[error("The sprite `{name}` of size {width}x{height} does not fit into the atlas.")]
+    DoesNotFit {
+        name: String,
+        width: u32,
+        height: u32,
+    },
+    #[cfg(any(feature = "json", feature = "ron"))]
+    #[error("Reading or writing `{}` failed.", path.display())]
+    IOError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[cfg(feature = "json")]
+    #[error("Invalid JSON atlas layout: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "ron")]
+    #[error("Failed to write the RON atlas layout: {0}")]
+    Ron(#[from] ron::Error),
+    #[cfg(feature = "ron")]
+    #[error("Invalid RON atlas layout: {0}")]
+    RonParse(#[from] ron::de::SpannedError),
+}
+
+/// The layout of a sprite atlas: named regions of one image.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Atlas {
+    /// The width of the atlas image.
+    pub width: u32,
+    /// The height of the atlas image.
+    pub height: u32,
+    /// The regions of the sprites, by name.
+    pub regions: BTreeMap<String, Rect>,
+}
+
+impl Atlas {
+    /// Get the region of a sprite.
+    pub fn get(&self, name: &str) -> Option<Rect> {
+        self.regions.get(name).copied()
+    }
+
+    /// Iterate over the sprites and their regions, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Rect)> {
+        self.regions
+            .iter()
+            .map(|(name, rect)| (name.as_str(), *rect))
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+impl Index<&str> for Atlas {
+    type Output = Rect;
+
+    /// Get the region of a sprite.
+    ///
+    /// # Panics
+    /// Panics if there is no sprite with this name.
+    fn index(&self, name: &str) -> &Rect {
+        self.regions
+            .get(name)
+            .unwrap_or_else(|| panic!("There is no sprite named `{name}` in the atlas."))
+    }
+}
+
+#[cfg(any(feature = "json", feature = "ron"))]
+fn read(path: &Path) -> Result<String, AtlasError> {
+    std::fs::read_to_string(path).map_err(|source| AtlasError::IOError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(any(feature = "json", feature = "ron"))]
+fn write(path: &Path, text: String) -> Result<(), AtlasError> {
+    std::fs::write(path, text).map_err(|source| AtlasError::IOError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(feature = "json")]
+impl Atlas {
+    /// Serialize the layout to JSON.
+    pub fn to_json(&self) -> Result<String, AtlasError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a layout from JSON.
+    pub fn from_json(text: &str) -> Result<Self, AtlasError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Save the layout to a JSON file.
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<(), AtlasError> {
+        write(path.as_ref(), self.to_json()?)
+    }
+
+    /// Load a layout from a JSON file.
+    pub fn load_json<P: AsRef<Path>>(path: P) -> Result<Self, AtlasError> {
+        Self::from_json(&read(path.as_ref())?)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl Atlas {
+    /// Serialize the layout to RON.
+    pub fn to_ron(&self) -> Result<String, AtlasError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    /// Parse a layout from RON.
+    pub fn from_ron(text: &str) -> Result<Self, AtlasError> {
+        Ok(ron::from_str(text)?)
+    }
+
+    /// Save the layout to a RON file.
+    pub fn save_ron<P: AsRef<Path>>(&self, path: P) -> Result<(), AtlasError> {
+        write(path.as_ref(), self.to_ron()?)
+    }
+
+    /// Load a layout from a RON file.
+    pub fn load_ron<P: AsRef<Path>>(path: P) -> Result<Self, AtlasError> {
+        Self::from_ron(&read(path.as_ref())?)
+    }
+}
+
+/// The top edge of the packed area, as horizontal segments `(x, y, width)`.
+struct Skyline {
+    width: u32,
+    segments: Vec<(u32, u32, u32)>,
+}
+
+impl Skyline {
+    fn new(width: u32) -> Self {
+        Self {
+            width,
+            segments: vec![(0, 0, width)],
+        }
+    }
+
+    /// Find the lowest position for a rectangle, returning the segment index and the position.
+    fn find(&self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+        let mut best: Option<(usize, u32, u32)> = None;
+        for (i, &(x, ..)) in self.segments.iter().enumerate() {
+            if x as u64 + width as u64 > self.width as u64 {
+                break;
+            }
+            let y = self.segments[i..]
+                .iter()
+                .take_while(|&&(sx, ..)| sx < x + width || sx == x)
+                .map(|&(_, sy, _)| sy)
+                .max()
+                .unwrap_or(0);
+            if best.is_none_or(|(_, bx, by)| (y + height, x) < (by + height, bx)) {
+                best = Some((i, x, y));
+            }
+        }
+        best
+    }
+
+    fn place(&mut self, i: usize, x: u32, y: u32, width: u32, height: u32) {
+        let end = x + width;
+        while let Some(segment) = self.segments.get_mut(i) {
+            if segment.0 >= end {
+                break;
+            }
+            if segment.0 + segment.2 <= end {
+                self.segments.remove(i);
+            } else {
+                segment.2 -= end - segment.0;
+                segment.0 = end;
+                break;
+            }
+        }
+        self.segments.insert(i, (x, y + height, width));
+        self.segments.dedup_by(|b, a| {
+            let merge = a.1 == b.1 && a.0 + a.2 == b.0;
+            if merge {
+                a.2 += b.2;
+            }
+            merge
+        });
+    }
+}
+
+/// Packs many images into one sprite atlas.
+///
+/// Sprites are placed with the skyline bottom-left algorithm, the tallest first.
+///
+/// ```no_run
+/// # #[cfg(feature = "ege")] {
+/// use xege::*;
+///
+/// let (hero, coin) = (Image::from_file("hero.png")?, Image::from_file("coin.png")?);
+/// let mut builder = AtlasBuilder::new(1024);
+/// builder.padding(2).add_image("hero", &hero)?.add_image("coin", &coin)?;
+/// let (image, atlas) = builder.build()?;
+/// image.save("sprites.png", true)?;
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct AtlasBuilder<'a> {
+    max_width: u32,
+    padding: u32,
+    sprites: Vec<(String, ImageView<'a>)>,
+}
+
+impl<'a> AtlasBuilder<'a> {
+    /// Create an empty builder.
+    ///
+    /// # Parameters
+    /// - `max_width`: The largest width of the atlas image. The height grows as needed.
+    pub fn new(max_width: u32) -> Self {
+        Self {
+            max_width,
+            padding: 0,
+            sprites: vec![],
+        }
+    }
+
+    /// Set the number of empty pixels between the sprites and around the border.
+    pub fn padding(&mut self, padding: u32) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Add a sprite.
+    ///
+    /// # Parameters
+    /// - `name`: The unique name of the sprite.
+    /// - `view`: The pixels of the sprite, e.g. a region of a sprite sheet.
+    ///
+    /// # Returns
+    /// The builder. Or an error if the name is already used.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        view: ImageView<'a>,
+    ) -> Result<&mut Self, AtlasError> {
+        let name = name.into();
+        if self.sprites.iter().any(|(n, _)| *n == name) {
+            return Err(AtlasError::DuplicateName(name));
+        }
+        self.sprites.push((name, view));
+        Ok(self)
+    }
+
+    /// Add a whole image as a sprite.
+    ///
+    /// # Note
+    /// See `add`.
+    #[cfg(feature = "ege")]
+    pub fn add_image(
+        &mut self,
+        name: impl Into<String>,
+        image: &'a Image,
+    ) -> Result<&mut Self, AtlasError> {
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width: image.getwidth() as i32,
+            height: image.getheight() as i32,
+        };
+        self.add(name, image.view(rect).expect("The image contains itself."))
+    }
+
+    /// Compute the layout without creating the atlas image.
+    ///
+    /// # Returns
+    /// The layout. Or an error if a sprite is wider than the atlas.
+    ///
+    /// # Note
+    /// Sprites without pixels take no space, their regions are empty and at the
+    /// top-left corner inside of the padding.
+    pub fn layout(&self) -> Result<Atlas, AtlasError> {
+        let pad = self.padding;
+        let mut order = (0..self.sprites.len()).collect::<Vec<_>>();
+        // The tallest sprites first, ties by width and then by name.
+        order.sort_by(|&a, &b| {
+            let ((name_a, a), (name_b, b)) = (&self.sprites[a], &self.sprites[b]);
+            (b.height(), b.width())
+                .cmp(&(a.height(), a.width()))
+                .then_with(|| name_a.cmp(name_b))
+        });
+        let mut skyline = Skyline::new(self.max_width.saturating_sub(pad));
+        let mut atlas = Atlas::default();
+        for i in order {
+            let (name, view) = &self.sprites[i];
+            if view.width() == 0 || view.height() == 0 {
+                let rect = Rect {
+                    x: pad as i32,
+                    y: pad as i32,
+                    width: view.width() as i32,
+                    height: view.height() as i32,
+                };
+                atlas.regions.insert(name.clone(), rect);
+                continue;
+            }
+            let (width, height) = (view.width() + pad, view.height() + pad);
+            let (segment, x, y) =
+                skyline
+                    .find(width, height)
+                    .ok_or_else(|| AtlasError::DoesNotFit {
+                        name: name.clone(),
+                        width: view.width(),
+                        height: view.height(),
+                    })?;
+            skyline.place(segment, x, y, width, height);
+            atlas.width = atlas.width.max(pad + x + width);
+            atlas.height = atlas.height.max(pad + y + height);
+            let rect = Rect {
+                x: (pad + x) as i32,
+                y: (pad + y) as i32,
+                width: view.width() as i32,
+                height: view.height() as i32,
+            };
+            atlas.regions.insert(name.clone(), rect);
+        }
+        Ok(atlas)
+    }
+
+    /// Pack the sprites into a new image.
+    ///
+    /// # Returns
+    /// The atlas image and its layout. Or an error if a sprite is wider than the atlas.
+    ///
+    /// # Note
+    /// The image is only as large as the packed sprites need. Pixels outside of the
+    /// sprites are transparent.
+    #[cfg(feature = "ege")]
+    pub fn build(&self) -> Result<(Image, Atlas), AtlasError> {
+        let atlas = self.layout()?;
+        let mut image = Image::new(atlas.width, atlas.height);
+        image.getbuffer_mut().fill(0);
+        for (name, view) in &self.sprites {
+            if let Some(mut target) = image.view_mut(atlas[name.as_str()]) {
+                target.copy_from(view);
+            }
+        }
+        Ok((image, atlas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZES: [(u32, u32); 8] = [
+        (10, 4),
+        (3, 9),
+        (7, 7),
+        (16, 2),
+        (1, 1),
+        (5, 12),
+        (8, 3),
+        (4, 4),
+    ];
+
+    fn builder(pixels: &[u32], max_width: u32, padding: u32) -> AtlasBuilder<'_> {
+        let mut builder = AtlasBuilder::new(max_width);
+        builder.padding(padding);
+        for (i, (width, height)) in SIZES.into_iter().enumerate() {
+            let view = ImageView::new(pixels, width as usize, width, height);
+            builder.add(format!("sprite{i}"), view).unwrap();
+        }
+        builder
+    }
+
+    /// Check that the sprites are inside of the atlas and at least `gap` pixels apart.
+    fn check(atlas: &Atlas, max_width: u32, gap: i32) {
+        assert!(atlas.width <= max_width);
+        let regions = atlas.iter().map(|(_, rect)| rect).collect::<Vec<_>>();
+        for (i, a) in regions.iter().enumerate() {
+            assert!(a.x >= gap && a.y >= gap, "{a:?}");
+            assert!(a.x + a.width + gap <= atlas.width as i32, "{a:?}");
+            assert!(a.y + a.height + gap <= atlas.height as i32, "{a:?}");
+            for b in &regions[i + 1..] {
+                let apart = a.x + a.width + gap <= b.x
+                    || b.x + b.width + gap <= a.x
+                    || a.y + a.height + gap <= b.y
+                    || b.y + b.height + gap <= a.y;
+                assert!(apart, "{a:?} and {b:?} are too close");
+            }
+        }
+    }
+
+    #[test]
+    fn sprites_do_not_overlap() {
+        let pixels = vec![0; 16 * 12];
+        for max_width in [16, 20, 32, 64] {
+            let atlas = builder(&pixels, max_width, 0).layout().unwrap();
+            assert_eq!(atlas.len(), SIZES.len());
+            for (i, (width, height)) in SIZES.into_iter().enumerate() {
+                let rect = atlas[format!("sprite{i}").as_str()];
+                assert_eq!((rect.width, rect.height), (width as i32, height as i32));
+            }
+            check(&atlas, max_width, 0);
+        }
+    }
+
+    #[test]
+    fn padding() {
+        let pixels = vec![0; 16 * 12];
+        for padding in [1, 2, 5] {
+            let max_width = 16 + 2 * padding;
+            let atlas = builder(&pixels, max_width, padding).layout().unwrap();
+            check(&atlas, max_width, padding as i32);
+        }
+        let mut builder = AtlasBuilder::new(10);
+        builder.padding(3);
+        builder.add("a", ImageView::new(&pixels, 4, 4, 2)).unwrap();
+        let atlas = builder.layout().unwrap();
+        assert_eq!((atlas.width, atlas.height), (10, 8));
+        assert_eq!(
+            atlas["a"],
+            Rect {
+                x: 3,
+                y: 3,
+                width: 4,
+                height: 2
+            }
+        );
+    }
+
+    #[test]
+    fn too_large() {
+        let pixels = vec![0; 16 * 12];
+        assert!(builder(&pixels, 16, 0).layout().is_ok());
+        assert!(matches!(
+            builder(&pixels, 15, 0).layout(),
+            Err(AtlasError::DoesNotFit { name, width: 16, height: 2 }) if name == "sprite3"
+        ));
+        assert!(matches!(
+            builder(&pixels, 17, 1).layout(),
+            Err(AtlasError::DoesNotFit { width: 16, .. })
+        ));
+        assert!(matches!(
+            AtlasBuilder::new(0)
+                .add("a", ImageView::new(&pixels, 1, 1, 1))
+                .unwrap()
+                .layout(),
+            Err(AtlasError::DoesNotFit {
+                width: 1,
+                height: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn empty_sprites_take_no_space() {
+        let pixels = vec![0; 4];
+        let mut builder = AtlasBuilder::new(8);
+        builder.padding(1);
+        builder
+            .add("wide", ImageView::new(&pixels, 100, 100, 0))
+            .unwrap()
+            .add("tall", ImageView::new(&pixels, 0, 0, 100))
+            .unwrap()
+            .add("dot", ImageView::new(&pixels, 2, 2, 2))
+            .unwrap();
+        let atlas = builder.layout().unwrap();
+        assert_eq!((atlas.width, atlas.height), (4, 4));
+        assert_eq!(
+            atlas["wide"],
+            Rect {
+                x: 1,
+                y: 1,
+                width: 100,
+                height: 0
+            }
+        );
+        assert_eq!(
+            atlas["tall"],
+            Rect {
+                x: 1,
+                y: 1,
+                width: 0,
+                height: 100
+            }
+        );
+        assert_eq!(
+            atlas["dot"],
+            Rect {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 2
+            }
+        );
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let pixels = vec![0; 1];
+        let mut builder = AtlasBuilder::new(8);
+        builder.add("a", ImageView::new(&pixels, 1, 1, 1)).unwrap();
+        assert!(matches!(
+            builder.add("a", ImageView::new(&pixels, 1, 1, 1)),
+            Err(AtlasError::DuplicateName(name)) if name == "a"
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let pixels = vec![0; 16 * 12];
+        let atlas = builder(&pixels, 32, 1).layout().unwrap();
+        assert_eq!(Atlas::from_json(&atlas.to_json().unwrap()).unwrap(), atlas);
+        let path = std::env::temp_dir().join(format!("xege-atlas-{}.json", std::process::id()));
+        atlas.save_json(&path).unwrap();
+        let loaded = Atlas::load_json(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), atlas);
+        assert!(matches!(Atlas::from_json("{"), Err(AtlasError::Json(_))));
+        assert!(matches!(
+            Atlas::load_json(&path),
+            Err(AtlasError::IOError { path: p, .. }) if p == path
+        ));
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_round_trip() {
+        let pixels = vec![0; 16 * 12];
+        let atlas = builder(&pixels, 32, 1).layout().unwrap();
+        assert_eq!(Atlas::from_ron(&atlas.to_ron().unwrap()).unwrap(), atlas);
+        let path = std::env::temp_dir().join(format!("xege-atlas-{}.ron", std::process::id()));
+        atlas.save_ron(&path).unwrap();
+        let loaded = Atlas::load_ron(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), atlas);
+        assert!(matches!(Atlas::from_ron("("), Err(AtlasError::RonParse(_))));
+    }
+}
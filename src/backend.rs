@@ -0,0 +1,466 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "ege")]
+use xege_ffi::*;
+
+use crate::{ARGB, Color, Event, FillPattern, Line, LineStyle, PixelPoint, Point};
+#[cfg(feature = "ege")]
+use crate::{Brush, DashPattern, EgeDevice};
+
+/// The drawing state of an EGE device that EGE has no equivalent for.
+#[cfg(feature = "ege")]
+#[derive(Debug, Clone, Default)]
+pub struct DeviceState {
+    /// The dash pattern of the lines, see `HighDraw::setlinedash`.
+    pub(crate) dash: Option<DashPattern>,
+    /// The brush of the fills, see `HighDraw::setfillbrush`.
+    pub(crate) brush: Option<Brush>,
+}
+
+/// The operations a device draws with.
+///
+/// The methods mirror those of `GraphicsEnvironment` and `Draw` with plain
+/// parameters: EGE devices forward them to EGE, a `Canvas` rasterizes them.
+/// Operations only available with EGE are methods of the traits that require
+/// `EgeDevice` instead.
+pub trait Backend {
+    fn clear(&mut self);
+    fn setfillcolor(&mut self, color: ARGB);
+    fn setcolor(&mut self, color: ARGB);
+    fn setbkcolor(&mut self, color: ARGB);
+    fn getbkcolor(&self) -> Color;
+    fn setlinecolor(&mut self, color: ARGB);
+    fn setfillstyle(&mut self, pattern: FillPattern, color: ARGB);
+    fn setlinestyle(&mut self, style: LineStyle, width: i32);
+    fn getcolor(&self) -> Color;
+    fn gettextcolor(&self) -> Color;
+    fn settextcolor(&mut self, color: ARGB);
+    fn setlinewidth(&mut self, width: f32);
+    fn getfillcolor(&self) -> Color;
+    fn getlinestyle(&self) -> (LineStyle, i32);
+    fn getwidth(&self) -> u32;
+    fn getheight(&self) -> u32;
+    fn getx(&self) -> i32;
+    fn gety(&self) -> i32;
+    fn moverel(&mut self, dx: i32, dy: i32);
+    fn moveto(&mut self, x: i32, y: i32);
+    fn getbuffer(&self) -> &[ARGB];
+    fn getbuffer_mut(&mut self) -> &mut [ARGB];
+    fn getpixel(&self, x: i32, y: i32) -> Color;
+    fn putpixel(&mut self, x: i32, y: i32, color: ARGB);
+    fn putpixel_savealpha(&mut self, x: i32, y: i32, color: ARGB);
+    fn putpixel_withalpha(&mut self, x: i32, y: i32, color: ARGB);
+    fn putpixel_alphablend(&mut self, x: i32, y: i32, color: ARGB, factor: u8);
+    fn putpixels(&mut self, array: &[PixelPoint<ARGB>]);
+    fn arc(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32);
+    fn arcf(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32);
+    fn ellipse(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32);
+    fn ellipsef(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32);
+    fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32);
+    fn linerel(&mut self, dx: i32, dy: i32);
+    fn lineto(&mut self, x: i32, y: i32);
+    fn drawlines(&mut self, lines: &[Line]);
+    fn polyline(&mut self, points: &[Point]);
+    fn drawpoly(&mut self, points: &[Point]);
+    fn drawbezier(&mut self, points: &[Point]);
+    fn rectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32);
+    fn fillrect(&mut self, left: i32, top: i32, right: i32, bottom: i32);
+    fn solidrectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32);
+    fn bar(&mut self, left: i32, top: i32, right: i32, bottom: i32);
+    fn polygon(&mut self, points: &[Point]);
+    fn fillpoly(&mut self, points: &[Point]);
+    fn solidpoly(&mut self, points: &[Point]);
+    fn circle(&mut self, x: i32, y: i32, radius: i32);
+    fn circlef(&mut self, x: f32, y: f32, radius: f32);
+    fn fillcircle(&mut self, x: i32, y: i32, radius: i32);
+    fn fillcirclef(&mut self, x: f32, y: f32, radius: f32);
+    fn fillellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32);
+    fn fillellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32);
+    fn solidcircle(&mut self, x: i32, y: i32, radius: i32);
+    fn solidcirclef(&mut self, x: f32, y: f32, radius: f32);
+    fn solidellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32);
+    fn solidellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32);
+    fn pie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32);
+    fn pief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32);
+    fn fillpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32);
+    fn fillpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32);
+    fn solidpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32);
+    fn solidpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32);
+    fn sector(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32);
+    fn sectorf(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32);
+    fn pieslice(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32);
+    fn pieslicef(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32);
+    fn floodfill(&mut self, x: i32, y: i32, color: ARGB);
+    fn floodfillsurface(&mut self, x: i32, y: i32, color: ARGB);
+}
+
+#[cfg(feature = "ege")]
+impl<T: EgeDevice> Backend for T {
+    fn clear(&mut self) {
+        unsafe { ege_cleardevice(self.mut_ptr()) };
+    }
+
+    fn setfillcolor(&mut self, color: ARGB) {
+        unsafe { ege_setfillcolor(color, self.mut_ptr()) };
+    }
+
+    fn setcolor(&mut self, color: ARGB) {
+        unsafe { ege_setcolor(color, self.mut_ptr()) };
+    }
+
+    fn setbkcolor(&mut self, color: ARGB) {
+        unsafe { ege_setbkcolor(color, self.mut_ptr()) };
+    }
+
+    fn getbkcolor(&self) -> Color {
+        let argb = unsafe { ege_getbkcolor(self.const_ptr()) };
+        Color::from_argb(argb)
+    }
+
+    fn setlinecolor(&mut self, color: ARGB) {
+        unsafe { ege_setlinecolor(color, self.mut_ptr()) };
+    }
+
+    fn setfillstyle(&mut self, pattern: FillPattern, color: ARGB) {
+        unsafe { ege_setfillstyle(pattern as i32, color, self.mut_ptr()) };
+    }
+
+    fn setlinestyle(&mut self, style: LineStyle, width: i32) {
+        if let LineStyle::UserDef(user_def) = style {
+            unsafe { ege_setlinestyle(style.into(), user_def, width, self.mut_ptr()) };
+        } else {
+            unsafe { ege_setlinestyle(style.into(), 0, width, self.mut_ptr()) };
+        }
+    }
+
+    fn getcolor(&self) -> Color {
+        let argb = unsafe { ege_getcolor(self.const_ptr()) };
+        Color::from_argb(argb)
+    }
+
+    fn gettextcolor(&self) -> Color {
+        let argb = unsafe { ege_gettextcolor(self.const_ptr()) };
+        Color::from_argb(argb)
+    }
+
+    fn settextcolor(&mut self, color: ARGB) {
+        unsafe { ege_settextcolor(color, self.mut_ptr()) };
+    }
+
+    fn setlinewidth(&mut self, width: f32) {
+        unsafe { ege_setlinewidth(width, self.mut_ptr()) };
+    }
+
+    fn getfillcolor(&self) -> Color {
+        let argb = unsafe { ege_getfillcolor(self.const_ptr()) };
+        Color::from_argb(argb)
+    }
+
+    fn getlinestyle(&self) -> (LineStyle, i32) {
+        let (mut linestyle, mut pattern, mut thickness) = (0i32, 0u16, 0i32);
+        unsafe {
+            ege_getlinestyle(
+                &mut linestyle as _,
+                &mut pattern as _,
+                &mut thickness as _,
+                self.const_ptr(),
+            );
+        }
+        let style = if linestyle == ege_line_styles_USERBIT_LINE {
+            LineStyle::UserDef(pattern)
+        } else {
+            LineStyle::try_from(linestyle).unwrap()
+        };
+        (style, thickness)
+    }
+
+    fn getwidth(&self) -> u32 {
+        unsafe { ege_getwidth(self.const_ptr()) as _ }
+    }
+
+    fn getheight(&self) -> u32 {
+        unsafe { ege_getheight(self.const_ptr()) as _ }
+    }
+
+    fn getx(&self) -> i32 {
+        unsafe { ege_getx(self.const_ptr()) }
+    }
+
+    fn gety(&self) -> i32 {
+        unsafe { ege_gety(self.const_ptr()) }
+    }
+
+    fn moverel(&mut self, dx: i32, dy: i32) {
+        unsafe { ege_moverel(dx, dy, self.mut_ptr()) };
+    }
+
+    fn moveto(&mut self, x: i32, y: i32) {
+        unsafe { ege_moveto(x, y, self.mut_ptr()) };
+    }
+
+    fn getbuffer(&self) -> &[ARGB] {
+        let ptr = unsafe { ege_getbuffer(self.const_ptr() as _) };
+        let width = self.getwidth();
+        let height = self.getheight();
+        unsafe { std::slice::from_raw_parts(ptr, width as usize * height as usize) }
+    }
+
+    fn getbuffer_mut(&mut self) -> &mut [ARGB] {
+        let ptr = unsafe { ege_getbuffer(self.mut_ptr()) };
+        let width = self.getwidth();
+        let height = self.getheight();
+        unsafe { std::slice::from_raw_parts_mut(ptr, width as usize * height as usize) }
+    }
+
+    fn getpixel(&self, x: i32, y: i32) -> Color {
+        let argb = unsafe { ege_getpixel(x, y, self.const_ptr()) };
+        Color::from_argb(argb)
+    }
+
+    fn putpixel(&mut self, x: i32, y: i32, color: ARGB) {
+        unsafe { ege_putpixel(x, y, color, self.mut_ptr()) };
+    }
+
+    fn putpixel_savealpha(&mut self, x: i32, y: i32, color: ARGB) {
+        unsafe { ege_putpixel_savealpha(x, y, color, self.mut_ptr()) };
+    }
+
+    fn putpixel_withalpha(&mut self, x: i32, y: i32, color: ARGB) {
+        unsafe { ege_putpixel_withalpha(x, y, color, self.mut_ptr()) };
+    }
+
+    fn putpixel_alphablend(&mut self, x: i32, y: i32, color: ARGB, factor: u8) {
+        unsafe { ege_putpixel_alphablend1(x, y, color, factor, self.mut_ptr()) };
+    }
+
+    fn putpixels(&mut self, array: &[PixelPoint<ARGB>]) {
+        let array = array
+            .iter()
+            .flat_map(|&PixelPoint { x, y, color }| {
+                let argb = unsafe { *(&color as *const u32 as *const i32) };
+                [x, y, argb]
+            })
+            .collect::<Vec<_>>();
+        unsafe { ege_putpixels(array.len() as _, array.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn arc(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
+        unsafe { ege_arc(x, y, start, end, radius, self.mut_ptr()) };
+    }
+
+    fn arcf(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
+        unsafe { ege_arcf(x, y, start, end, radius, self.mut_ptr()) };
+    }
+
+    fn ellipse(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        unsafe { ege_ellipse(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn ellipsef(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        unsafe { ege_ellipsef(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        unsafe { ege_line(x1, y1, x2, y2, self.mut_ptr()) };
+    }
+
+    fn linerel(&mut self, dx: i32, dy: i32) {
+        unsafe { ege_linerel(dx, dy, self.mut_ptr()) };
+    }
+
+    fn lineto(&mut self, x: i32, y: i32) {
+        unsafe { ege_lineto(x, y, self.mut_ptr()) };
+    }
+
+    fn drawlines(&mut self, lines: &[Line]) {
+        let lines = lines
+            .iter()
+            .flat_map(|&Line { x1, y1, x2, y2 }| [x1, y1, x2, y2])
+            .collect::<Vec<_>>();
+        unsafe { ege_drawlines(lines.len() as _, lines.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn polyline(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 2);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_polyline(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn drawpoly(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 2);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_drawpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn drawbezier(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 4 && points.len() % 3 == 1);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_drawbezier(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn rectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        unsafe { ege_rectangle(left, top, right, bottom, self.mut_ptr()) };
+    }
+
+    fn fillrect(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        unsafe { ege_fillrect(left, top, right, bottom, self.mut_ptr()) };
+    }
+
+    fn solidrectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        unsafe { ege_solidrect(left, top, right, bottom, self.mut_ptr()) };
+    }
+
+    fn bar(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        unsafe { ege_bar(left, top, right, bottom, self.mut_ptr()) };
+    }
+
+    fn polygon(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 3);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_polygon(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn fillpoly(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 3);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_fillpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn solidpoly(&mut self, points: &[Point]) {
+        #[cfg(debug_assertions)]
+        assert!(points.len() >= 3);
+        let points = points
+            .iter()
+            .flat_map(|&Point { x, y }| [x, y])
+            .collect::<Vec<_>>();
+        unsafe { ege_solidpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+    }
+
+    fn circle(&mut self, x: i32, y: i32, radius: i32) {
+        unsafe { ege_circle(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn circlef(&mut self, x: f32, y: f32, radius: f32) {
+        unsafe { ege_circlef(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn fillcircle(&mut self, x: i32, y: i32, radius: i32) {
+        unsafe { ege_fillcircle(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn fillcirclef(&mut self, x: f32, y: f32, radius: f32) {
+        unsafe { ege_fillcirclef(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn fillellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
+        unsafe { ege_fillellipse(x, y, rx, ry, self.mut_ptr()) };
+    }
+
+    fn fillellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
+        unsafe { ege_fillellipsef(x, y, rx, ry, self.mut_ptr()) };
+    }
+
+    fn solidcircle(&mut self, x: i32, y: i32, radius: i32) {
+        unsafe { ege_solidcircle(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn solidcirclef(&mut self, x: f32, y: f32, radius: f32) {
+        unsafe { ege_solidcirclef(x, y, radius, self.mut_ptr()) };
+    }
+
+    fn solidellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
+        unsafe { ege_solidellipse(x, y, rx, ry, self.mut_ptr()) };
+    }
+
+    fn solidellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
+        unsafe { ege_solidellipsef(x, y, rx, ry, self.mut_ptr()) };
+    }
+
+    fn pie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        unsafe { ege_pie(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn pief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        unsafe { ege_pief(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn fillpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        unsafe { ege_fillpie(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn fillpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        unsafe { ege_fillpief(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn solidpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        unsafe { ege_solidpie(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn solidpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        unsafe { ege_solidpief(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn sector(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        unsafe { ege_sector(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn sectorf(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        unsafe { ege_sectorf(x, y, start, end, rx, ry, self.mut_ptr()) };
+    }
+
+    fn pieslice(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
+        unsafe { ege_pieslice(x, y, start, end, radius, self.mut_ptr()) };
+    }
+
+    fn pieslicef(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
+        unsafe { ege_pieslicef(x, y, start, end, radius, self.mut_ptr()) };
+    }
+
+    fn floodfill(&mut self, x: i32, y: i32, color: ARGB) {
+        let color = unsafe { *(&color as *const _ as *const i32) };
+        unsafe { ege_floodfill(x, y, color, self.mut_ptr()) };
+    }
+
+    fn floodfillsurface(&mut self, x: i32, y: i32, color: ARGB) {
+        unsafe { ege_floodfillsurface(x, y, color, self.mut_ptr()) };
+    }
+}
+
+/// The part of the event loop of `XEGE` that depends on its device.
+pub trait EventSource {
+    /// Checks if the environment is running.
+    fn is_run(&self) -> bool;
+
+    /// Checks if the device is not a window.
+    fn is_headless(&self) -> bool;
+
+    /// Queue the events of the device since the last call.
+    fn poll(&mut self, events: &mut VecDeque<Event>);
+
+    /// Yield the CPU for a while.
+    fn idle(&self, duration: Duration);
+
+    /// Show what was drawn in a tick of `XEGE::run`.
+    fn present(&mut self);
+}
@@ -0,0 +1,639 @@
+use crate::color::IntoARGB;
+use crate::mat::IntoEGEMatrix;
+use crate::{ARGB, GraphicsEnvironment, ImageViewMut, PathFillMode, Point};
+
+/// How a gradient continues outside of its `0.0..=1.0` range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpreadMode {
+    /// Extend the colors of the first and last stop.
+    #[default]
+    Pad,
+    /// Repeat the gradient.
+    Repeat,
+    /// Repeat the gradient, mirroring every other repetition.
+    Reflect,
+}
+
+impl SpreadMode {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Pad => t.clamp(0.0, 1.0),
+            Self::Repeat => t - t.floor(),
+            Self::Reflect => {
+                let t = t.rem_euclid(2.0);
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        }
+    }
+}
+
+/// A color at a position of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// The position of the stop, from `0.0` to `1.0`.
+    pub offset: f32,
+    /// The color of the stop.
+    pub color: ARGB,
+}
+
+impl ColorStop {
+    pub fn new(offset: f32, color: impl IntoARGB) -> Self {
+        Self {
+            offset,
+            color: color.into_argb(),
+        }
+    }
+}
+
+/// The color stops of a gradient.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gradient {
+    /// The color stops, sorted by their offsets.
+    pub stops: Vec<ColorStop>,
+    /// How the gradient continues outside of its range.
+    pub spread: SpreadMode,
+}
+
+impl Gradient {
+    /// Create a gradient.
+    ///
+    /// # Parameters
+    /// - `stops`: The color stops, in any order.
+    /// - `spread`: How the gradient continues outside of its range.
+    ///
+    /// # Note
+    /// Stops with the same offset make a hard transition between their colors.
+    pub fn new(stops: &[ColorStop], spread: SpreadMode) -> Self {
+        let mut stops = stops.to_vec();
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        Self { stops, spread }
+    }
+
+    /// Create a gradient from one color to another.
+    pub fn two_color(from: impl IntoARGB, to: impl IntoARGB) -> Self {
+        Self::new(
+            &[ColorStop::new(0.0, from), ColorStop::new(1.0, to)],
+            SpreadMode::Pad,
+        )
+    }
+
+    /// The premultiplied color at the position `t` inside of `0.0..=1.0`.
+    fn color_at(&self, t: f32) -> [f32; 4] {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0.0; 4],
+        };
+        if t <= first.offset {
+            return premultiply(first.color);
+        }
+        if t >= last.offset {
+            return premultiply(last.color);
+        }
+        let i = self.stops.partition_point(|stop| stop.offset <= t);
+        let (a, b) = (self.stops[i - 1], self.stops[i]);
+        let f = (t - a.offset) / (b.offset - a.offset);
+        let (a, b) = (premultiply(a.color), premultiply(b.color));
+        std::array::from_fn(|i| a[i] + (b[i] - a[i]) * f)
+    }
+
+    /// A lookup table of the colors of the gradient.
+    fn table(&self) -> Vec<[f32; 4]> {
+        (0..LUT_SIZE)
+            .map(|i| self.color_at(i as f32 / (LUT_SIZE - 1) as f32))
+            .collect()
+    }
+}
+
+const LUT_SIZE: usize = 256;
+
+/// An image repeated over the plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    width: u32,
+    height: u32,
+    pixels: Vec<ARGB>,
+    /// The inverse of the pattern transform, `None` if it is singular.
+    inverse: Option<[f32; 6]>,
+}
+
+impl Pattern {
+    /// Create a pattern from the pixels of an image.
+    ///
+    /// # Parameters
+    /// - `image`: The image to repeat, e.g. an `Image`. Its pixels are copied.
+    /// - `transform`: The transform from the image space to the space of the filled shapes.
+    ///
+    /// # Note
+    /// Pixels are sampled with the nearest neighbour. A singular transform makes
+    /// the pattern transparent.
+    pub fn new(image: &impl GraphicsEnvironment, transform: impl IntoEGEMatrix) -> Self {
+        Self {
+            width: image.getwidth(),
+            height: image.getheight(),
+            pixels: image.getbuffer().to_vec(),
+            inverse: invert(transform.to_affine()),
+        }
+    }
+
+    fn sample(&self, x: f32, y: f32) -> [f32; 4] {
+        match self.inverse {
+            Some([a, b, c, d, e, f]) if self.width > 0 && self.height > 0 => {
+                let u = (a * x + c * y + e).floor() as i64;
+                let v = (b * x + d * y + f).floor() as i64;
+                let u = u.rem_euclid(self.width as i64) as usize;
+                let v = v.rem_euclid(self.height as i64) as usize;
+                premultiply(self.pixels[v * self.width as usize + u])
+            }
+            _ => [0.0; 4],
+        }
+    }
+}
+
+/// The paint used to fill shapes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Brush {
+    /// A single color.
+    Solid(ARGB),
+    /// Colors along the line from `start` to `end`.
+    LinearGradient {
+        start: Point<f32>,
+        end: Point<f32>,
+        gradient: Gradient,
+    },
+    /// Colors along the distance to `center`, reaching `1.0` at `radius`.
+    RadialGradient {
+        center: Point<f32>,
+        radius: f32,
+        gradient: Gradient,
+    },
+    /// Colors around `center`, starting at `angle` degrees and turning clockwise.
+    ConicGradient {
+        center: Point<f32>,
+        angle: f32,
+        gradient: Gradient,
+    },
+    /// A repeated image.
+    Pattern(Pattern),
+}
+
+impl Brush {
+    /// Create a brush of a single color.
+    pub fn solid(color: impl IntoARGB) -> Self {
+        Self::Solid(color.into_argb())
+    }
+
+    /// Create a linear gradient brush.
+    ///
+    /// # Parameters
+    /// - `start`: The point of the offset `0.0`.
+    /// - `end`: The point of the offset `1.0`.
+    /// - `gradient`: The color stops and the spread mode.
+    pub fn linear(start: Point<f32>, end: Point<f32>, gradient: Gradient) -> Self {
+        Self::LinearGradient {
+            start,
+            end,
+            gradient,
+        }
+    }
+
+    /// Create a radial gradient brush.
+    ///
+    /// # Parameters
+    /// - `center`: The point of the offset `0.0`.
+    /// - `radius`: The distance of the offset `1.0`.
+    /// - `gradient`: The color stops and the spread mode.
+    pub fn radial(center: Point<f32>, radius: f32, gradient: Gradient) -> Self {
+        Self::RadialGradient {
+            center,
+            radius,
+            gradient,
+        }
+    }
+
+    /// Create a conic gradient brush.
+    ///
+    /// # Parameters
+    /// - `center`: The center of the gradient.
+    /// - `angle`: The angle of the offset `0.0` in degrees, `0.0` points to the right.
+    /// - `gradient`: The color stops and the spread mode.
+    ///
+    /// # Note
+    /// The offset grows clockwise on screen and reaches `1.0` after a full turn.
+    pub fn conic(center: Point<f32>, angle: f32, gradient: Gradient) -> Self {
+        Self::ConicGradient {
+            center,
+            angle,
+            gradient,
+        }
+    }
+
+    /// Create an image pattern brush.
+    ///
+    /// # Note
+    /// See `Pattern::new`.
+    pub fn pattern(image: &impl GraphicsEnvironment, transform: impl IntoEGEMatrix) -> Self {
+        Self::Pattern(Pattern::new(image, transform))
+    }
+}
+
+/// A brush prepared for sampling.
+enum Shader<'a> {
+    Solid([f32; 4]),
+    Gradient {
+        table: Vec<[f32; 4]>,
+        spread: SpreadMode,
+        offset: Box<dyn Fn(f32, f32) -> f32 + 'a>,
+    },
+    Pattern(&'a Pattern),
+}
+
+impl<'a> Shader<'a> {
+    fn new(brush: &'a Brush) -> Self {
+        let gradient =
+            |gradient: &Gradient, offset: Box<dyn Fn(f32, f32) -> f32 + 'a>| Shader::Gradient {
+                table: gradient.table(),
+                spread: gradient.spread,
+                offset,
+            };
+        match brush {
+            Brush::Solid(color) => Shader::Solid(premultiply(*color)),
+            Brush::LinearGradient {
+                start,
+                end,
+                gradient: g,
+            } => {
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                let len2 = dx * dx + dy * dy;
+                let (sx, sy) = (start.x, start.y);
+                gradient(
+                    g,
+                    Box::new(move |x, y| match len2 > 0.0 {
+                        true => ((x - sx) * dx + (y - sy) * dy) / len2,
+                        false => 0.0,
+                    }),
+                )
+            }
+            Brush::RadialGradient {
+                center,
+                radius,
+                gradient: g,
+            } => {
+                let (cx, cy, r) = (center.x, center.y, *radius);
+                gradient(
+                    g,
+                    Box::new(move |x, y| match r > 0.0 {
+                        true => (x - cx).hypot(y - cy) / r,
+                        false => 1.0,
+                    }),
+                )
+            }
+            Brush::ConicGradient {
+                center,
+                angle,
+                gradient: g,
+            } => {
+                let (cx, cy, angle) = (center.x, center.y, *angle);
+                gradient(
+                    g,
+                    Box::new(move |x, y| {
+                        ((y - cy).atan2(x - cx).to_degrees() - angle).rem_euclid(360.0) / 360.0
+                    }),
+                )
+            }
+            Brush::Pattern(pattern) => Shader::Pattern(pattern),
+        }
+    }
+
+    fn sample(&self, x: f32, y: f32) -> [f32; 4] {
+        match self {
+            Shader::Solid(color) => *color,
+            Shader::Gradient {
+                table,
+                spread,
+                offset,
+            } => {
+                let t = spread.apply(offset(x, y));
+                table[(t * (LUT_SIZE - 1) as f32).round() as usize]
+            }
+            Shader::Pattern(pattern) => pattern.sample(x, y),
+        }
+    }
+}
+
+/// Invert an affine transform `[m11, m12, m21, m22, m31, m32]`.
+///
+/// # Returns
+/// The inverse, `None` if the transform is singular.
+fn invert([m11, m12, m21, m22, m31, m32]: [f32; 6]) -> Option<[f32; 6]> {
+    let det = m11 * m22 - m12 * m21;
+    (det.abs() > f32::EPSILON).then(|| {
+        let (a, b) = (m22 / det, -m12 / det);
+        let (c, d) = (-m21 / det, m11 / det);
+        [a, b, c, d, -(a * m31 + c * m32), -(b * m31 + d * m32)]
+    })
+}
+
+/// Convert an ARGB color to premultiplied `[r, g, b, a]` in `0.0..=1.0`.
+fn premultiply(color: ARGB) -> [f32; 4] {
+    let [b, g, r, a] = color.to_le_bytes().map(|c| c as f32 / 255.0);
+    [r * a, g * a, b * a, a]
+}
+
+/// Blend a premultiplied color over an ARGB pixel.
+pub(crate) fn blend(dst: ARGB, src: [f32; 4]) -> ARGB {
+    let [db, dg, dr, da] = dst.to_le_bytes().map(|c| c as f32 / 255.0);
+    let rest = da * (1.0 - src[3]);
+    let alpha = src[3] + rest;
+    if alpha <= 0.0 {
+        return 0;
+    }
+    let channel = |s: f32, d: f32| (((s + d * rest) / alpha).clamp(0.0, 1.0) * 255.0).round() as u8;
+    u32::from_le_bytes([
+        channel(src[2], db),
+        channel(src[1], dg),
+        channel(src[0], dr),
+        (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+    ])
+}
+
+/// Number of sub-scanlines sampled per pixel row.
+const SUBSAMPLES: usize = 4;
+
+/// Fill the area of closed polygons into a pixel buffer.
+///
+/// # Parameters
+/// - `target`: The pixels to fill, nothing outside of them is touched.
+/// - `transform`: The transform from the space of the polygons and the brush
+///   to the pixels of `target`.
+///
+/// # Note
+/// The coverage of each pixel is computed exactly in the horizontal direction and
+/// with `SUBSAMPLES` sub-scanlines in the vertical direction.
+#[cfg_attr(not(feature = "ege"), allow(dead_code))]
+pub(crate) fn fill(
+    target: &mut ImageViewMut,
+    polygons: &[Vec<Point<f32>>],
+    mode: PathFillMode,
+    brush: &Brush,
+    transform: [f32; 6],
+) {
+    let Some([a, b, c, d, e, f]) = invert(transform) else {
+        return;
+    };
+    let [m11, m12, m21, m22, m31, m32] = transform;
+    let (width, height) = (target.width() as usize, target.height() as usize);
+    let polygons = polygons
+        .iter()
+        .map(|polygon| {
+            let map = |p: &Point<f32>| Point {
+                x: m11 * p.x + m21 * p.y + m31,
+                y: m12 * p.x + m22 * p.y + m32,
+            };
+            polygon.iter().map(map).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let edges = polygons
+        .iter()
+        .filter(|polygon| polygon.len() >= 3)
+        .flat_map(|polygon| {
+            (0..polygon.len()).map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+        })
+        .filter(|(a, b)| a.y != b.y)
+        .collect::<Vec<_>>();
+    let Some((top, bottom)) = edges
+        .iter()
+        .fold(None, |range: Option<(f32, f32)>, (a, b)| {
+            let (lo, hi) = (a.y.min(b.y), a.y.max(b.y));
+            Some(range.map_or((lo, hi), |(t, b)| (t.min(lo), b.max(hi))))
+        })
+    else {
+        return;
+    };
+    let top = (top.floor().max(0.0) as usize).min(height);
+    let bottom = (bottom.ceil().max(0.0) as usize).min(height);
+
+    let shader = Shader::new(brush);
+    let mut coverage = vec![0.0f32; width + 1];
+    let mut crossings = vec![];
+    let weight = 1.0 / SUBSAMPLES as f32;
+    for row in top..bottom {
+        coverage.fill(0.0);
+        for sub in 0..SUBSAMPLES {
+            let y = row as f32 + (sub as f32 + 0.5) * weight;
+            crossings.clear();
+            for &(a, b) in &edges {
+                let (lo, hi, dir) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+                if lo.y <= y && y < hi.y {
+                    let x = lo.x + (y - lo.y) / (hi.y - lo.y) * (hi.x - lo.x);
+                    crossings.push((x, dir));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match mode {
+                    PathFillMode::Winding => winding != 0,
+                    PathFillMode::Default | PathFillMode::Alternate => winding % 2 != 0,
+                };
+                if inside {
+                    add_span(&mut coverage, pair[0].0, pair[1].0, weight);
+                }
+            }
+        }
+        let Some(line) = target.row_mut(row as u32) else {
+            break;
+        };
+        for (x, pixel) in line.iter_mut().enumerate() {
+            let cover = coverage[x].min(1.0);
+            if cover <= 0.0 {
+                continue;
+            }
+            let (px, py) = (x as f32 + 0.5, row as f32 + 0.5);
+            let color = shader.sample(a * px + c * py + e, b * px + d * py + f);
+            *pixel = blend(*pixel, color.map(|c| c * cover));
+        }
+    }
+}
+
+/// Add the coverage of the span from `x0` to `x1` to the cells it crosses.
+fn add_span(coverage: &mut [f32], x0: f32, x1: f32, weight: f32) {
+    let width = (coverage.len() - 1) as f32;
+    let (x0, x1) = (x0.clamp(0.0, width), x1.clamp(0.0, width));
+    if x1 <= x0 {
+        return;
+    }
+    let (first, last) = (x0.floor() as usize, x1.floor() as usize);
+    if first == last {
+        coverage[first] += (x1 - x0) * weight;
+        return;
+    }
+    coverage[first] += (first as f32 + 1.0 - x0) * weight;
+    for cell in &mut coverage[first + 1..last] {
+        *cell += weight;
+    }
+    coverage[last] += (x1 - last as f32) * weight;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+    fn p(x: f32, y: f32) -> Point<f32> {
+        Point { x, y }
+    }
+
+    #[test]
+    fn gradient_stops() {
+        let gradient = Gradient::new(
+            &[
+                ColorStop::new(1.0, 0xFFFFFFFFu32),
+                ColorStop::new(0.0, 0xFF000000u32),
+                ColorStop::new(0.5, 0xFFFF0000u32),
+            ],
+            SpreadMode::Pad,
+        );
+        let offsets = gradient
+            .stops
+            .iter()
+            .map(|stop| stop.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [0.0, 0.5, 1.0]);
+        assert_eq!(gradient.color_at(-1.0), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.color_at(0.25), [0.5, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.color_at(0.75), [1.0, 0.5, 0.5, 1.0]);
+        assert_eq!(gradient.color_at(2.0), [1.0; 4]);
+
+        // Stops at the same offset make a hard transition.
+        let hard = Gradient::new(
+            &[
+                ColorStop::new(0.0, 0xFF000000u32),
+                ColorStop::new(0.5, 0xFF000000u32),
+                ColorStop::new(0.5, 0xFFFFFFFFu32),
+                ColorStop::new(1.0, 0xFFFFFFFFu32),
+            ],
+            SpreadMode::Pad,
+        );
+        assert_eq!(hard.color_at(0.49), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(hard.color_at(0.5), [1.0; 4]);
+        assert_eq!(Gradient::default().color_at(0.5), [0.0; 4]);
+    }
+
+    #[test]
+    fn spread_modes() {
+        let cases = [
+            (SpreadMode::Pad, [(-0.5, 0.0), (0.25, 0.25), (1.5, 1.0)]),
+            (
+                SpreadMode::Repeat,
+                [(-0.25, 0.75), (0.25, 0.25), (1.25, 0.25)],
+            ),
+            (
+                SpreadMode::Reflect,
+                [(-0.25, 0.25), (1.25, 0.75), (2.25, 0.25)],
+            ),
+        ];
+        for (spread, values) in cases {
+            for (t, expected) in values {
+                assert_eq!(spread.apply(t), expected, "{spread:?} at {t}");
+            }
+        }
+
+        // The linear gradient over the first two pixels continues to the others.
+        let fill_row = |spread| {
+            let gradient = Gradient::new(
+                &[
+                    ColorStop::new(0.0, 0xFF000000u32),
+                    ColorStop::new(1.0, 0xFFFFFFFFu32),
+                ],
+                spread,
+            );
+            let brush = Brush::linear(p(0.0, 0.0), p(2.0, 0.0), gradient);
+            let mut pixels = vec![0; 4];
+            let square = vec![p(0.0, 0.0), p(4.0, 0.0), p(4.0, 1.0), p(0.0, 1.0)];
+            let mut target = ImageViewMut::new(&mut pixels, 4, 4, 1);
+            fill(
+                &mut target,
+                &[square],
+                PathFillMode::Winding,
+                &brush,
+                IDENTITY,
+            );
+            pixels
+        };
+        let pad = fill_row(SpreadMode::Pad);
+        assert_eq!((pad[2], pad[3]), (0xFFFFFFFF, 0xFFFFFFFF));
+        let repeat = fill_row(SpreadMode::Repeat);
+        assert_eq!((repeat[2], repeat[3]), (repeat[0], repeat[1]));
+        let reflect = fill_row(SpreadMode::Reflect);
+        assert_eq!((reflect[2], reflect[3]), (reflect[1], reflect[0]));
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn pattern_tiles_under_translation() {
+        use crate::Canvas;
+        use crate::mat::Mat3;
+
+        let colors = [0xFFFF0000, 0xFF00FF00, 0xFF0000FF, 0xFFFFFFFF];
+        let mut image = Canvas::new(2, 2);
+        image.getbuffer_mut().copy_from_slice(&colors);
+        let translate = Mat3::from([[1.0, 0.0, 1.0], [0.0, 1.0, 3.0], [0.0, 0.0, 1.0]]);
+        let pattern = Pattern::new(&image, translate);
+        // The pixel (0, 0) of the image lands on (1, 3), the others repeat around it.
+        assert_eq!(pattern.sample(1.5, 3.5), premultiply(colors[0]));
+        assert_eq!(pattern.sample(0.5, 3.5), premultiply(colors[1]));
+        assert_eq!(pattern.sample(1.5, 0.5), premultiply(colors[2]));
+        assert_eq!(pattern.sample(4.5, 6.5), premultiply(colors[3]));
+
+        let singular = Mat3::from([[0.0, 0.0, 1.0], [0.0, 0.0, 3.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(Pattern::new(&image, singular).sample(1.5, 3.5), [0.0; 4]);
+    }
+
+    #[test]
+    fn fill_edge_coverage() {
+        // Half of the first column and of the last one, half of the first row.
+        let mut pixels = vec![0; 12];
+        let shape = vec![p(0.5, 0.5), p(2.5, 0.5), p(2.5, 3.0), p(0.5, 3.0)];
+        let mut target = ImageViewMut::new(&mut pixels, 3, 3, 4);
+        fill(
+            &mut target,
+            &[shape],
+            PathFillMode::Winding,
+            &Brush::solid(0xFFFFFFFFu32),
+            IDENTITY,
+        );
+        assert_eq!(
+            pixels,
+            [
+                0x40FFFFFF, 0x80FFFFFF, 0x40FFFFFF, //
+                0x80FFFFFF, 0xFFFFFFFF, 0x80FFFFFF, //
+                0x80FFFFFF, 0xFFFFFFFF, 0x80FFFFFF, //
+                0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn fill_modes() {
+        // A square inside of another one, both clockwise.
+        let outer = vec![p(0.0, 0.0), p(3.0, 0.0), p(3.0, 3.0), p(0.0, 3.0)];
+        let inner = vec![p(1.0, 1.0), p(2.0, 1.0), p(2.0, 2.0), p(1.0, 2.0)];
+        let center = |mode| {
+            let mut pixels = vec![0; 9];
+            let mut target = ImageViewMut::new(&mut pixels, 3, 3, 3);
+            let polygons = [outer.clone(), inner.clone()];
+            fill(
+                &mut target,
+                &polygons,
+                mode,
+                &Brush::solid(0xFFFFFFFFu32),
+                IDENTITY,
+            );
+            (pixels[0], pixels[4])
+        };
+        assert_eq!(center(PathFillMode::Winding), (0xFFFFFFFF, 0xFFFFFFFF));
+        assert_eq!(center(PathFillMode::Alternate), (0xFFFFFFFF, 0));
+    }
+}
@@ -0,0 +1,1059 @@
+use std::f64::consts::PI;
+
+use crate::backend::Backend;
+use crate::image::argb_to_rgba;
+use crate::{
+    ARGB, Color, DrawableDevice, FillPattern, ImageError, Line, LineStyle, PixelPoint, Point,
+};
+
+/// Software canvas.
+///
+/// A framebuffer of ARGB pixels owned by Rust and rasterized without EGE,
+/// so drawing code can be built and tested on platforms where the C++ library
+/// is not available. The canvas implements `DrawableDevice`, which makes the
+/// `GraphicsEnvironment` and `Draw` methods work on it like on any other device.
+///
+/// # Note
+/// Only the following operations are rasterized in software:
+/// * colors, fill style, line style and line width of `GraphicsEnvironment`,
+///   the current position and the pixel buffer;
+/// * pixels, lines, polylines, bezier curves, rectangles, polygons, circles,
+///   ellipses, arcs, pies, sectors and flood fills of `Draw`.
+///
+/// Fill patterns other than `FillPattern::Empty` are filled solid. Operations that
+/// require EGE (text, fonts, ROP2, `HighDraw` and `ImageDraw`) are only available
+/// on an `EgeDevice`, not on a canvas.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    buffer: Vec<ARGB>,
+    linecolor: ARGB,
+    textcolor: ARGB,
+    fillcolor: ARGB,
+    bkcolor: ARGB,
+    fillstyle: FillPattern,
+    linestyle: LineStyle,
+    thickness: i32,
+    x: i32,
+    y: i32,
+}
+
+impl Canvas {
+    /// Create a new canvas.
+    ///
+    /// # Parameters
+    /// - `width`: The width of the canvas.
+    /// - `height`: The height of the canvas.
+    ///
+    /// # Returns
+    /// A new `Canvas` object with all pixels set to `0x00000000`, like a new `Image`.
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        Self {
+            width,
+            height,
+            buffer: vec![0; width as usize * height as usize],
+            linecolor: 0xFFD3D3D3,
+            textcolor: 0xFFD3D3D3,
+            fillcolor: 0xFF000000,
+            bkcolor: 0xFF000000,
+            fillstyle: FillPattern::Solid,
+            linestyle: LineStyle::Solid,
+            thickness: 1,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Save the canvas to a file.
+    ///
+    /// # Parameters
+    /// - `path`: The filename of the image. The format is deduced from the extension.
+    /// - `with_alpha`: Whether to save the alpha channel.
+    ///
+    /// # Note
+    /// Unlike `Image::save`, any format supported by the `image` crate can be used.
+    pub fn save<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        with_alpha: bool,
+    ) -> Result<(), ImageError> {
+        let path = path.as_ref();
+        let image = argb_to_rgba(&self.buffer, self.width, self.height);
+        let result = if with_alpha {
+            image.save(path)
+        } else {
+            image::DynamicImage::ImageRgba8(image).to_rgb8().save(path)
+        };
+        result.map_err(|e| ImageError::from(e).with_path(path))
+    }
+}
+
+impl DrawableDevice for Canvas {
+    fn backend(&self) -> &dyn Backend {
+        self
+    }
+
+    fn backend_mut(&mut self) -> &mut dyn Backend {
+        self
+    }
+}
+
+/// Blend `src` over `dst` with `alpha`, keeping the alpha of `dst`.
+fn colorblend(dst: ARGB, src: ARGB, alpha: u32) -> ARGB {
+    let channel = |shift: u32| {
+        let d = (dst >> shift) & 0xFF;
+        let s = (src >> shift) & 0xFF;
+        ((255 * d + s * alpha + 127 - d * alpha) / 255) << shift
+    };
+    (dst & 0xFF000000) | channel(16) | channel(8) | channel(0)
+}
+
+/// Blend `src` over `dst` with `alpha`, including the alpha channel.
+fn alphablend(dst: ARGB, src: ARGB, alpha: u32) -> ARGB {
+    let a = dst >> 24;
+    let a = (255 * a + (255 - a) * alpha + 127) / 255;
+    (a << 24) | (colorblend(dst, src, alpha) & 0x00FFFFFF)
+}
+
+/// Whether the angle `angle` lies on the counterclockwise sweep from `start`.
+fn in_sweep(angle: f64, start: f64, sweep: f64) -> bool {
+    (angle - start).rem_euclid(2.0 * PI) <= sweep
+}
+
+/// Round a floating point coordinate to the nearest pixel.
+fn round(v: f32) -> i32 {
+    v.round() as i32
+}
+
+/// Convert a pair of angles in degrees into a start angle and a sweep in radians.
+fn sweep_of(start: f64, end: f64) -> (f64, f64) {
+    let start = start.to_radians().rem_euclid(2.0 * PI);
+    let sweep = (end.to_radians() - start).rem_euclid(2.0 * PI);
+    (start, if sweep == 0.0 { 2.0 * PI } else { sweep })
+}
+
+// Pixels.
+impl Canvas {
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            None
+        } else {
+            Some((y as u32 * self.width + x as u32) as usize)
+        }
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> ARGB {
+        self.index(x, y).map_or(0, |i| self.buffer[i])
+    }
+
+    fn set(&mut self, x: i32, y: i32, color: ARGB) {
+        if let Some(i) = self.index(x, y) {
+            self.buffer[i] = color;
+        }
+    }
+
+    fn hline(&mut self, x1: i32, x2: i32, y: i32, color: ARGB) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+        let x1 = x1.max(0);
+        let x2 = x2.min(self.width as i32 - 1);
+        if x1 > x2 {
+            return;
+        }
+        let row = (y as u32 * self.width) as usize;
+        self.buffer[row + x1 as usize..=row + x2 as usize].fill(color);
+    }
+}
+
+// Outlines.
+impl Canvas {
+    /// The bit pattern of the current line style.
+    fn pattern(&self) -> u16 {
+        match self.linestyle {
+            LineStyle::Solid => 0xFFFF,
+            LineStyle::Center => 0xFC78,
+            LineStyle::Dotted => 0xCCCC,
+            LineStyle::Dashed => 0xF8F8,
+            LineStyle::Null => 0x0000,
+            LineStyle::UserDef(bits) => bits,
+        }
+    }
+
+    /// Plot a point of an outline with the current pen.
+    fn plot(&mut self, x: i32, y: i32, step: &mut u32) {
+        let on = self.pattern() & (1 << (*step % 16)) != 0;
+        *step += 1;
+        if !on {
+            return;
+        }
+        let color = self.linecolor;
+        if self.thickness <= 1 {
+            self.set(x, y, color);
+            return;
+        }
+        // A round pen of diameter `thickness` centered on the point.
+        let lo = -(self.thickness / 2);
+        let hi = lo + self.thickness - 1;
+        let center = (lo + hi) as f64 / 2.0;
+        let r2 = (self.thickness as f64 / 2.0).powi(2);
+        for dy in lo..=hi {
+            let half = (r2 - (dy as f64 - center).powi(2)).max(0.0).sqrt();
+            let x1 = (center - half).ceil() as i32;
+            let x2 = (center + half).floor() as i32;
+            self.hline(x + x1, x + x2, y + dy, color);
+        }
+    }
+
+    /// Bresenham line from `(x1, y1)` to `(x2, y2)`, the end point is excluded like GDI.
+    fn segment(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, step: &mut u32) {
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (sx, sy) = (if x1 < x2 { 1 } else { -1 }, if y1 < y2 { 1 } else { -1 });
+        let (mut x, mut y, mut err) = (x1, y1, dx + dy);
+        while x != x2 || y != y2 {
+            self.plot(x, y, step);
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn path(&mut self, points: &[Point], closed: bool) {
+        let mut step = 0;
+        for pair in points.windows(2) {
+            self.segment(pair[0].x, pair[0].y, pair[1].x, pair[1].y, &mut step);
+        }
+        if let (true, Some(first), Some(last)) = (closed, points.first(), points.last()) {
+            self.segment(last.x, last.y, first.x, first.y, &mut step);
+        }
+    }
+}
+
+/// The pixels on the border of an ellipse, listed by quadrant symmetry.
+fn ellipse_points(rx: i64, ry: i64) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    let mut push = |x: i64, y: i64| {
+        for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+            points.push(((sx * x) as i32, (sy * y) as i32));
+        }
+    };
+    if rx == 0 || ry == 0 {
+        for x in 0..=rx {
+            push(x, 0);
+        }
+        for y in 0..=ry {
+            push(0, y);
+        }
+        return points;
+    }
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let (mut x, mut y) = (0, ry);
+    let mut d = 4 * ry2 - 4 * rx2 * ry + rx2;
+    while ry2 * x <= rx2 * y {
+        push(x, y);
+        if d >= 0 {
+            y -= 1;
+            d -= 8 * rx2 * y;
+        }
+        x += 1;
+        d += 4 * ry2 * (2 * x + 1);
+    }
+    let (mut x, mut y) = (rx, 0);
+    let mut d = 4 * rx2 - 4 * ry2 * rx + ry2;
+    while rx2 * y <= ry2 * x {
+        push(x, y);
+        if d >= 0 {
+            x -= 1;
+            d -= 8 * ry2 * x;
+        }
+        y += 1;
+        d += 4 * rx2 * (2 * y + 1);
+    }
+    points
+}
+
+/// The largest `n` in `lo..=hi` for which `pred` holds, `lo - 1` if there is none.
+///
+/// `pred` must hold up to some `n` and fail after it.
+fn last_where(lo: i64, hi: i64, pred: impl Fn(i64) -> bool) -> i64 {
+    let (mut lo, mut hi) = (lo, hi + 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo - 1
+}
+
+/// The half widths of the rows of the points of `ellipse_points`, computed for a
+/// single row without walking the whole border.
+struct EllipseRows {
+    rx: i64,
+    ry: i64,
+    /// The last column of the flat part of the border.
+    flat_end: i64,
+    /// The last row of the steep part of the border.
+    steep_end: i64,
+}
+
+impl EllipseRows {
+    fn new(rx: i64, ry: i64) -> Self {
+        let mut rows = Self {
+            rx,
+            ry,
+            flat_end: 0,
+            steep_end: 0,
+        };
+        if rx > 0 && ry > 0 {
+            let (rx2, ry2) = (rx as i128 * rx as i128, ry as i128 * ry as i128);
+            rows.flat_end = last_where(0, rx, |x| ry2 * x as i128 <= rx2 * rows.row_of(x) as i128);
+            rows.steep_end = last_where(0, ry, |y| {
+                rx2 * y as i128 <= ry2 * rows.column_of(y) as i128
+            });
+        }
+        rows
+    }
+
+    /// Whether the point `(x / 2, y / 2)` is inside of the ellipse.
+    fn inside(&self, x: i64, y: i64) -> bool {
+        let (rx2, ry2) = (
+            self.rx as i128 * self.rx as i128,
+            self.ry as i128 * self.ry as i128,
+        );
+        let (x, y) = (x as i128, y as i128);
+        ry2 * x * x < 4 * rx2 * ry2 - rx2 * y * y
+    }
+
+    /// The row of the column `x` in the flat part, chosen by its midpoint.
+    fn row_of(&self, x: i64) -> i64 {
+        last_where(0, self.ry, |y| self.inside(2 * x, 2 * y - 1))
+    }
+
+    /// The column of the row `y` in the steep part, chosen by its midpoint.
+    fn column_of(&self, y: i64) -> i64 {
+        last_where(0, self.rx, |x| self.inside(2 * x - 1, 2 * y))
+    }
+
+    /// The half width of the row `dy`, which must not be farther than `ry` from the center.
+    fn half_width(&self, dy: i64) -> i64 {
+        let y = dy.abs();
+        if self.rx == 0 || self.ry == 0 {
+            return if y == 0 { self.rx } else { 0 };
+        }
+        let steep = if y <= self.steep_end {
+            self.column_of(y)
+        } else {
+            -1
+        };
+        let flat = if y >= self.row_of(self.flat_end) {
+            last_where(0, self.flat_end, |x| self.row_of(x) >= y)
+        } else {
+            -1
+        };
+        steep.max(flat)
+    }
+}
+
+// Curves and filled shapes.
+impl Canvas {
+    /// The parametric angle of the offset `(dx, dy)` on an ellipse with radii `(rx, ry)`.
+    fn angle_of(dx: i32, dy: i32, rx: i32, ry: i32) -> f64 {
+        (-(dy as f64) * rx.max(1) as f64)
+            .atan2(dx as f64 * ry.max(1) as f64)
+            .rem_euclid(2.0 * PI)
+    }
+
+    /// The end point of the arc at the parametric angle `angle`.
+    fn arc_point(x: i32, y: i32, rx: i32, ry: i32, angle: f64) -> Point {
+        Point {
+            x: (x as f64 + rx as f64 * angle.cos()).round() as i32,
+            y: (y as f64 - ry as f64 * angle.sin()).round() as i32,
+        }
+    }
+
+    fn ellipse_arc(&mut self, x: i32, y: i32, rx: i32, ry: i32, start: f64, sweep: f64) {
+        let (rx, ry) = (rx.abs(), ry.abs());
+        // Walk the border counterclockwise from the start so that line styles stay continuous.
+        let mut points = ellipse_points(rx as i64, ry as i64)
+            .into_iter()
+            .map(|(dx, dy)| {
+                let offset = (Self::angle_of(dx, dy, rx, ry) - start).rem_euclid(2.0 * PI);
+                (offset, dx, dy)
+            })
+            .filter(|&(offset, _, _)| offset <= sweep)
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by_key(|&mut (_, dx, dy)| (dx, dy));
+        let mut step = 0;
+        for (_, dx, dy) in points {
+            self.plot(x + dx, y + dy, &mut step);
+        }
+    }
+
+    /// Fill an ellipse, optionally restricted to a sweep, with the current fill style.
+    fn fill_ellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32, sweep: Option<(f64, f64)>) {
+        if self.fillstyle == FillPattern::Empty {
+            return;
+        }
+        let (rx, ry) = (rx.abs(), ry.abs());
+        let rows = EllipseRows::new(rx as i64, ry as i64);
+        let (x, y) = (x as i64, y as i64);
+        let (width, height) = (self.width as i64, self.height as i64);
+        let color = self.fillcolor;
+        // Only the rows and columns on the canvas are visited.
+        for row in (y - ry as i64).max(0)..=(y + ry as i64).min(height - 1) {
+            let dy = row - y;
+            let half = rows.half_width(dy);
+            let (left, right) = ((x - half).max(0), (x + half).min(width - 1));
+            if left > right {
+                continue;
+            }
+            match sweep {
+                None => self.hline(left as i32, right as i32, row as i32, color),
+                Some((start, sweep)) => {
+                    for column in left..=right {
+                        let (dx, dy) = ((column - x) as i32, dy as i32);
+                        if (dx == 0 && dy == 0)
+                            || in_sweep(Self::angle_of(dx, dy, rx, ry), start, sweep)
+                        {
+                            self.set(column as i32, row as i32, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn pie_outline(&mut self, x: i32, y: i32, rx: i32, ry: i32, start: f64, sweep: f64) {
+        self.ellipse_arc(x, y, rx, ry, start, sweep);
+        if sweep < 2.0 * PI {
+            let a = Self::arc_point(x, y, rx, ry, start);
+            let b = Self::arc_point(x, y, rx, ry, start + sweep);
+            let mut step = 0;
+            self.segment(a.x, a.y, x, y, &mut step);
+            self.segment(x, y, b.x, b.y, &mut step);
+        }
+    }
+
+    /// Fill a polygon with the even-odd rule, sampling pixels at their integer coordinates.
+    fn fill_polygon(&mut self, points: &[Point]) {
+        if self.fillstyle == FillPattern::Empty || points.len() < 3 {
+            return;
+        }
+        let top = points.iter().map(|p| p.y).min().unwrap().max(0);
+        let bottom = points
+            .iter()
+            .map(|p| p.y)
+            .max()
+            .unwrap()
+            .min(self.height as i32);
+        let color = self.fillcolor;
+        let mut xs = Vec::new();
+        for y in top..bottom {
+            xs.clear();
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                let (lo, hi) = if a.y < b.y { (*a, b) } else { (b, *a) };
+                if lo.y <= y && y < hi.y {
+                    let t = (y - lo.y) as f64 / (hi.y - lo.y) as f64;
+                    xs.push(lo.x as f64 + t * (hi.x - lo.x) as f64);
+                }
+            }
+            xs.sort_by(f64::total_cmp);
+            for span in xs.chunks_exact(2) {
+                self.hline(span[0].ceil() as i32, span[1].ceil() as i32 - 1, y, color);
+            }
+        }
+    }
+}
+
+// Flood fill.
+impl Canvas {
+    /// Scanline flood fill of the 4-connected region of pixels accepted by `inside`.
+    fn flood(&mut self, x: i32, y: i32, inside: impl Fn(ARGB) -> bool) {
+        if self.fillstyle == FillPattern::Empty || self.index(x, y).is_none() {
+            return;
+        }
+        let (w, h) = (self.width as i32, self.height as i32);
+        let color = self.fillcolor;
+        let mut visited = vec![false; self.buffer.len()];
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let i = self.index(x, y).unwrap();
+            if visited[i] || !inside(self.buffer[i]) {
+                continue;
+            }
+            let (mut left, mut right) = (x, x);
+            while left > 0 {
+                let j = i - (x - left + 1) as usize;
+                if visited[j] || !inside(self.buffer[j]) {
+                    break;
+                }
+                left -= 1;
+            }
+            while right < w - 1 {
+                let j = i + (right - x + 1) as usize;
+                if visited[j] || !inside(self.buffer[j]) {
+                    break;
+                }
+                right += 1;
+            }
+            for fx in left..=right {
+                let j = (y * w + fx) as usize;
+                visited[j] = true;
+                self.buffer[j] = color;
+            }
+            for ny in [y - 1, y + 1] {
+                if ny < 0 || ny >= h {
+                    continue;
+                }
+                for fx in left..=right {
+                    let j = (ny * w + fx) as usize;
+                    if !visited[j] {
+                        stack.push((fx, ny));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The operations of `GraphicsEnvironment` and `Draw`.
+impl Backend for Canvas {
+    fn clear(&mut self) {
+        self.buffer.fill(self.bkcolor);
+    }
+
+    fn setfillcolor(&mut self, color: ARGB) {
+        self.fillcolor = color;
+        self.fillstyle = FillPattern::Solid;
+    }
+
+    fn setcolor(&mut self, color: ARGB) {
+        self.linecolor = color;
+        self.textcolor = color;
+    }
+
+    fn setbkcolor(&mut self, color: ARGB) {
+        let old = self.bkcolor;
+        for pixel in self.buffer.iter_mut().filter(|p| **p == old) {
+            *pixel = color;
+        }
+        self.bkcolor = color;
+    }
+
+    fn getbkcolor(&self) -> Color {
+        Color::from_argb(self.bkcolor)
+    }
+
+    fn setlinecolor(&mut self, color: ARGB) {
+        self.linecolor = color;
+    }
+
+    fn setfillstyle(&mut self, pattern: FillPattern, color: ARGB) {
+        self.fillcolor = color;
+        self.fillstyle = pattern;
+    }
+
+    fn setlinestyle(&mut self, style: LineStyle, width: i32) {
+        self.linestyle = style;
+        self.thickness = width.max(1);
+    }
+
+    fn getcolor(&self) -> Color {
+        Color::from_argb(self.linecolor)
+    }
+
+    fn gettextcolor(&self) -> Color {
+        Color::from_argb(self.textcolor)
+    }
+
+    fn settextcolor(&mut self, color: ARGB) {
+        self.textcolor = color;
+    }
+
+    fn setlinewidth(&mut self, width: f32) {
+        self.thickness = (width.round() as i32).max(1);
+    }
+
+    fn getfillcolor(&self) -> Color {
+        Color::from_argb(self.fillcolor)
+    }
+
+    fn getlinestyle(&self) -> (LineStyle, i32) {
+        (self.linestyle, self.thickness)
+    }
+
+    fn getwidth(&self) -> u32 {
+        self.width
+    }
+
+    fn getheight(&self) -> u32 {
+        self.height
+    }
+
+    fn getx(&self) -> i32 {
+        self.x
+    }
+
+    fn gety(&self) -> i32 {
+        self.y
+    }
+
+    fn moverel(&mut self, dx: i32, dy: i32) {
+        self.x += dx;
+        self.y += dy;
+    }
+
+    fn moveto(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    fn getbuffer(&self) -> &[ARGB] {
+        &self.buffer
+    }
+
+    fn getbuffer_mut(&mut self) -> &mut [ARGB] {
+        &mut self.buffer
+    }
+
+    fn getpixel(&self, x: i32, y: i32) -> Color {
+        Color::from_argb(self.pixel(x, y))
+    }
+
+    fn putpixel(&mut self, x: i32, y: i32, color: ARGB) {
+        self.set(x, y, color);
+    }
+
+    fn putpixel_savealpha(&mut self, x: i32, y: i32, color: ARGB) {
+        if let Some(i) = self.index(x, y) {
+            self.buffer[i] = (self.buffer[i] & 0xFF000000) | (color & 0x00FFFFFF);
+        }
+    }
+
+    fn putpixel_withalpha(&mut self, x: i32, y: i32, color: ARGB) {
+        if let Some(i) = self.index(x, y) {
+            self.buffer[i] = colorblend(self.buffer[i], color, color >> 24);
+        }
+    }
+
+    fn putpixel_alphablend(&mut self, x: i32, y: i32, color: ARGB, factor: u8) {
+        if let Some(i) = self.index(x, y) {
+            let alpha = ((color >> 24) * factor as u32 + 127) / 255;
+            self.buffer[i] = alphablend(self.buffer[i], color, alpha);
+        }
+    }
+
+    fn putpixels(&mut self, array: &[PixelPoint<ARGB>]) {
+        for p in array {
+            self.set(p.x, p.y, p.color);
+        }
+    }
+
+    fn arc(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
+        self.ellipse(x, y, start, end, radius, radius)
+    }
+
+    fn arcf(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
+        self.ellipsef(x, y, start, end, radius, radius)
+    }
+
+    fn ellipse(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.ellipse_arc(x, y, rx, ry, start, sweep);
+    }
+
+    fn ellipsef(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.ellipse_arc(round(x), round(y), round(rx), round(ry), start, sweep);
+    }
+
+    fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        self.segment(x1, y1, x2, y2, &mut 0);
+    }
+
+    fn linerel(&mut self, dx: i32, dy: i32) {
+        self.lineto(self.x + dx, self.y + dy);
+    }
+
+    fn lineto(&mut self, x: i32, y: i32) {
+        self.line(self.x, self.y, x, y);
+        self.moveto(x, y);
+    }
+
+    fn drawlines(&mut self, lines: &[Line]) {
+        for l in lines {
+            self.line(l.x1, l.y1, l.x2, l.y2);
+        }
+    }
+
+    fn polyline(&mut self, points: &[Point]) {
+        self.path(points, false);
+    }
+
+    fn drawpoly(&mut self, points: &[Point]) {
+        self.polyline(points)
+    }
+
+    fn drawbezier(&mut self, points: &[Point]) {
+        let Some(&first) = points.first() else {
+            return;
+        };
+        let mut flattened = vec![first];
+        for c in points[1..].chunks_exact(3) {
+            let p0 = *flattened.last().unwrap();
+            let hull = [p0, c[0], c[1], c[2]]
+                .windows(2)
+                .map(|w| ((w[1].x - w[0].x).abs() + (w[1].y - w[0].y).abs()) as usize)
+                .sum::<usize>();
+            let n = (hull / 4).clamp(4, 256);
+            for i in 1..=n {
+                let t = i as f64 / n as f64;
+                let u = 1.0 - t;
+                let w = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+                let x = w[0] * p0.x as f64
+                    + w[1] * c[0].x as f64
+                    + w[2] * c[1].x as f64
+                    + w[3] * c[2].x as f64;
+                let y = w[0] * p0.y as f64
+                    + w[1] * c[0].y as f64
+                    + w[2] * c[1].y as f64
+                    + w[3] * c[2].y as f64;
+                flattened.push(Point {
+                    x: x.round() as i32,
+                    y: y.round() as i32,
+                });
+            }
+        }
+        self.path(&flattened, false);
+    }
+
+    fn rectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        let (right, bottom) = (right - 1, bottom - 1);
+        let corners = [
+            Point { x: left, y: top },
+            Point { x: right, y: top },
+            Point {
+                x: right,
+                y: bottom,
+            },
+            Point { x: left, y: bottom },
+        ];
+        self.path(&corners, true);
+    }
+
+    fn fillrect(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        self.bar(left, top, right, bottom);
+        self.rectangle(left, top, right, bottom);
+    }
+
+    fn solidrectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        self.bar(left, top, right, bottom)
+    }
+
+    fn bar(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        if self.fillstyle == FillPattern::Empty {
+            return;
+        }
+        let color = self.fillcolor;
+        for y in top.max(0)..bottom.min(self.height as i32) {
+            self.hline(left, right.saturating_sub(1), y, color);
+        }
+    }
+
+    fn polygon(&mut self, points: &[Point]) {
+        self.path(points, true);
+    }
+
+    fn fillpoly(&mut self, points: &[Point]) {
+        self.fill_polygon(points);
+        self.polygon(points);
+    }
+
+    fn solidpoly(&mut self, points: &[Point]) {
+        self.fill_polygon(points);
+    }
+
+    fn circle(&mut self, x: i32, y: i32, radius: i32) {
+        self.ellipse(x, y, 0, 360, radius, radius)
+    }
+
+    fn circlef(&mut self, x: f32, y: f32, radius: f32) {
+        self.ellipsef(x, y, 0.0, 360.0, radius, radius)
+    }
+
+    fn fillcircle(&mut self, x: i32, y: i32, radius: i32) {
+        self.fillellipse(x, y, radius, radius)
+    }
+
+    fn fillcirclef(&mut self, x: f32, y: f32, radius: f32) {
+        self.fillellipsef(x, y, radius, radius)
+    }
+
+    fn fillellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
+        self.fill_ellipse(x, y, rx, ry, None);
+        self.ellipse_arc(x, y, rx, ry, 0.0, 2.0 * PI);
+    }
+
+    fn fillellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
+        self.fillellipse(round(x), round(y), round(rx), round(ry));
+    }
+
+    fn solidcircle(&mut self, x: i32, y: i32, radius: i32) {
+        self.solidellipse(x, y, radius, radius)
+    }
+
+    fn solidcirclef(&mut self, x: f32, y: f32, radius: f32) {
+        self.solidellipsef(x, y, radius, radius)
+    }
+
+    fn solidellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
+        self.fill_ellipse(x, y, rx, ry, None);
+    }
+
+    fn solidellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
+        self.solidellipse(round(x), round(y), round(rx), round(ry));
+    }
+
+    fn pie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.pie_outline(x, y, rx, ry, start, sweep);
+    }
+
+    fn pief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.pie_outline(round(x), round(y), round(rx), round(ry), start, sweep);
+    }
+
+    fn fillpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.fill_ellipse(x, y, rx, ry, Some((start, sweep)));
+        self.pie_outline(x, y, rx, ry, start, sweep);
+    }
+
+    fn fillpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        let (x, y, rx, ry) = (round(x), round(y), round(rx), round(ry));
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.fill_ellipse(x, y, rx, ry, Some((start, sweep)));
+        self.pie_outline(x, y, rx, ry, start, sweep);
+    }
+
+    fn solidpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.fill_ellipse(x, y, rx, ry, Some((start, sweep)));
+    }
+
+    fn solidpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        let (start, sweep) = sweep_of(start as f64, end as f64);
+        self.fill_ellipse(
+            round(x),
+            round(y),
+            round(rx),
+            round(ry),
+            Some((start, sweep)),
+        );
+    }
+
+    fn sector(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
+        self.fillpie(x, y, start, end, rx, ry)
+    }
+
+    fn sectorf(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
+        self.fillpief(x, y, start, end, rx, ry)
+    }
+
+    fn pieslice(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
+        self.fillpie(x, y, start, end, radius, radius)
+    }
+
+    fn pieslicef(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
+        self.fillpief(x, y, start, end, radius, radius)
+    }
+
+    fn floodfill(&mut self, x: i32, y: i32, border: ARGB) {
+        let border = border & 0x00FFFFFF;
+        self.flood(x, y, |c| c & 0x00FFFFFF != border);
+    }
+
+    fn floodfillsurface(&mut self, x: i32, y: i32, area: ARGB) {
+        let area = area & 0x00FFFFFF;
+        self.flood(x, y, |c| c & 0x00FFFFFF == area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: ARGB = 0xFFFF0000;
+
+    /// The canvas as rows of `#` for `color` and `.` for other pixels.
+    fn grid(canvas: &Canvas, color: ARGB) -> Vec<String> {
+        (0..canvas.height as i32)
+            .map(|y| {
+                (0..canvas.width as i32)
+                    .map(|x| {
+                        if canvas.pixel(x, y) == color {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn canvas(width: u32, height: u32) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        canvas.setcolor(RED);
+        canvas.setfillcolor(RED);
+        canvas
+    }
+
+    #[test]
+    fn new_canvas() {
+        let canvas = Canvas::new(0, 3);
+        assert_eq!((canvas.getwidth(), canvas.getheight()), (1, 3));
+        assert!(canvas.getbuffer().iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn line_excludes_end_point() {
+        let mut canvas = canvas(6, 3);
+        canvas.line(0, 0, 4, 0);
+        canvas.line(5, 0, 5, 2);
+        canvas.line(0, 2, 2, 1);
+        assert_eq!(grid(&canvas, RED), ["####.#", ".#...#", "#....."]);
+    }
+
+    #[test]
+    fn line_style_pattern() {
+        let mut canvas = canvas(9, 2);
+        canvas.setlinestyle(LineStyle::Dotted, 1);
+        canvas.line(0, 0, 9, 0);
+        canvas.setlinestyle(LineStyle::Null, 1);
+        canvas.line(0, 1, 9, 1);
+        assert_eq!(grid(&canvas, RED), ["..##..##.", "........."]);
+    }
+
+    #[test]
+    fn rectangles() {
+        let mut canvas = canvas(6, 5);
+        canvas.rectangle(0, 0, 6, 5);
+        canvas.setfillcolor(0xFF00FF00);
+        canvas.bar(2, 1, 4, 3);
+        assert_eq!(
+            grid(&canvas, RED),
+            ["######", "#....#", "#....#", "#....#", "######"]
+        );
+        assert_eq!(
+            grid(&canvas, 0xFF00FF00),
+            ["......", "..##..", "..##..", "......", "......"]
+        );
+    }
+
+    #[test]
+    fn polygon_fill_is_half_open() {
+        let mut canvas = canvas(6, 6);
+        let square = [
+            Point { x: 1, y: 1 },
+            Point { x: 4, y: 1 },
+            Point { x: 4, y: 4 },
+            Point { x: 1, y: 4 },
+        ];
+        canvas.solidpoly(&square);
+        assert_eq!(
+            grid(&canvas, RED),
+            ["......", ".###..", ".###..", ".###..", "......", "......"]
+        );
+    }
+
+    #[test]
+    fn filled_circle_is_symmetric() {
+        let mut canvas = canvas(11, 11);
+        canvas.fillcircle(5, 5, 3);
+        let rows = grid(&canvas, RED);
+        let flipped = rows.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(rows, flipped);
+        assert!(rows.iter().all(|row| row.chars().eq(row.chars().rev())));
+        assert_eq!(rows[5], "..#######..");
+        assert_eq!(rows[1], "...........");
+    }
+
+    #[test]
+    fn ellipse_rows_match_the_border() {
+        for rx in 0..24 {
+            for ry in 0..24 {
+                let mut half = vec![-1; ry as usize + 1];
+                for (dx, dy) in ellipse_points(rx, ry) {
+                    let h = &mut half[dy.unsigned_abs() as usize];
+                    *h = (*h).max(dx.abs() as i64);
+                }
+                let rows = EllipseRows::new(rx, ry);
+                for (dy, &h) in half.iter().enumerate() {
+                    assert_eq!(rows.half_width(dy as i64), h, "rx {rx}, ry {ry}, dy {dy}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_shapes_are_clipped() {
+        let mut bar = canvas(4, 3);
+        bar.bar(-i32::MAX, -i32::MAX, i32::MAX, i32::MAX);
+        assert!(bar.getbuffer().iter().all(|&p| p == RED));
+
+        let mut ellipse = canvas(4, 3);
+        ellipse.solidellipse(2, 1, i32::MAX, i32::MAX);
+        assert!(ellipse.getbuffer().iter().all(|&p| p == RED));
+
+        let mut pie = canvas(4, 3);
+        pie.solidpie(0, 2, 0, 90, 1 << 30, 1 << 30);
+        assert_eq!(grid(&pie, RED), ["####", "####", "####"]);
+    }
+
+    #[test]
+    fn flood_fill_stops_at_border() {
+        let mut canvas = canvas(5, 5);
+        canvas.rectangle(0, 0, 4, 4);
+        canvas.setfillcolor(0xFF0000FF);
+        canvas.floodfill(1, 1, RED);
+        assert_eq!(
+            grid(&canvas, 0xFF0000FF),
+            [".....", ".##..", ".##..", ".....", "....."]
+        );
+    }
+
+    #[test]
+    fn bezier() {
+        let mut canvas = canvas(8, 1);
+        canvas.drawbezier(&[]);
+        assert_eq!(grid(&canvas, RED), ["........"]);
+        let points = [0, 2, 4, 7].map(|x| Point { x, y: 0 });
+        canvas.drawbezier(&points);
+        assert_eq!(grid(&canvas, RED), ["#######."]);
+    }
+
+    #[test]
+    fn alpha_blend() {
+        let mut canvas = canvas(2, 1);
+        canvas.clear();
+        canvas.putpixel_alphablend(0, 0, 0xFFFFFFFF, 128);
+        canvas.putpixel_withalpha(1, 0, 0x80FFFFFF);
+        assert_eq!(canvas.pixel(0, 0), 0xFF808080);
+        assert_eq!(canvas.pixel(1, 0), 0xFF808080);
+    }
+}
@@ -1,8 +1,17 @@
 /// Color
+///
+/// An sRGB color with straight alpha. It converts from and to `ARGB` and formats as
+/// hex, e.g. `#ff8000` or `#ff800080` when not opaque.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
 pub struct Color(palette::Srgba<u8>);
 
 impl Color {
-    pub fn from_argb(argb: ARGB) -> Self {
+    pub const fn from_argb(argb: ARGB) -> Self {
         Color(palette::Srgba::<u8>::new(
             ((argb >> 16) & 0xff) as _,
             ((argb >> 8) & 0xff) as _,
@@ -11,21 +20,428 @@ impl Color {
         ))
     }
 
-    pub fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
         Color(palette::Srgba::<u8>::new(red, green, blue, alpha))
     }
 
-    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
         Self::rgba(red, green, blue, 255)
     }
 
-    pub fn gray(gray: u8) -> Self {
+    pub const fn gray(gray: u8) -> Self {
         Self::rgb(gray, gray, gray)
     }
 
-    pub fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+    pub const fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
         Self::rgba(red, green, blue, alpha)
     }
+
+    /// Get a copy of the color with another alpha.
+    pub fn with_alpha(&self, alpha: u8) -> Self {
+        Self::rgba(self.red, self.green, self.blue, alpha)
+    }
+
+    fn to_float(self) -> Srgba<f32> {
+        self.0.into_format()
+    }
+
+    fn from_float(color: Srgba<f32>) -> Self {
+        Color(color.into_format())
+    }
+
+    /// Create an opaque color from hue, saturation and value.
+    ///
+    /// # Parameters
+    /// - `hue`: The hue in degrees.
+    /// - `saturation`: The saturation from `0.0` to `1.0`.
+    /// - `value`: The value from `0.0` to `1.0`.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        Self::from_float(Srgb::from_color(Hsv::new(hue, saturation, value)).into())
+    }
+
+    /// Get the hue in degrees from `0.0` to `360.0`, the saturation and the value.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let hsv = Hsv::from_color(self.to_float().color);
+        (hsv.hue.into_positive_degrees(), hsv.saturation, hsv.value)
+    }
+
+    /// Create an opaque color from hue, saturation and lightness.
+    ///
+    /// # Parameters
+    /// - `hue`: The hue in degrees.
+    /// - `saturation`: The saturation from `0.0` to `1.0`.
+    /// - `lightness`: The lightness from `0.0` to `1.0`.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self::from_float(Srgb::from_color(Hsl::new(hue, saturation, lightness)).into())
+    }
+
+    /// Get the hue in degrees from `0.0` to `360.0`, the saturation and the lightness.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let hsl = Hsl::from_color(self.to_float().color);
+        (
+            hsl.hue.into_positive_degrees(),
+            hsl.saturation,
+            hsl.lightness,
+        )
+    }
+
+    /// Create an opaque color in the Oklab color space.
+    ///
+    /// # Parameters
+    /// - `l`: The perceived lightness from `0.0` to `1.0`.
+    /// - `a`: How green (negative) or red (positive) the color is.
+    /// - `b`: How blue (negative) or yellow (positive) the color is.
+    ///
+    /// # Note
+    /// Colors outside of sRGB are clamped.
+    pub fn from_oklab(l: f32, a: f32, b: f32) -> Self {
+        Self::from_float(Srgb::from_color(Oklab::new(l, a, b)).into())
+    }
+
+    /// Get the lightness, `a` and `b` in the Oklab color space.
+    pub fn to_oklab(&self) -> (f32, f32, f32) {
+        let oklab = Oklab::from_color(self.to_float().color);
+        (oklab.l, oklab.a, oklab.b)
+    }
+
+    /// Interpolate between two colors in linear light.
+    ///
+    /// # Parameters
+    /// - `other`: The color at `t = 1.0`.
+    /// - `t`: The position from `0.0` to `1.0`.
+    pub fn lerp(&self, other: &Color, t: f32) -> Self {
+        let (a, b) = (
+            self.to_float().into_linear(),
+            other.to_float().into_linear(),
+        );
+        Self::from_float(Srgba::from_linear(a.mix(b, t)))
+    }
+
+    /// Interpolate between two colors in the Oklab color space.
+    ///
+    /// # Note
+    /// See `lerp`. The colors in between look evenly spaced.
+    pub fn lerp_oklab(&self, other: &Color, t: f32) -> Self {
+        let a = Oklaba::from_color(self.to_float());
+        let b = Oklaba::from_color(other.to_float());
+        Self::from_float(Srgba::from_color(a.mix(b, t)))
+    }
+
+    /// Get the relative luminance from `0.0` for black to `1.0` for white, as defined by WCAG.
+    pub fn luminance(&self) -> f32 {
+        let linear = self.to_float().color.into_linear();
+        0.2126 * linear.red + 0.7152 * linear.green + 0.0722 * linear.blue
+    }
+
+    /// Get the contrast ratio with another color, from `1.0` to `21.0`, as defined by WCAG.
+    ///
+    /// # Note
+    /// The alpha is ignored. WCAG requires at least `4.5` for normal text.
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Parse a hex color.
+    ///
+    /// # Parameters
+    /// - `hex`: The color as `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+        let error = || ParseColorError::InvalidHex(hex.to_string());
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let channel = |i: usize, len: usize| {
+            let value =
+                u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).map_err(|_| error());
+            value.map(|v| if len == 1 { v * 17 } else { v })
+        };
+        let len = match digits.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return Err(error()),
+        };
+        let alpha = match digits.len() / len {
+            4 => channel(3, len)?,
+            _ => 255,
+        };
+        Ok(Self::rgba(
+            channel(0, len)?,
+            channel(1, len)?,
+            channel(2, len)?,
+            alpha,
+        ))
+    }
+
+    /// Format the color as `#rrggbb`, or `#rrggbbaa` if it is not opaque.
+    pub fn to_hex(&self) -> String {
+        let (r, g, b, a) = (self.red, self.green, self.blue, self.alpha);
+        match a {
+            255 => format!("#{r:02x}{g:02x}{b:02x}"),
+            _ => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        }
+    }
+}
+
+/// The named colors of EGE.
+///
+/// They are the CSS colors, plus `LIGHTRED` and `LIGHTMAGENTA` of the classic BGI
+/// palette. See `palette::named`, re-exported by this module, for the CSS colors as
+/// `Srgb<u8>`.
+impl Color {
+    pub const ALICEBLUE: Color = Color::rgb(0xF0, 0xF8, 0xFF);
+    pub const ANTIQUEWHITE: Color = Color::rgb(0xFA, 0xEB, 0xD7);
+    pub const AQUA: Color = Color::rgb(0x00, 0xFF, 0xFF);
+    pub const AQUAMARINE: Color = Color::rgb(0x7F, 0xFF, 0xD4);
+    pub const AZURE: Color = Color::rgb(0xF0, 0xFF, 0xFF);
+    pub const BEIGE: Color = Color::rgb(0xF5, 0xF5, 0xDC);
+    pub const BISQUE: Color = Color::rgb(0xFF, 0xE4, 0xC4);
+    pub const BLACK: Color = Color::rgb(0x00, 0x00, 0x00);
+    pub const BLANCHEDALMOND: Color = Color::rgb(0xFF, 0xEB, 0xCD);
+    pub const BLUE: Color = Color::rgb(0x00, 0x00, 0xFF);
+    pub const BLUEVIOLET: Color = Color::rgb(0x8A, 0x2B, 0xE2);
+    pub const BROWN: Color = Color::rgb(0xA5, 0x2A, 0x2A);
+    pub const BURLYWOOD: Color = Color::rgb(0xDE, 0xB8, 0x87);
+    pub const CADETBLUE: Color = Color::rgb(0x5F, 0x9E, 0xA0);
+    pub const CHARTREUSE: Color = Color::rgb(0x7F, 0xFF, 0x00);
+    pub const CHOCOLATE: Color = Color::rgb(0xD2, 0x69, 0x1E);
+    pub const CORAL: Color = Color::rgb(0xFF, 0x7F, 0x50);
+    pub const CORNFLOWERBLUE: Color = Color::rgb(0x64, 0x95, 0xED);
+    pub const CORNSILK: Color = Color::rgb(0xFF, 0xF8, 0xDC);
+    pub const CRIMSON: Color = Color::rgb(0xDC, 0x14, 0x3C);
+    pub const CYAN: Color = Color::rgb(0x00, 0xFF, 0xFF);
+    pub const DARKBLUE: Color = Color::rgb(0x00, 0x00, 0x8B);
+    pub const DARKCYAN: Color = Color::rgb(0x00, 0x8B, 0x8B);
+    pub const DARKGOLDENROD: Color = Color::rgb(0xB8, 0x86, 0x0B);
+    pub const DARKGRAY: Color = Color::rgb(0xA9, 0xA9, 0xA9);
+    pub const DARKGREEN: Color = Color::rgb(0x00, 0x64, 0x00);
+    pub const DARKKHAKI: Color = Color::rgb(0xBD, 0xB7, 0x6B);
+    pub const DARKMAGENTA: Color = Color::rgb(0x8B, 0x00, 0x8B);
+    pub const DARKOLIVEGREEN: Color = Color::rgb(0x55, 0x6B, 0x2F);
+    pub const DARKORANGE: Color = Color::rgb(0xFF, 0x8C, 0x00);
+    pub const DARKORCHID: Color = Color::rgb(0x99, 0x32, 0xCC);
+    pub const DARKRED: Color = Color::rgb(0x8B, 0x00, 0x00);
+    pub const DARKSALMON: Color = Color::rgb(0xE9, 0x96, 0x7A);
+    pub const DARKSEAGREEN: Color = Color::rgb(0x8F, 0xBC, 0x8F);
+    pub const DARKSLATEBLUE: Color = Color::rgb(0x48, 0x3D, 0x8B);
+    pub const DARKSLATEGRAY: Color = Color::rgb(0x2F, 0x4F, 0x4F);
+    pub const DARKTURQUOISE: Color = Color::rgb(0x00, 0xCE, 0xD1);
+    pub const DARKVIOLET: Color = Color::rgb(0x94, 0x00, 0xD3);
+    pub const DEEPPINK: Color = Color::rgb(0xFF, 0x14, 0x93);
+    pub const DEEPSKYBLUE: Color = Color::rgb(0x00, 0xBF, 0xFF);
+    pub const DIMGRAY: Color = Color::rgb(0x69, 0x69, 0x69);
+    pub const DODGERBLUE: Color = Color::rgb(0x1E, 0x90, 0xFF);
+    pub const FIREBRICK: Color = Color::rgb(0xB2, 0x22, 0x22);
+    pub const FLORALWHITE: Color = Color::rgb(0xFF, 0xFA, 0xF0);
+    pub const FORESTGREEN: Color = Color::rgb(0x22, 0x8B, 0x22);
+    pub const FUCHSIA: Color = Color::rgb(0xFF, 0x00, 0xFF);
+    pub const GAINSBORO: Color = Color::rgb(0xDC, 0xDC, 0xDC);
+    pub const GHOSTWHITE: Color = Color::rgb(0xF8, 0xF8, 0xFF);
+    pub const GOLD: Color = Color::rgb(0xFF, 0xD7, 0x00);
+    pub const GOLDENROD: Color = Color::rgb(0xDA, 0xA5, 0x20);
+    pub const GRAY: Color = Color::rgb(0x80, 0x80, 0x80);
+    pub const GREEN: Color = Color::rgb(0x00, 0x80, 0x00);
+    pub const GREENYELLOW: Color = Color::rgb(0xAD, 0xFF, 0x2F);
+    pub const HONEYDEW: Color = Color::rgb(0xF0, 0xFF, 0xF0);
+    pub const HOTPINK: Color = Color::rgb(0xFF, 0x69, 0xB4);
+    pub const INDIANRED: Color = Color::rgb(0xCD, 0x5C, 0x5C);
+    pub const INDIGO: Color = Color::rgb(0x4B, 0x00, 0x82);
+    pub const IVORY: Color = Color::rgb(0xFF, 0xFF, 0xF0);
+    pub const KHAKI: Color = Color::rgb(0xF0, 0xE6, 0x8C);
+    pub const LAVENDER: Color = Color::rgb(0xE6, 0xE6, 0xFA);
+    pub const LAVENDERBLUSH: Color = Color::rgb(0xFF, 0xF0, 0xF5);
+    pub const LAWNGREEN: Color = Color::rgb(0x7C, 0xFC, 0x00);
+    pub const LEMONCHIFFON: Color = Color::rgb(0xFF, 0xFA, 0xCD);
+    pub const LIGHTBLUE: Color = Color::rgb(0xAD, 0xD8, 0xE6);
+    pub const LIGHTCORAL: Color = Color::rgb(0xF0, 0x80, 0x80);
+    pub const LIGHTCYAN: Color = Color::rgb(0xE0, 0xFF, 0xFF);
+    pub const LIGHTGOLDENRODYELLOW: Color = Color::rgb(0xFA, 0xFA, 0xD2);
+    pub const LIGHTGRAY: Color = Color::rgb(0xD3, 0xD3, 0xD3);
+    pub const LIGHTGREEN: Color = Color::rgb(0x90, 0xEE, 0x90);
+    pub const LIGHTPINK: Color = Color::rgb(0xFF, 0xB6, 0xC1);
+    pub const LIGHTSALMON: Color = Color::rgb(0xFF, 0xA0, 0x7A);
+    pub const LIGHTSEAGREEN: Color = Color::rgb(0x20, 0xB2, 0xAA);
+    pub const LIGHTSKYBLUE: Color = Color::rgb(0x87, 0xCE, 0xFA);
+    pub const LIGHTSLATEGRAY: Color = Color::rgb(0x77, 0x88, 0x99);
+    pub const LIGHTSTEELBLUE: Color = Color::rgb(0xB0, 0xC4, 0xDE);
+    pub const LIGHTYELLOW: Color = Color::rgb(0xFF, 0xFF, 0xE0);
+    pub const LIGHTRED: Color = Color::rgb(0xFC, 0x54, 0x54);
+    pub const LIGHTMAGENTA: Color = Color::rgb(0xFC, 0x54, 0xFC);
+    pub const LIME: Color = Color::rgb(0x00, 0xFF, 0x00);
+    pub const LIMEGREEN: Color = Color::rgb(0x32, 0xCD, 0x32);
+    pub const LINEN: Color = Color::rgb(0xFA, 0xF0, 0xE6);
+    pub const MAGENTA: Color = Color::rgb(0xFF, 0x00, 0xFF);
+    pub const MAROON: Color = Color::rgb(0x80, 0x00, 0x00);
+    pub const MEDIUMAQUAMARINE: Color = Color::rgb(0x66, 0xCD, 0xAA);
+    pub const MEDIUMBLUE: Color = Color::rgb(0x00, 0x00, 0xCD);
+    pub const MEDIUMORCHID: Color = Color::rgb(0xBA, 0x55, 0xD3);
+    pub const MEDIUMPURPLE: Color = Color::rgb(0x93, 0x70, 0xDB);
+    pub const MEDIUMSEAGREEN: Color = Color::rgb(0x3C, 0xB3, 0x71);
+    pub const MEDIUMSLATEBLUE: Color = Color::rgb(0x7B, 0x68, 0xEE);
+    pub const MEDIUMSPRINGGREEN: Color = Color::rgb(0x00, 0xFA, 0x9A);
+    pub const MEDIUMTURQUOISE: Color = Color::rgb(0x48, 0xD1, 0xCC);
+    pub const MEDIUMVIOLETRED: Color = Color::rgb(0xC7, 0x15, 0x85);
+    pub const MIDNIGHTBLUE: Color = Color::rgb(0x19, 0x19, 0x70);
+    pub const MINTCREAM: Color = Color::rgb(0xF5, 0xFF, 0xFA);
+    pub const MISTYROSE: Color = Color::rgb(0xFF, 0xE4, 0xE1);
+    pub const MOCCASIN: Color = Color::rgb(0xFF, 0xE4, 0xB5);
+    pub const NAVAJOWHITE: Color = Color::rgb(0xFF, 0xDE, 0xAD);
+    pub const NAVY: Color = Color::rgb(0x00, 0x00, 0x80);
+    pub const OLDLACE: Color = Color::rgb(0xFD, 0xF5, 0xE6);
+    pub const OLIVE: Color = Color::rgb(0x80, 0x80, 0x00);
+    pub const OLIVEDRAB: Color = Color::rgb(0x6B, 0x8E, 0x23);
+    pub const ORANGE: Color = Color::rgb(0xFF, 0xA5, 0x00);
+    pub const ORANGERED: Color = Color::rgb(0xFF, 0x45, 0x00);
+    pub const ORCHID: Color = Color::rgb(0xDA, 0x70, 0xD6);
+    pub const PALEGOLDENROD: Color = Color::rgb(0xEE, 0xE8, 0xAA);
+    pub const PALEGREEN: Color = Color::rgb(0x98, 0xFB, 0x98);
+    pub const PALETURQUOISE: Color = Color::rgb(0xAF, 0xEE, 0xEE);
+    pub const PALEVIOLETRED: Color = Color::rgb(0xDB, 0x70, 0x93);
+    pub const PAPAYAWHIP: Color = Color::rgb(0xFF, 0xEF, 0xD5);
+    pub const PEACHPUFF: Color = Color::rgb(0xFF, 0xDA, 0xB9);
+    pub const PERU: Color = Color::rgb(0xCD, 0x85, 0x3F);
+    pub const PINK: Color = Color::rgb(0xFF, 0xC0, 0xCB);
+    pub const PLUM: Color = Color::rgb(0xDD, 0xA0, 0xDD);
+    pub const POWDERBLUE: Color = Color::rgb(0xB0, 0xE0, 0xE6);
+    pub const PURPLE: Color = Color::rgb(0x80, 0x00, 0x80);
+    pub const RED: Color = Color::rgb(0xFF, 0x00, 0x00);
+    pub const ROSYBROWN: Color = Color::rgb(0xBC, 0x8F, 0x8F);
+    pub const ROYALBLUE: Color = Color::rgb(0x41, 0x69, 0xE1);
+    pub const SADDLEBROWN: Color = Color::rgb(0x8B, 0x45, 0x13);
+    pub const SALMON: Color = Color::rgb(0xFA, 0x80, 0x72);
+    pub const SANDYBROWN: Color = Color::rgb(0xF4, 0xA4, 0x60);
+    pub const SEAGREEN: Color = Color::rgb(0x2E, 0x8B, 0x57);
+    pub const SEASHELL: Color = Color::rgb(0xFF, 0xF5, 0xEE);
+    pub const SIENNA: Color = Color::rgb(0xA0, 0x52, 0x2D);
+    pub const SILVER: Color = Color::rgb(0xC0, 0xC0, 0xC0);
+    pub const SKYBLUE: Color = Color::rgb(0x87, 0xCE, 0xEB);
+    pub const SLATEBLUE: Color = Color::rgb(0x6A, 0x5A, 0xCD);
+    pub const SLATEGRAY: Color = Color::rgb(0x70, 0x80, 0x90);
+    pub const SNOW: Color = Color::rgb(0xFF, 0xFA, 0xFA);
+    pub const SPRINGGREEN: Color = Color::rgb(0x00, 0xFF, 0x7F);
+    pub const STEELBLUE: Color = Color::rgb(0x46, 0x82, 0xB4);
+    pub const TAN: Color = Color::rgb(0xD2, 0xB4, 0x8C);
+    pub const TEAL: Color = Color::rgb(0x00, 0x80, 0x80);
+    pub const THISTLE: Color = Color::rgb(0xD8, 0xBF, 0xD8);
+    pub const TOMATO: Color = Color::rgb(0xFF, 0x63, 0x47);
+    pub const TURQUOISE: Color = Color::rgb(0x40, 0xE0, 0xD0);
+    pub const VIOLET: Color = Color::rgb(0xEE, 0x82, 0xEE);
+    pub const WHEAT: Color = Color::rgb(0xF5, 0xDE, 0xB3);
+    pub const WHITE: Color = Color::rgb(0xFF, 0xFF, 0xFF);
+    pub const WHITESMOKE: Color = Color::rgb(0xF5, 0xF5, 0xF5);
+    pub const YELLOW: Color = Color::rgb(0xFF, 0xFF, 0x00);
+    pub const YELLOWGREEN: Color = Color::rgb(0x9A, 0xCD, 0x32);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseColorError {
+    #[error("`{0}` is not a hex color like `#rrggbb` or `#rrggbbaa`.")]
+    InvalidHex(String),
+    #[error("`{0}` is not a CSS color like `rgb(255, 128, 0)` or `rgba(255 128 0 / 50%)`.")]
+    InvalidCss(String),
+}
+
+impl std::fmt::Debug for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Color")
+            .field("red", &self.red)
+            .field("green", &self.green)
+            .field("blue", &self.blue)
+            .field("alpha", &self.alpha)
+            .finish()
+    }
+}
+
+impl std::hash::Hash for Color {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.into_argb().hash(state);
+    }
+}
+
+impl std::fmt::Display for Color {
+    /// Format the color as hex, see `to_hex`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Parse a CSS `rgb()` or `rgba()` color.
+///
+/// The channels are numbers from `0` to `255` or percentages, the alpha is a number
+/// from `0` to `1` or a percentage. Both the comma and the space separated syntax
+/// are accepted.
+fn parse_css(text: &str) -> Option<Color> {
+    let (name, rest) = text.split_once('(')?;
+    let name = name.trim().to_ascii_lowercase();
+    let body = rest.trim_end().strip_suffix(')')?;
+    if name != "rgb" && name != "rgba" {
+        return None;
+    }
+    let (channels, alpha) = match body.split_once('/') {
+        Some((channels, alpha)) => (channels, Some(alpha.trim())),
+        None => (body, None),
+    };
+    let mut parts = channels
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    let alpha = match (alpha, parts.len()) {
+        (None, 4) => parts.pop(),
+        (alpha, 3) => alpha,
+        _ => return None,
+    };
+    let number = |part: &str, scale: f32| match part.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok().map(|p| p / 100.0),
+        None => part.parse::<f32>().ok().map(|v| v / scale),
+    };
+    let byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let [r, g, b] = [parts[0], parts[1], parts[2]].map(|part| number(part, 255.0));
+    let a = alpha.map_or(Some(1.0), |alpha| number(alpha, 1.0));
+    Some(Color::rgba(byte(r?), byte(g?), byte(b?), byte(a?)))
+}
+
+impl std::str::FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parse a hex color, see `from_hex`, or a CSS `rgb()` or `rgba()` color.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text
+            .get(..3)
+            .is_some_and(|name| name.eq_ignore_ascii_case("rgb"))
+        {
+            parse_css(text).ok_or_else(|| ParseColorError::InvalidCss(text.to_string()))
+        } else {
+            Self::from_hex(text)
+        }
+    }
+}
+
+impl From<ARGB> for Color {
+    fn from(argb: ARGB) -> Self {
+        Self::from_argb(argb)
+    }
+}
+
+impl From<Color> for ARGB {
+    fn from(color: Color) -> Self {
+        color.into_argb()
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_hex()
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ParseColorError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
 }
 
 /// This trait is the interface between other color formats and the EGE used color format(`u32`, ARGB).
@@ -42,6 +458,12 @@ impl IntoARGB for palette::Srgba<u8> {
     }
 }
 
+impl IntoARGB for ARGB {
+    fn into_argb(&self) -> u32 {
+        *self
+    }
+}
+
 impl IntoARGB for Color {
     fn into_argb(&self) -> u32 {
         self.0.into_argb()
@@ -57,11 +479,77 @@ impl IntoARGB for palette::rgb::Srgb<u8> {
     }
 }
 
+/// Implement `IntoARGB` for floating point `palette` colors by converting them to sRGB.
+macro_rules! impl_into_argb {
+    ($($color:ty),* $(,)?) => {
+        $(
+            impl IntoARGB for $color {
+                fn into_argb(&self) -> u32 {
+                    Srgba::<f32>::from_color(*self).into_format::<u8, u8>().into_argb()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_argb!(
+    Srgb<f32>,
+    Srgba<f32>,
+    palette::LinSrgb,
+    palette::LinSrgba,
+    palette::SrgbLuma,
+    palette::SrgbLumaa,
+    Hsv,
+    palette::Hsva,
+    Hsl,
+    palette::Hsla,
+    palette::Hwb,
+    palette::Hwba,
+    palette::Lab,
+    palette::Laba,
+    palette::Lch,
+    palette::Lcha,
+    palette::Luv,
+    palette::Luva,
+    palette::Lchuv,
+    palette::Lchuva,
+    palette::Hsluv,
+    palette::Hsluva,
+    Oklab,
+    Oklaba,
+    palette::Oklch,
+    palette::Oklcha,
+    palette::Okhsv,
+    palette::Okhsva,
+    palette::Okhsl,
+    palette::Okhsla,
+    palette::Okhwb,
+    palette::Okhwba,
+    palette::Xyz,
+    palette::Xyza,
+    palette::Yxy,
+    palette::Yxya,
+);
+
+impl IntoARGB for palette::SrgbLuma<u8> {
+    fn into_argb(&self) -> u32 {
+        palette::rgb::Srgb::new(self.luma, self.luma, self.luma).into_argb()
+    }
+}
+
+impl IntoARGB for palette::SrgbLumaa<u8> {
+    fn into_argb(&self) -> u32 {
+        Srgba::new(self.luma, self.luma, self.luma, self.alpha).into_argb()
+    }
+}
+
 // re-exporting palette's named colors
 pub use palette::named::*;
 
 use std::ops::{Deref, DerefMut};
 
+use palette::{FromColor, Hsl, Hsv, Mix, Oklab, Oklaba, Srgb, Srgba};
+
 use crate::ARGB;
 
 impl Deref for Color {
@@ -76,4 +564,191 @@ impl DerefMut for Color {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    fn channels_close(a: Color, b: Color) -> bool {
+        let (a, b) = (a.into_argb().to_be_bytes(), b.into_argb().to_be_bytes());
+        a.iter().zip(b).all(|(&a, b)| a.abs_diff(b) <= 1)
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        for color in [
+            Color::rgb(255, 0, 0),
+            Color::rgb(255, 128, 0),
+            Color::rgb(18, 200, 99),
+            Color::rgb(40, 60, 250),
+        ] {
+            let (h, s, v) = color.to_hsv();
+            assert!(channels_close(Color::from_hsv(h, s, v), color), "{color}");
+        }
+        let (h, s, v) = Color::from_hsv(210.0, 0.5, 0.8).to_hsv();
+        assert!(close(h, 210.0, 1.0) && close(s, 0.5, 0.01) && close(v, 0.8, 0.01));
+    }
+
+    #[test]
+    fn hsl_round_trip() {
+        for color in [
+            Color::rgb(0, 0, 255),
+            Color::rgb(255, 128, 0),
+            Color::rgb(18, 200, 99),
+            Color::rgb(200, 60, 150),
+        ] {
+            let (h, s, l) = color.to_hsl();
+            assert!(channels_close(Color::from_hsl(h, s, l), color), "{color}");
+        }
+        let (h, s, l) = Color::from_hsl(120.0, 1.0, 0.25).to_hsl();
+        assert!(close(h, 120.0, 1.0) && close(s, 1.0, 0.01) && close(l, 0.25, 0.01));
+    }
+
+    #[test]
+    fn gray_has_no_hue() {
+        for gray in [0, 128, 255] {
+            let (h, s, _) = Color::gray(gray).to_hsv();
+            assert_eq!((h, s), (0.0, 0.0));
+            let (h, s, _) = Color::gray(gray).to_hsl();
+            assert_eq!((h, s), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn lerp_endpoints() {
+        let (a, b) = (Color::rgba(255, 128, 0, 255), Color::rgba(0, 64, 255, 0));
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert!(channels_close(a.lerp_oklab(&b, 0.0), a));
+        assert!(channels_close(a.lerp_oklab(&b, 1.0), b));
+        // Half the light is brighter than the perceived middle gray.
+        let linear = Color::BLACK.lerp(&Color::WHITE, 0.5);
+        let oklab = Color::BLACK.lerp_oklab(&Color::WHITE, 0.5);
+        assert!(channels_close(linear, Color::gray(188)), "{linear}");
+        assert!(channels_close(oklab, Color::gray(99)), "{oklab}");
+    }
+
+    #[test]
+    fn luminance() {
+        assert_eq!(Color::BLACK.luminance(), 0.0);
+        assert!(close(Color::WHITE.luminance(), 1.0, 1e-6));
+        assert!(close(Color::rgb(255, 0, 0).luminance(), 0.2126, 1e-6));
+        assert!(close(Color::rgb(0, 255, 0).luminance(), 0.7152, 1e-6));
+        assert!(close(Color::rgb(0, 0, 255).luminance(), 0.0722, 1e-6));
+        assert!(close(Color::gray(128).luminance(), 0.2158, 1e-3));
+    }
+
+    #[test]
+    fn contrast_ratio() {
+        assert!(close(
+            Color::BLACK.contrast_ratio(&Color::WHITE),
+            21.0,
+            1e-4
+        ));
+        assert!(close(
+            Color::WHITE.contrast_ratio(&Color::BLACK),
+            21.0,
+            1e-4
+        ));
+        let color = Color::rgb(18, 200, 99);
+        assert_eq!(color.contrast_ratio(&color), 1.0);
+        assert_eq!(color.contrast_ratio(&color.with_alpha(0)), 1.0);
+    }
+
+    #[test]
+    fn from_hex() {
+        let orange = Color::rgb(0xFF, 0x80, 0x00);
+        assert_eq!(Color::from_hex("#ff8000"), Ok(orange));
+        assert_eq!(Color::from_hex("FF8000"), Ok(orange));
+        assert_eq!(Color::from_hex("#ff800080"), Ok(orange.with_alpha(0x80)));
+        assert_eq!(Color::from_hex("#abc"), Ok(Color::rgb(0xAA, 0xBB, 0xCC)));
+        assert_eq!(
+            Color::from_hex("#abc8"),
+            Ok(Color::rgba(0xAA, 0xBB, 0xCC, 0x88))
+        );
+        for hex in ["#12345", "#", "#ggg", "#ff80 0", "#+f8000"] {
+            assert_eq!(
+                Color::from_hex(hex),
+                Err(ParseColorError::InvalidHex(hex.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("#abc".parse(), Ok(Color::rgb(0xAA, 0xBB, 0xCC)));
+        assert_eq!(" #abc ".parse(), Ok(Color::rgb(0xAA, 0xBB, 0xCC)));
+        assert_eq!("rgb(255, 128, 0)".parse(), Ok(Color::rgb(255, 128, 0)));
+        assert_eq!("RGB(100%, 0%, 50%)".parse(), Ok(Color::rgb(255, 0, 128)));
+        assert_eq!(
+            "rgba(255 128 0 / 50%)".parse(),
+            Ok(Color::rgba(255, 128, 0, 128))
+        );
+        assert_eq!(
+            "rgba(255, 128, 0, 0.25)".parse(),
+            Ok(Color::rgba(255, 128, 0, 64))
+        );
+        assert_eq!("rgb(300, -5, 0)".parse(), Ok(Color::rgb(255, 0, 0)));
+        for css in ["rgb(1,2)", "rgb(1, 2, 3", "rgb(a, 2, 3)", "rgbx(1, 2, 3)"] {
+            assert_eq!(
+                css.parse::<Color>(),
+                Err(ParseColorError::InvalidCss(css.to_string()))
+            );
+        }
+        assert_eq!(
+            "#12345".parse::<Color>(),
+            Err(ParseColorError::InvalidHex("#12345".to_string()))
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Color::rgb(255, 128, 0).to_string(), "#ff8000");
+        assert_eq!(Color::rgba(255, 128, 0, 0x80).to_string(), "#ff800080");
+        for color in [Color::ALICEBLUE, Color::rgba(1, 2, 3, 4)] {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let color = Color::rgba(0x12, 0x34, 0x56, 0x78);
+        assert_eq!(Color::from(0x78123456), color);
+        assert_eq!(ARGB::from(color), 0x78123456);
+        assert_eq!(String::from(color), "#12345678");
+        assert_eq!(Color::try_from("#12345678".to_string()), Ok(color));
+        assert_eq!(
+            Color::try_from("red".to_string()),
+            Err(ParseColorError::InvalidHex("red".to_string()))
+        );
+    }
+
+    #[test]
+    fn palette() {
+        assert_eq!(Color::LIGHTRED.into_argb(), 0xFFFC5454);
+        assert_eq!(Color::LIGHTMAGENTA.into_argb(), 0xFFFC54FC);
+        assert_eq!(Color::ALICEBLUE.into_argb(), 0xFFF0F8FF);
+        assert_eq!(Color::BLACK.into_argb(), 0xFF000000);
+        assert_eq!(Color::YELLOWGREEN.into_argb(), 0xFF9ACD32);
+        assert_eq!(Color::WHITE.into_argb(), WHITE.into_argb());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let colors = vec![Color::LIGHTRED, Color::rgba(255, 128, 0, 0x80)];
+        let json = serde_json::to_string(&colors).unwrap();
+        assert_eq!(json, r##"["#fc5454","#ff800080"]"##);
+        assert_eq!(serde_json::from_str::<Vec<Color>>(&json).unwrap(), colors);
+        assert_eq!(
+            serde_json::from_str::<Color>(r#""rgb(1, 2, 3)""#).unwrap(),
+            Color::rgb(1, 2, 3)
+        );
+        assert!(serde_json::from_str::<Color>(r##""#12345""##).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{ARGB, AlphaType, ImageView, ImageViewMut, Point, Rect};
+
+/// How a source pixel is combined with a destination pixel.
+///
+/// The Porter-Duff operators decide which parts of the source and the destination
+/// remain. The other modes mix the colors where both overlap and draw like
+/// `SrcOver` elsewhere, as defined by the W3C compositing specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Neither the source nor the destination.
+    Clear,
+    /// The source only.
+    Src,
+    /// The destination only.
+    Dst,
+    /// The source over the destination.
+    #[default]
+    SrcOver,
+    /// The destination over the source.
+    DstOver,
+    /// The source where the destination is.
+    SrcIn,
+    /// The destination where the source is.
+    DstIn,
+    /// The source where the destination is not.
+    SrcOut,
+    /// The destination where the source is not.
+    DstOut,
+    /// The source where the destination is, over the destination.
+    SrcAtop,
+    /// The destination where the source is, over the source.
+    DstAtop,
+    /// The source where the destination is not and the destination where the source is not.
+    Xor,
+    /// The sum of the source and the destination.
+    Plus,
+    /// The product of the colors, always darker.
+    Multiply,
+    /// The inverted product of the inverted colors, always lighter.
+    Screen,
+    /// `Multiply` on dark and `Screen` on light destination colors.
+    Overlay,
+    /// The darker of the colors.
+    Darken,
+    /// The lighter of the colors.
+    Lighten,
+    /// Brighten the destination to reflect the source.
+    ColorDodge,
+    /// Darken the destination to reflect the source.
+    ColorBurn,
+    /// `Multiply` on dark and `Screen` on light source colors.
+    HardLight,
+    /// A softer version of `HardLight`.
+    SoftLight,
+    /// The absolute difference of the colors.
+    Difference,
+    /// Like `Difference` with lower contrast.
+    Exclusion,
+}
+
+impl BlendMode {
+    /// The factors of the source and the destination of a Porter-Duff operator.
+    fn factors(&self, src_alpha: f32, dst_alpha: f32) -> Option<(f32, f32)> {
+        let (sa, da) = (src_alpha, dst_alpha);
+        Some(match self {
+            Self::Clear => (0.0, 0.0),
+            Self::Src => (1.0, 0.0),
+            Self::Dst => (0.0, 1.0),
+            Self::SrcOver => (1.0, 1.0 - sa),
+            Self::DstOver => (1.0 - da, 1.0),
+            Self::SrcIn => (da, 0.0),
+            Self::DstIn => (0.0, sa),
+            Self::SrcOut => (1.0 - da, 0.0),
+            Self::DstOut => (0.0, 1.0 - sa),
+            Self::SrcAtop => (da, 1.0 - sa),
+            Self::DstAtop => (1.0 - da, sa),
+            Self::Xor => (1.0 - da, 1.0 - sa),
+            Self::Plus => (1.0, 1.0),
+            _ => return None,
+        })
+    }
+
+    /// Mix a straight destination color channel with a straight source color channel.
+    fn mix(&self, b: f32, s: f32) -> f32 {
+        let multiply = |b: f32, s: f32| b * s;
+        let screen = |b: f32, s: f32| b + s - b * s;
+        let hard_light = |b: f32, s: f32| {
+            if s <= 0.5 {
+                multiply(b, 2.0 * s)
+            } else {
+                screen(b, 2.0 * s - 1.0)
+            }
+        };
+        match self {
+            Self::Multiply => multiply(b, s),
+            Self::Screen => screen(b, s),
+            Self::Overlay => hard_light(s, b),
+            Self::Darken => b.min(s),
+            Self::Lighten => b.max(s),
+            Self::ColorDodge if b <= 0.0 => 0.0,
+            Self::ColorDodge if s >= 1.0 => 1.0,
+            Self::ColorDodge => (b / (1.0 - s)).min(1.0),
+            Self::ColorBurn if b >= 1.0 => 1.0,
+            Self::ColorBurn if s <= 0.0 => 0.0,
+            Self::ColorBurn => 1.0 - ((1.0 - b) / s).min(1.0),
+            Self::HardLight => hard_light(b, s),
+            Self::SoftLight if s <= 0.5 => b - (1.0 - 2.0 * s) * b * (1.0 - b),
+            Self::SoftLight => {
+                let d = if b <= 0.25 {
+                    ((16.0 * b - 12.0) * b + 4.0) * b
+                } else {
+                    b.sqrt()
+                };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+            Self::Difference => (b - s).abs(),
+            Self::Exclusion => b + s - 2.0 * b * s,
+            _ => s,
+        }
+    }
+
+    /// Blend two premultiplied colors.
+    ///
+    /// # Parameters
+    /// - `dst`: The destination `[r, g, b, a]`, from `0.0` to `1.0`.
+    /// - `src`: The source `[r, g, b, a]`, from `0.0` to `1.0`.
+    ///
+    /// # Returns
+    /// The premultiplied result.
+    pub fn blend(&self, dst: [f32; 4], src: [f32; 4]) -> [f32; 4] {
+        let (sa, da) = (src[3], dst[3]);
+        let mut out = [0.0; 4];
+        if let Some((fs, fd)) = self.factors(sa, da) {
+            for c in 0..4 {
+                out[c] = src[c] * fs + dst[c] * fd;
+            }
+        } else {
+            let straight = |c: f32, a: f32| if a > 0.0 { c / a } else { 0.0 };
+            for c in 0..3 {
+                let mixed = self.mix(straight(dst[c], da), straight(src[c], sa));
+                out[c] = src[c] * (1.0 - da) + dst[c] * (1.0 - sa) + sa * da * mixed;
+            }
+            out[3] = sa + da * (1.0 - sa);
+        }
+        let alpha = out[3].clamp(0.0, 1.0);
+        [out[0], out[1], out[2], alpha].map(|c| c.clamp(0.0, alpha))
+    }
+}
+
+/// Convert an ARGB color to premultiplied `[r, g, b, a]` in `0.0..=1.0`.
+fn to_pixel(color: ARGB, alpha: AlphaType) -> [f32; 4] {
+    let [b, g, r, a] = color.to_le_bytes().map(|c| c as f32 / 255.0);
+    match alpha {
+        AlphaType::Straight => [r * a, g * a, b * a, a],
+        AlphaType::PreMultiplied => [r, g, b, a],
+    }
+}
+
+/// Convert a premultiplied pixel to an ARGB color.
+fn from_pixel(pixel: [f32; 4], alpha: AlphaType) -> ARGB {
+    let [r, g, b, a] = pixel;
+    let [r, g, b] = match alpha {
+        AlphaType::Straight if a > 0.0 => [r / a, g / a, b / a],
+        AlphaType::Straight => [0.0; 3],
+        AlphaType::PreMultiplied => [r, g, b],
+    };
+    let [r, g, b, a] = [r, g, b, a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    u32::from_le_bytes([b, g, r, a])
+}
+
+/// Combine a region of pixels with the pixels of another view.
+///
+/// # Parameters
+/// - `dst`: The destination pixels.
+/// - `src`: The source pixels.
+/// - `src_rect`: The region of the source.
+/// - `dst_point`: The position of the top-left corner of the region in the destination.
+/// - `mode`: How the pixels are combined.
+/// - `opacity`: Multiplied with the alpha of the source, from `0.0` to `1.0`.
+/// - `alpha`: How both views store their alpha.
+///
+/// # Note
+/// Parts of the region outside of either view are skipped.
+pub fn composite(
+    dst: &mut ImageViewMut,
+    src: &ImageView,
+    src_rect: Rect,
+    dst_point: Point,
+    mode: BlendMode,
+    opacity: f32,
+    alpha: AlphaType,
+) {
+    // Clip the region to the source and then to the destination.
+    let left = (-src_rect.x).max(-dst_point.x).max(0);
+    let top = (-src_rect.y).max(-dst_point.y).max(0);
+    let right = src_rect
+        .width
+        .min(src.width() as i32 - src_rect.x)
+        .min(dst.width() as i32 - dst_point.x);
+    let bottom = src_rect
+        .height
+        .min(src.height() as i32 - src_rect.y)
+        .min(dst.height() as i32 - dst_point.y);
+    if right <= left || bottom <= top {
+        return;
+    }
+    let opacity = opacity.clamp(0.0, 1.0);
+    for y in top..bottom {
+        let Some(src_row) = src.row((src_rect.y + y) as u32) else {
+            continue;
+        };
+        let Some(dst_row) = dst.row_mut((dst_point.y + y) as u32) else {
+            continue;
+        };
+        let src_row = &src_row[(src_rect.x + left) as usize..(src_rect.x + right) as usize];
+        let dst_row = &mut dst_row[(dst_point.x + left) as usize..(dst_point.x + right) as usize];
+        for (d, &s) in dst_row.iter_mut().zip(src_row) {
+            let s = to_pixel(s, alpha).map(|c| c * opacity);
+            *d = from_pixel(mode.blend(to_pixel(*d, alpha), s), alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 4], expected: [f32; 4], mode: BlendMode) {
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(a, e)| (a - e).abs() < 1e-5);
+        assert!(close, "{mode:?}: {actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn porter_duff_table() {
+        // Premultiplied red with alpha 0.6 over blue with alpha 0.4.
+        let src = [0.6, 0.0, 0.0, 0.6];
+        let dst = [0.0, 0.0, 0.4, 0.4];
+        let table = [
+            (BlendMode::Clear, [0.0, 0.0, 0.0, 0.0]),
+            (BlendMode::Src, [0.6, 0.0, 0.0, 0.6]),
+            (BlendMode::Dst, [0.0, 0.0, 0.4, 0.4]),
+            (BlendMode::SrcOver, [0.6, 0.0, 0.16, 0.76]),
+            (BlendMode::DstOver, [0.36, 0.0, 0.4, 0.76]),
+            (BlendMode::SrcIn, [0.24, 0.0, 0.0, 0.24]),
+            (BlendMode::DstIn, [0.0, 0.0, 0.24, 0.24]),
+            (BlendMode::SrcOut, [0.36, 0.0, 0.0, 0.36]),
+            (BlendMode::DstOut, [0.0, 0.0, 0.16, 0.16]),
+            (BlendMode::SrcAtop, [0.24, 0.0, 0.16, 0.4]),
+            (BlendMode::DstAtop, [0.36, 0.0, 0.24, 0.6]),
+            (BlendMode::Xor, [0.36, 0.0, 0.16, 0.52]),
+            (BlendMode::Plus, [0.6, 0.0, 0.4, 1.0]),
+        ];
+        for (mode, expected) in table {
+            assert_close(mode.blend(dst, src), expected, mode);
+        }
+        // `Plus` saturates.
+        assert_close(
+            BlendMode::Plus.blend([0.8, 0.0, 0.0, 0.8], [0.6, 0.0, 0.0, 0.6]),
+            [1.0, 0.0, 0.0, 1.0],
+            BlendMode::Plus,
+        );
+    }
+
+    #[test]
+    fn separable_modes() {
+        let gray = |v: f32| [v, v, v, 1.0];
+        let table = [
+            (BlendMode::Multiply, 0.2 * 0.6),
+            (BlendMode::Screen, 0.2 + 0.6 - 0.2 * 0.6),
+            (BlendMode::Overlay, 2.0 * 0.2 * 0.6),
+            (BlendMode::Darken, 0.2),
+            (BlendMode::Lighten, 0.6),
+            (BlendMode::ColorDodge, 0.5),
+            (BlendMode::ColorBurn, 0.0),
+            (BlendMode::HardLight, 1.0 - 2.0 * 0.8 * 0.4),
+            (BlendMode::Difference, 0.4),
+            (BlendMode::Exclusion, 0.2 + 0.6 - 2.0 * 0.2 * 0.6),
+        ];
+        for (mode, expected) in table {
+            assert_close(mode.blend(gray(0.2), gray(0.6)), gray(expected), mode);
+            // Without overlap, the modes draw like `SrcOver`.
+            assert_close(mode.blend([0.0; 4], gray(0.6)), gray(0.6), mode);
+            assert_close(mode.blend(gray(0.2), [0.0; 4]), gray(0.2), mode);
+        }
+        // Half-transparent source: the mixed color only where both overlap.
+        let mixed = BlendMode::Multiply.blend(gray(0.2), [0.3, 0.3, 0.3, 0.5]);
+        assert_close(
+            mixed,
+            gray(0.5 * 0.2 + 0.5 * 0.2 * 0.6),
+            BlendMode::Multiply,
+        );
+    }
+
+    #[test]
+    fn composite_clips_and_converts() {
+        let src = [0xFFFFFFFF, 0xFFFF0000, 0x80FFFFFF, 0x00000000];
+        let mut pixels = [0xFF000000; 9];
+        let src_rect = Rect {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+        composite(
+            &mut ImageViewMut::new(&mut pixels, 3, 3, 3),
+            &ImageView::new(&src, 2, 2, 2),
+            src_rect,
+            Point { x: -1, y: 2 },
+            BlendMode::Src,
+            1.0,
+            AlphaType::Straight,
+        );
+        let mut expected = [0xFF000000; 9];
+        expected[6] = 0xFFFF0000;
+        assert_eq!(pixels, expected);
+
+        let mut pixels = [0xFF000000; 4];
+        composite(
+            &mut ImageViewMut::new(&mut pixels, 2, 2, 2),
+            &ImageView::new(&src, 2, 2, 2),
+            src_rect,
+            Point { x: 0, y: 0 },
+            BlendMode::SrcOver,
+            0.5,
+            AlphaType::Straight,
+        );
+        assert_eq!(pixels, [0xFF808080, 0xFF800000, 0xFF404040, 0xFF000000]);
+    }
+}
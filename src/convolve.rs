@@ -0,0 +1,542 @@
+use std::thread;
+
+use mats::Mat;
+
+#[cfg(feature = "ege")]
+use crate::Image;
+use crate::image::{premultiply, unpremultiply};
+use crate::{ARGB, AlphaType, ApplyMask, ImageView, ImageViewMut, TemplateMode};
+
+/// Map a coordinate outside of `0..len` according to the edge mode.
+///
+/// Returns `None` if the coordinate has no corresponding pixel.
+pub(crate) fn edge_index(i: i32, len: u32, mode: TemplateMode) -> Option<u32> {
+    let len = len as i32;
+    if (0..len).contains(&i) {
+        return Some(i as u32);
+    }
+    if len == 0 {
+        return None;
+    }
+    match mode {
+        TemplateMode::Clamp => Some(i.clamp(0, len - 1) as u32),
+        TemplateMode::Wrap => Some(i.rem_euclid(len) as u32),
+        TemplateMode::Mirror if len == 1 => Some(0),
+        TemplateMode::Mirror => {
+            let period = 2 * (len - 1);
+            let i = i.rem_euclid(period);
+            Some(if i < len { i } else { period - i } as u32)
+        }
+        _ => None,
+    }
+}
+
+/// A convolution kernel.
+///
+/// The kernel is applied as a correlation: the weight at `(x, y)` is multiplied
+/// with the pixel at the offset `(x - width / 2, y - height / 2)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kernel {
+    width: usize,
+    height: usize,
+    weights: Vec<f32>,
+    /// The horizontal and vertical factors if the kernel is separable.
+    factors: Option<(Vec<f32>, Vec<f32>)>,
+}
+
+impl Kernel {
+    /// Create a kernel.
+    ///
+    /// # Parameters
+    /// - `width`: The width of the kernel, must be odd.
+    /// - `height`: The height of the kernel, must be odd.
+    /// - `weights`: The weights, row by row.
+    ///
+    /// # Panics
+    /// Panics if a size is even or the number of weights does not match.
+    ///
+    /// # Note
+    /// Separable kernels are detected and applied as two one-dimensional passes.
+    pub fn new(width: usize, height: usize, weights: Vec<f32>) -> Self {
+        assert!(
+            width % 2 == 1 && height % 2 == 1,
+            "The kernel size must be odd."
+        );
+        assert_eq!(weights.len(), width * height, "The kernel size is wrong.");
+        let factors = Self::separate(width, height, &weights);
+        Self {
+            width,
+            height,
+            weights,
+            factors,
+        }
+    }
+
+    /// Create a separable kernel from its horizontal and vertical factors.
+    ///
+    /// # Panics
+    /// Panics if a factor has an even length.
+    pub fn separable(horizontal: &[f32], vertical: &[f32]) -> Self {
+        let weights = vertical
+            .iter()
+            .flat_map(|v| horizontal.iter().map(move |h| h * v))
+            .collect();
+        let mut kernel = Self::new(horizontal.len(), vertical.len(), weights);
+        kernel.factors = Some((horizontal.to_vec(), vertical.to_vec()));
+        kernel
+    }
+
+    /// Create a kernel from a template mask, see `Image::template`.
+    pub fn from_mat<const N: usize>(mask: &Mat<f32, N, N>) -> Self {
+        let weights = (0..N)
+            .flat_map(|m| (0..N).map(move |n| mask[m][n]))
+            .collect();
+        Self::new(N, N, weights)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The weights, row by row.
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    /// Checks if the kernel is applied as two one-dimensional passes.
+    pub fn is_separable(&self) -> bool {
+        self.factors.is_some()
+    }
+
+    /// Split a rank one kernel into its horizontal and vertical factors.
+    fn separate(width: usize, height: usize, weights: &[f32]) -> Option<(Vec<f32>, Vec<f32>)> {
+        let (pivot, &max) = weights
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?;
+        if max == 0.0 {
+            return None;
+        }
+        let (py, px) = (pivot / width, pivot % width);
+        let horizontal = weights[py * width..(py + 1) * width].to_vec();
+        let vertical = (0..height)
+            .map(|y| weights[y * width + px] / max)
+            .collect::<Vec<_>>();
+        let tolerance = max.abs() * 1e-5;
+        let separable = (0..height).all(|y| {
+            (0..width)
+                .all(|x| (weights[y * width + x] - vertical[y] * horizontal[x]).abs() <= tolerance)
+        });
+        separable.then_some((horizontal, vertical))
+    }
+}
+
+/// Options of a convolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvolveOptions {
+    /// The channels to convolve. The other channels are kept.
+    pub channels: ApplyMask,
+    /// The handling of pixels outside of the image.
+    ///
+    /// `White` and `Black` are opaque. `DotCare` keeps the pixels near the edges.
+    pub edge: TemplateMode,
+    /// How the pixels store their alpha.
+    ///
+    /// Straight alpha pixels are premultiplied while the alpha channel is
+    /// convolved, so transparent pixels do not bleed their color.
+    pub alpha: AlphaType,
+    /// The number of threads, `0` uses the available parallelism.
+    pub threads: usize,
+}
+
+impl Default for ConvolveOptions {
+    fn default() -> Self {
+        Self {
+            channels: ApplyMask::All,
+            edge: TemplateMode::Clamp,
+            alpha: AlphaType::Straight,
+            threads: 0,
+        }
+    }
+}
+
+type Pixel = [f32; 4];
+
+/// Split a buffer of rows into bands and process them in parallel.
+pub(crate) fn par_rows<T: Send>(
+    out: &mut [T],
+    width: usize,
+    threads: usize,
+    process: impl Fn(usize, &mut [T]) + Sync,
+) {
+    if width == 0 || out.is_empty() {
+        return;
+    }
+    let rows = out.len() / width;
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    // Small images are not worth spawning threads for.
+    let threads = threads.min(out.len() / 16384 + 1).min(rows).max(1);
+    if threads == 1 {
+        for (y, row) in out.chunks_mut(width).enumerate() {
+            process(y, row);
+        }
+        return;
+    }
+    let band = rows.div_ceil(threads);
+    thread::scope(|scope| {
+        for (i, chunk) in out.chunks_mut(band * width).enumerate() {
+            let process = &process;
+            scope.spawn(move || {
+                for (y, row) in chunk.chunks_mut(width).enumerate() {
+                    process(i * band + y, row);
+                }
+            });
+        }
+    });
+}
+
+fn to_pixel(color: ARGB) -> Pixel {
+    let [b, g, r, a] = color.to_le_bytes();
+    [r as f32, g as f32, b as f32, a as f32]
+}
+
+fn from_pixel(pixel: Pixel) -> ARGB {
+    let [r, g, b, a] = pixel.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    u32::from_le_bytes([b, g, r, a])
+}
+
+/// Convolve pixels into a view of the same size.
+///
+/// # Parameters
+/// - `src`: The source pixels.
+/// - `dst`: The destination pixels.
+/// - `kernel`: The convolution kernel.
+/// - `options`: The channels, edge mode, alpha type and number of threads.
+///
+/// # Panics
+/// Panics if the sizes of the views differ.
+pub fn convolve(
+    src: &ImageView,
+    dst: &mut ImageViewMut,
+    kernel: &Kernel,
+    options: &ConvolveOptions,
+) {
+    assert_eq!(
+        (src.width(), src.height()),
+        (dst.width(), dst.height()),
+        "The view sizes differ."
+    );
+    let (width, height) = (src.width(), src.height());
+    let (w, h) = (width as usize, height as usize);
+    let premultiplied =
+        options.alpha == AlphaType::Straight && options.channels.contains(ApplyMask::Alpha);
+    let source = src.to_vec();
+    let input = source
+        .iter()
+        .map(|&c| to_pixel(if premultiplied { premultiply(c) } else { c }))
+        .collect::<Vec<_>>();
+    let (rx, ry) = ((kernel.width / 2) as i32, (kernel.height / 2) as i32);
+    let edge = options.edge;
+    let mut output = vec![None::<Pixel>; w * h];
+
+    match &kernel.factors {
+        Some((horizontal, vertical))
+            if matches!(
+                edge,
+                TemplateMode::Clamp | TemplateMode::Wrap | TemplateMode::Mirror
+            ) =>
+        {
+            let mut temp = vec![[0.0; 4]; w * h];
+            par_rows(&mut temp, w, options.threads, |y, row| {
+                for (x, out) in row.iter_mut().enumerate() {
+                    for (k, weight) in horizontal.iter().enumerate() {
+                        let sx = edge_index(x as i32 + k as i32 - rx, width, edge).unwrap_or(0);
+                        let p = input[y * w + sx as usize];
+                        (0..4).for_each(|c| out[c] += weight * p[c]);
+                    }
+                }
+            });
+            par_rows(&mut output, w, options.threads, |y, row| {
+                for (x, out) in row.iter_mut().enumerate() {
+                    let mut sum = [0.0; 4];
+                    for (k, weight) in vertical.iter().enumerate() {
+                        let sy = edge_index(y as i32 + k as i32 - ry, height, edge).unwrap_or(0);
+                        let p = temp[sy as usize * w + x];
+                        (0..4).for_each(|c| sum[c] += weight * p[c]);
+                    }
+                    *out = Some(sum);
+                }
+            });
+        }
+        _ => {
+            let outside = match edge {
+                TemplateMode::White => Some([255.0; 4]),
+                TemplateMode::Black => Some([0.0, 0.0, 0.0, 255.0]),
+                _ => None,
+            };
+            par_rows(&mut output, w, options.threads, |y, row| {
+                'pixel: for (x, out) in row.iter_mut().enumerate() {
+                    let mut sum = [0.0; 4];
+                    for ky in 0..kernel.height {
+                        let sy = edge_index(y as i32 + ky as i32 - ry, height, edge);
+                        for kx in 0..kernel.width {
+                            let sx = edge_index(x as i32 + kx as i32 - rx, width, edge);
+                            let p = match (sx, sy, outside) {
+                                (Some(sx), Some(sy), _) => input[sy as usize * w + sx as usize],
+                                (.., Some(color)) => color,
+                                _ if edge == TemplateMode::Ignore => continue,
+                                _ => continue 'pixel,
+                            };
+                            let weight = kernel.weights[ky * kernel.width + kx];
+                            (0..4).for_each(|c| sum[c] += weight * p[c]);
+                        }
+                    }
+                    *out = Some(sum);
+                }
+            });
+        }
+    }
+
+    let masks = [
+        ApplyMask::Red,
+        ApplyMask::Green,
+        ApplyMask::Blue,
+        ApplyMask::Alpha,
+    ];
+    for (y, row) in dst.rows_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let original = source[y * w + x];
+            let Some(sum) = output[y * w + x] else {
+                *pixel = original;
+                continue;
+            };
+            let base = to_pixel(if premultiplied {
+                premultiply(original)
+            } else {
+                original
+            });
+            let mut result = [0.0; 4];
+            for c in 0..4 {
+                result[c] = if options.channels.contains(masks[c]) {
+                    sum[c]
+                } else {
+                    base[c]
+                };
+            }
+            // Premultiplied colors can not exceed the alpha.
+            if premultiplied || options.alpha == AlphaType::PreMultiplied {
+                let alpha = result[3].round().clamp(0.0, 255.0);
+                (0..3).for_each(|c| result[c] = result[c].min(alpha));
+            }
+            let color = from_pixel(result);
+            *pixel = if premultiplied {
+                unpremultiply(color)
+            } else {
+                color
+            };
+        }
+    }
+}
+
+impl ImageViewMut<'_> {
+    /// Convolve the pixels of the view in place.
+    ///
+    /// # Note
+    /// See `convolve`. Pixels outside of the view are treated as non-existent.
+    pub fn convolve(&mut self, kernel: &Kernel, options: &ConvolveOptions) {
+        let copy = self.as_view().to_vec();
+        let src = ImageView::new(&copy, self.width() as usize, self.width(), self.height());
+        convolve(&src, self, kernel, options);
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Image {
+    /// Convolve the image in place.
+    ///
+    /// # Parameters
+    /// - `kernel`: The convolution kernel.
+    /// - `options`: The channels, edge mode, alpha type and number of threads.
+    ///
+    /// # Note
+    /// Unlike `template`, separable kernels are applied in two passes, the rows are
+    /// processed in parallel and the alpha channel can be convolved.
+    pub fn convolve(&mut self, kernel: &Kernel, options: &ConvolveOptions) {
+        self.as_view_mut().convolve(kernel, options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo-random pixels, so the tests do not depend on `rand`.
+    fn noise(len: usize, opaque: bool) -> Vec<ARGB> {
+        let mut state = 0x2545F491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                if opaque { state | 0xFF000000 } else { state }
+            })
+            .collect()
+    }
+
+    fn run(src: &[ARGB], width: u32, kernel: &Kernel, options: &ConvolveOptions) -> Vec<ARGB> {
+        let height = src.len() as u32 / width;
+        let mut dst = vec![0; src.len()];
+        convolve(
+            &ImageView::new(src, width as usize, width, height),
+            &mut ImageViewMut::new(&mut dst, width as usize, width, height),
+            kernel,
+            options,
+        );
+        dst
+    }
+
+    fn assert_close(a: &[ARGB], b: &[ARGB]) {
+        for (i, (a, b)) in a.iter().zip(b).enumerate() {
+            let close = a
+                .to_le_bytes()
+                .iter()
+                .zip(b.to_le_bytes())
+                .all(|(a, b)| a.abs_diff(b) <= 1);
+            assert!(close, "pixel {i}: {a:08X} != {b:08X}");
+        }
+    }
+
+    #[test]
+    fn edge_modes() {
+        let index = |i, mode| edge_index(i, 4, mode);
+        assert_eq!(index(2, TemplateMode::Ignore), Some(2));
+        assert_eq!(index(-1, TemplateMode::Ignore), None);
+        assert_eq!(index(-2, TemplateMode::Clamp), Some(0));
+        assert_eq!(index(5, TemplateMode::Clamp), Some(3));
+        assert_eq!(index(-1, TemplateMode::Wrap), Some(3));
+        assert_eq!(index(9, TemplateMode::Wrap), Some(1));
+        assert_eq!(index(-1, TemplateMode::Mirror), Some(1));
+        assert_eq!(index(4, TemplateMode::Mirror), Some(2));
+        assert_eq!(index(7, TemplateMode::Mirror), Some(1));
+        assert_eq!(edge_index(-3, 1, TemplateMode::Mirror), Some(0));
+        assert_eq!(edge_index(0, 0, TemplateMode::Clamp), None);
+    }
+
+    #[test]
+    fn separable_detection() {
+        let gaussian = Kernel::new(3, 3, vec![1.0, 2.0, 1.0, 2.0, 4.0, 2.0, 1.0, 2.0, 1.0]);
+        assert!(gaussian.is_separable());
+        let laplacian = Kernel::new(3, 3, vec![0.0, 1.0, 0.0, 1.0, -4.0, 1.0, 0.0, 1.0, 0.0]);
+        assert!(!laplacian.is_separable());
+        assert!(!Kernel::new(1, 1, vec![0.0]).is_separable());
+
+        let kernel = Kernel::separable(&[1.0, 2.0, 3.0], &[1.0, -1.0, 0.5]);
+        assert_eq!((kernel.width(), kernel.height()), (3, 3));
+        assert_eq!(
+            kernel.weights(),
+            [1.0, 2.0, 3.0, -1.0, -2.0, -3.0, 0.5, 1.0, 1.5]
+        );
+    }
+
+    #[test]
+    fn separable_matches_direct() {
+        let (width, height) = (150u32, 120u32);
+        let horizontal = [1.0, 4.0, 6.0, 4.0, 1.0].map(|w| w / 16.0);
+        let vertical = [1.0, 2.0, 1.0].map(|w| w / 4.0);
+        let separable = Kernel::separable(&horizontal, &vertical);
+        let direct = Kernel {
+            factors: None,
+            ..separable.clone()
+        };
+        for opaque in [true, false] {
+            let src = noise((width * height) as usize, opaque);
+            for edge in [
+                TemplateMode::Clamp,
+                TemplateMode::Wrap,
+                TemplateMode::Mirror,
+            ] {
+                for alpha in [AlphaType::Straight, AlphaType::PreMultiplied] {
+                    let options = ConvolveOptions {
+                        edge,
+                        alpha,
+                        threads: 1,
+                        ..ConvolveOptions::default()
+                    };
+                    let expected = run(&src, width, &direct, &options);
+                    assert_close(&run(&src, width, &separable, &options), &expected);
+                    // The rows are split among the threads.
+                    let parallel = ConvolveOptions {
+                        threads: 4,
+                        ..options
+                    };
+                    assert_eq!(
+                        run(&src, width, &separable, &parallel),
+                        run(&src, width, &separable, &options)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn identity_and_channels() {
+        let src = noise(7 * 5, false);
+        let identity = Kernel::new(3, 1, vec![0.0, 1.0, 0.0]);
+        for edge in [
+            TemplateMode::Ignore,
+            TemplateMode::Black,
+            TemplateMode::Wrap,
+        ] {
+            let options = ConvolveOptions {
+                edge,
+                alpha: AlphaType::PreMultiplied,
+                ..ConvolveOptions::default()
+            };
+            let src = src.iter().map(|&c| premultiply(c)).collect::<Vec<_>>();
+            assert_eq!(run(&src, 7, &identity, &options), src);
+        }
+
+        let zero = Kernel::new(1, 1, vec![0.0]);
+        let options = ConvolveOptions {
+            channels: ApplyMask::Rgb,
+            alpha: AlphaType::PreMultiplied,
+            ..ConvolveOptions::default()
+        };
+        let dst = run(&src, 7, &zero, &options);
+        assert!(dst.iter().zip(&src).all(|(d, s)| *d == s & 0xFF000000));
+    }
+
+    #[test]
+    fn outside_pixels() {
+        let src = vec![0xFF102030; 3];
+        let sum = Kernel::new(3, 1, vec![1.0, 1.0, 1.0]);
+        let options = |edge| ConvolveOptions {
+            edge,
+            channels: ApplyMask::Rgb,
+            ..ConvolveOptions::default()
+        };
+        assert_eq!(
+            run(&src, 3, &sum, &options(TemplateMode::DotCare)),
+            [0xFF102030, 0xFF306090, 0xFF102030]
+        );
+        assert_eq!(
+            run(&src, 3, &sum, &options(TemplateMode::Ignore)),
+            [0xFF204060, 0xFF306090, 0xFF204060]
+        );
+        assert_eq!(
+            run(&src, 3, &sum, &options(TemplateMode::Black)),
+            [0xFF204060, 0xFF306090, 0xFF204060]
+        );
+        assert_eq!(
+            run(&src, 3, &sum, &options(TemplateMode::White)),
+            [0xFFFFFFFF, 0xFF306090, 0xFFFFFFFF]
+        );
+    }
+}
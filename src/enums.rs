@@ -1,6 +1,6 @@
 use std::ops::BitOr;
 
-use xege_ffi::*;
+use crate::sys::{self, *};
 
 /// Fill pattern enumeration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -68,12 +68,12 @@ impl TryFrom<i32> for LineStyle {
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            xege_ffi::ege_line_styles_SOLID_LINE => Ok(Self::Solid),
-            xege_ffi::ege_line_styles_CENTER_LINE => Ok(Self::Center),
-            xege_ffi::ege_line_styles_DOTTED_LINE => Ok(Self::Dotted),
-            xege_ffi::ege_line_styles_DASHED_LINE => Ok(Self::Dashed),
-            xege_ffi::ege_line_styles_NULL_LINE => Ok(Self::Null),
-            xege_ffi::ege_line_styles_USERBIT_LINE => Ok(Self::UserDef(0)),
+            sys::ege_line_styles_SOLID_LINE => Ok(Self::Solid),
+            sys::ege_line_styles_CENTER_LINE => Ok(Self::Center),
+            sys::ege_line_styles_DOTTED_LINE => Ok(Self::Dotted),
+            sys::ege_line_styles_DASHED_LINE => Ok(Self::Dashed),
+            sys::ege_line_styles_NULL_LINE => Ok(Self::Null),
+            sys::ege_line_styles_USERBIT_LINE => Ok(Self::UserDef(0)),
             _ => Err(()),
         }
     }
@@ -92,9 +92,9 @@ impl TryFrom<i32> for LineCap {
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            xege_ffi::ege_line_cap_type_LINECAP_FLAT => Ok(Self::Flat),
-            xege_ffi::ege_line_cap_type_LINECAP_SQUARE => Ok(Self::Square),
-            xege_ffi::ege_line_cap_type_LINECAP_ROUND => Ok(Self::Round),
+            sys::ege_line_cap_type_LINECAP_FLAT => Ok(Self::Flat),
+            sys::ege_line_cap_type_LINECAP_SQUARE => Ok(Self::Square),
+            sys::ege_line_cap_type_LINECAP_ROUND => Ok(Self::Round),
             _ => Err(()),
         }
     }
@@ -113,9 +113,9 @@ impl TryFrom<i32> for LineJoin {
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            xege_ffi::ege_line_join_type_LINEJOIN_MITER => Ok(Self::Miter),
-            xege_ffi::ege_line_join_type_LINEJOIN_ROUND => Ok(Self::Round),
-            xege_ffi::ege_line_join_type_LINEJOIN_BEVEL => Ok(Self::Bevel),
+            sys::ege_line_join_type_LINEJOIN_MITER => Ok(Self::Miter),
+            sys::ege_line_join_type_LINEJOIN_ROUND => Ok(Self::Round),
+            sys::ege_line_join_type_LINEJOIN_BEVEL => Ok(Self::Bevel),
             _ => Err(()),
         }
     }
@@ -447,6 +447,7 @@ pub struct Font {
     pub facename: String,
 }
 
+#[cfg(feature = "ege")]
 impl Into<LOGFONTW> for Font {
     fn into(self) -> LOGFONTW {
         let mut buff = [0u16; 32];
@@ -477,6 +478,7 @@ impl Into<LOGFONTW> for Font {
     }
 }
 
+#[cfg(feature = "ege")]
 impl TryFrom<LOGFONTW> for Font {
     type Error = ();
 
@@ -1094,6 +1096,18 @@ impl Into<u32> for Key {
     }
 }
 
+/// Path fill mode.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PathFillMode {
+    /// Default fill mode.
+    Default = ege_fill_mode_FILLMODE_DEFAULT,
+    /// Winding fill mode.
+    Winding = ege_fill_mode_FILLMODE_WINDING,
+    /// Alternate fill mode.
+    Alternate = ege_fill_mode_FILLMODE_ALTERNATE,
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum RenderMode {
@@ -1108,6 +1122,6 @@ pub enum RenderMode {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum BkMode {
     #[default]
-    Opaque = xege_ffi::OPAQUE,
-    Transparent = xege_ffi::TRANSPARENT,
+    Opaque = sys::OPAQUE,
+    Transparent = sys::TRANSPARENT,
 }
\ No newline at end of file
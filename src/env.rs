@@ -1,14 +1,30 @@
-use std::ptr::null;
+use std::collections::VecDeque;
+#[cfg(any(feature = "ege", feature = "software"))]
 use std::ptr::null_mut;
+#[cfg(feature = "ege")]
 use std::sync::Arc;
+#[cfg(feature = "ege")]
 use std::sync::Mutex;
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
-use crate::RenderMode;
-use crate::graphics::DrawableDevice;
+use crate::backend;
+#[cfg(feature = "software")]
+use crate::Canvas;
+use crate::DrawableDevice;
+#[cfg(any(feature = "ege", feature = "software"))]
+use crate::GraphicsEnvironment;
+use crate::Event;
+#[cfg(feature = "ege")]
+use crate::event::WindowState;
 use crate::window::Window;
+#[cfg(feature = "ege")]
+use crate::backend::DeviceState;
+#[cfg(feature = "ege")]
+use crate::{EgeDevice, Image, ImageError, RenderMode};
 
 /// The initialization options.
+#[cfg(feature = "ege")]
 #[bitmask_enum::bitmask(i32)]
 pub enum Init {
     /// Default initialization mode.
@@ -45,17 +61,36 @@ pub enum Init {
 }
 
 /// Graphics environment.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct XEGE {
+///
+/// # Note
+/// The environment draws on its device `D`: the window for `initgraph`, a software
+/// `Canvas` for `headless`.
+#[derive(Debug)]
+pub struct XEGE<D = Screen> {
     pub window: Window,
+    pub(crate) device: D,
+    pub(crate) events: VecDeque<Event>,
+}
+
+/// The window of the environment created by `initgraph`, drawn on by EGE.
+#[derive(Debug)]
+pub struct Screen {
+    #[cfg(feature = "ege")]
+    pub(crate) state: WindowState,
+    #[cfg(feature = "ege")]
+    drawing: DeviceState,
+    #[cfg(not(feature = "ege"))]
+    _private: (),
 }
 
+#[cfg(feature = "ege")]
 #[derive(Debug, thiserror::Error)]
 pub enum XEGEError {
     #[error("XEGE is already initialized.")]
     Initialized,
 }
 
+#[cfg(feature = "ege")]
 lazy_static::lazy_static! {
     static ref INIT_FLAG: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
 }
@@ -70,6 +105,7 @@ lazy_static::lazy_static! {
 /// # Errors
 ///
 /// Returns an error if XEGE is already initialized.
+#[cfg(feature = "ege")]
 pub fn initgraph(width: i32, height: i32, mode: Init) -> Result<XEGE, XEGEError> {
     {
         let mut flag = INIT_FLAG.lock().unwrap();
@@ -85,11 +121,41 @@ pub fn initgraph(width: i32, height: i32, mode: Init) -> Result<XEGE, XEGEError>
         ege_initgraph(width, height, mode.bits | ege_initmode_flag_INIT_UNICODE);
     }
     Ok(XEGE {
-        window: Window(null_mut()),
+        window: Window(null_mut(), false),
+        device: Screen {
+            state: WindowState::default(),
+            drawing: DeviceState::default(),
+        },
+        events: VecDeque::new(),
     })
 }
 
-impl Drop for XEGE {
+/// Initializes a headless graphics environment.
+///
+/// # Parameters
+/// * `width` - The width of the framebuffer.
+/// * `height` - The height of the framebuffer.
+///
+/// # Note
+/// No window is created and EGE is not initialized. Drawing goes to a software
+/// `Canvas` in memory, see `Canvas` for the supported operations. Use `XEGE::save`
+/// to dump the framebuffer. The `window` of a headless environment has no input
+/// of its own, it only replays recordings, see `Window::start_replay`.
+/// Any number of headless environments may exist at the same time, also next to
+/// the one created by `initgraph`.
+#[cfg(feature = "software")]
+pub fn headless(width: u32, height: u32) -> XEGE<Canvas> {
+    let mut canvas = Canvas::new(width, height);
+    canvas.clear();
+    XEGE {
+        window: Window(null_mut(), true),
+        device: canvas,
+        events: VecDeque::new(),
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Drop for Screen {
     fn drop(&mut self) {
         {
             let mut flag = INIT_FLAG.lock().unwrap();
@@ -99,21 +165,13 @@ impl Drop for XEGE {
     }
 }
 
-impl XEGE {
+impl<D> XEGE<D> {
     /// Closes the graphics environment.
     pub fn closegraph(self) {}
+}
 
-    /// Checks if the graphics environment is running.
-    ///
-    /// # Return
-    /// `true` if the graphics environment is running, `false` otherwise.
-    ///
-    /// # Note
-    /// This function is only valid when `Init::NoForceExit` is present in initialization mode.
-    pub fn is_run(&self) -> bool {
-        unsafe { ege_is_run() }
-    }
-
+#[cfg(feature = "ege")]
+impl XEGE {
     /// Sets the window caption.
     ///
     /// # Parameters
@@ -145,14 +203,132 @@ impl XEGE {
     pub fn setrendermode(mode: RenderMode) {
         unsafe { ege_setrendermode(mode as i32) };
     }
+
+    /// Save the framebuffer to a file.
+    ///
+    /// # Parameters
+    /// * `path` - The filename of the image.
+    /// * `with_alpha` - Whether to save the alpha channel.
+    ///
+    /// # Note
+    /// The content is captured with `Image::from_window` and saved with `Image::save`,
+    /// which only supports BMP and PNG formats.
+    pub fn save<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        with_alpha: bool,
+    ) -> Result<(), ImageError> {
+        let (width, height) = (self.getwidth() as i32, self.getheight() as i32);
+        Image::from_window(0, 0, width, height)?.save(path, with_alpha)
+    }
+}
+
+#[cfg(feature = "software")]
+impl XEGE<Canvas> {
+    /// Save the framebuffer to a file.
+    ///
+    /// # Parameters
+    /// * `path` - The filename of the image.
+    /// * `with_alpha` - Whether to save the alpha channel.
+    ///
+    /// # Note
+    /// The content is saved with `Canvas::save`.
+    pub fn save<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        with_alpha: bool,
+    ) -> Result<(), crate::ImageError> {
+        self.device.save(path, with_alpha)
+    }
 }
 
-impl DrawableDevice for XEGE {
+impl<D: DrawableDevice> DrawableDevice for XEGE<D> {
+    fn backend(&self) -> &dyn backend::Backend {
+        self.device.backend()
+    }
+
+    fn backend_mut(&mut self) -> &mut dyn backend::Backend {
+        self.device.backend_mut()
+    }
+}
+
+#[cfg(feature = "ege")]
+impl<D: EgeDevice> EgeDevice for XEGE<D> {
+    fn mut_ptr(&mut self) -> *mut ege_IMAGE {
+        self.device.mut_ptr()
+    }
+
+    fn const_ptr(&self) -> *const ege_IMAGE {
+        self.device.const_ptr()
+    }
+
+    fn state(&self) -> &DeviceState {
+        self.device.state()
+    }
+
+    fn state_mut(&mut self) -> &mut DeviceState {
+        self.device.state_mut()
+    }
+}
+
+#[cfg(feature = "ege")]
+impl DrawableDevice for Screen {
+    fn backend(&self) -> &dyn backend::Backend {
+        self
+    }
+
+    fn backend_mut(&mut self) -> &mut dyn backend::Backend {
+        self
+    }
+}
+
+/// EGE draws on the window when no image is given.
+#[cfg(feature = "ege")]
+impl EgeDevice for Screen {
     fn mut_ptr(&mut self) -> *mut ege_IMAGE {
         null_mut()
     }
 
     fn const_ptr(&self) -> *const ege_IMAGE {
-        null()
+        std::ptr::null()
+    }
+
+    fn state(&self) -> &DeviceState {
+        &self.drawing
+    }
+
+    fn state_mut(&mut self) -> &mut DeviceState {
+        &mut self.drawing
+    }
+}
+
+#[cfg(all(test, feature = "software"))]
+mod tests {
+    use super::*;
+    use crate::Draw;
+
+    #[test]
+    fn headless_framebuffer_dump() {
+        let mut xege = headless(4, 3);
+        assert!(xege.is_headless() && xege.is_run());
+        assert_eq!((xege.getwidth(), xege.getheight()), (4, 3));
+        xege.setfillcolor(0xFFFF0000u32);
+        xege.bar(1, 1, 3, 2);
+
+        let path = std::env::temp_dir().join(format!("xege-headless-{}.png", std::process::id()));
+        xege.save(&path, true).unwrap();
+        let image = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(2, 1).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 2).0, [0, 0, 0, 255]);
+
+        let missing = std::env::temp_dir()
+            .join("xege-missing-dir")
+            .join("out.png");
+        let error = xege.save(&missing, false).unwrap_err();
+        assert_eq!(error.path(), Some(missing.as_path()));
     }
 }
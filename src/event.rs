@@ -0,0 +1,430 @@
+#[cfg(any(feature = "ege", feature = "software"))]
+use std::collections::VecDeque;
+use std::ops::ControlFlow;
+#[cfg(feature = "software")]
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "ege")]
+use xege_ffi::GetForegroundWindow;
+
+#[cfg(feature = "software")]
+use crate::Canvas;
+#[cfg(feature = "ege")]
+use crate::Screen;
+use crate::backend::EventSource;
+#[cfg(feature = "ege")]
+use crate::graphics::GraphicsEnvironment;
+#[cfg(feature = "ege")]
+use crate::ime::next_text_event;
+use crate::{Key, KeyFlags, KeyMsg, MouseButton, MouseEventKind, MouseMsg, Point, XEGE};
+
+/// An input or window event.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// A key was pressed, or repeated while held down.
+    KeyDown { key: Key, flags: KeyFlags },
+    /// A key was released.
+    KeyUp { key: Key, flags: KeyFlags },
+    /// A character was typed.
+    Char(char),
+    /// The mouse moved.
+    MouseMove { pos: Point },
+    /// A mouse button was pressed.
+    MouseDown { button: MouseButton, pos: Point },
+    /// A mouse button was released.
+    MouseUp { button: MouseButton, pos: Point },
+    /// The mouse wheel was turned, a notch is `120`.
+    MouseWheel { delta: i32, pos: Point },
+    /// The input method started composing text, see `Window::start_text_input`.
+    CompositionStart,
+    /// The text being composed changed.
+    CompositionUpdate {
+        /// The text being composed, not yet committed.
+        text: String,
+        /// The position of the cursor in the text, in bytes.
+        cursor: usize,
+    },
+    /// The input method committed text.
+    TextCommit(String),
+    /// The input method stopped composing, after a commit or when it was cancelled.
+    CompositionEnd,
+    /// The size of the window changed.
+    Resized { width: u32, height: u32 },
+    /// The window gained (`true`) or lost (`false`) the focus.
+    Focus(bool),
+    /// The user asked to close the window.
+    ///
+    /// Only reported with `Init::NoForceExit`, otherwise the program exits.
+    CloseRequested,
+    /// A frame of `XEGE::run` is due.
+    Tick {
+        /// The time since the previous tick.
+        delta: Duration,
+    },
+}
+
+impl From<(KeyMsg, KeyFlags)> for Event {
+    fn from((msg, flags): (KeyMsg, KeyFlags)) -> Self {
+        match msg {
+            KeyMsg::Down(key) => Event::KeyDown { key, flags },
+            KeyMsg::Up(key) => Event::KeyUp { key, flags },
+            KeyMsg::Char(c) => Event::Char(c),
+        }
+    }
+}
+
+impl TryFrom<MouseMsg> for Event {
+    type Error = MouseMsg;
+
+    /// Fails for button events without a known button.
+    fn try_from(msg: MouseMsg) -> Result<Self, Self::Error> {
+        let pos = msg.pos;
+        let event = match (msg.kind, msg.button) {
+            (MouseEventKind::Move, _) => Event::MouseMove { pos },
+            (MouseEventKind::Wheel, _) => Event::MouseWheel {
+                delta: msg.wheel,
+                pos,
+            },
+            (MouseEventKind::Down, Some(button)) => Event::MouseDown { button, pos },
+            (MouseEventKind::Up, Some(button)) => Event::MouseUp { button, pos },
+            _ => return Err(msg),
+        };
+        Ok(event)
+    }
+}
+
+/// The state of the window at the last poll, to detect its changes.
+#[cfg(feature = "ege")]
+#[derive(Debug, Default)]
+pub(crate) struct WindowState {
+    size: Option<(u32, u32)>,
+    focused: Option<bool>,
+    closed: bool,
+}
+
+/// The interval of the idle checks of `wait_event` and `run`.
+const IDLE_INTERVAL: Duration = Duration::from_millis(1);
+
+impl<D: EventSource> XEGE<D> {
+    /// Checks if the graphics environment is running.
+    ///
+    /// # Return
+    /// `true` if the graphics environment is running, `false` otherwise.
+    ///
+    /// # Note
+    /// This function is only valid when `Init::NoForceExit` is present in initialization mode.
+    /// A headless environment is always running.
+    pub fn is_run(&self) -> bool {
+        self.device.is_run()
+    }
+
+    /// Checks if the graphics environment is headless.
+    ///
+    /// # Return
+    /// `true` if the environment was created by `headless`, `false` otherwise.
+    pub fn is_headless(&self) -> bool {
+        self.device.is_headless()
+    }
+
+    /// Queue an event.
+    ///
+    /// # Parameters
+    /// * `event` - The event, returned by a later `poll_event` before any new input.
+    ///
+    /// # Note
+    /// Next to replays, this is the only source of events of a headless environment.
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Get the next event without blocking.
+    ///
+    /// # Returns
+    /// * `Some(Event)` if an event is available, `None` otherwise.
+    ///
+    /// # Note
+    /// Window changes are detected when this function is called: `Resized` and
+    /// `Focus` are reported once per change, `CloseRequested` is reported once.
+    /// They come before the key and mouse messages received in the meantime.
+    /// `Tick` is only produced by `run`.
+    ///
+    /// EGE keeps key and mouse messages in separate queues without timestamps,
+    /// so their relative order is lost: all pending key messages are reported
+    /// before the pending mouse messages. Poll every frame to keep the delay
+    /// between a key and a mouse message that happened in order short.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+        self.device.poll(&mut self.events);
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+        if let Some(msg) = self.window.getmsg() {
+            return Some(msg.into());
+        }
+        while let Some(msg) = self.window.getmouse() {
+            if let Ok(event) = Event::try_from(msg) {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Wait for the next event.
+    ///
+    /// # Parameters
+    /// * `timeout` - The longest time to wait, `None` waits without limit.
+    ///
+    /// # Returns
+    /// * `Some(Event)` if an event arrived in time, `None` otherwise.
+    ///
+    /// # Note
+    /// The CPU is yielded while waiting. The window is updated as with `delay_ms`.
+    /// A headless environment only gets events from `push_event` and replays.
+    pub fn wait_event(&mut self, timeout: Option<Duration>) -> Option<Event> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(event) = self.poll_event() {
+                return Some(event);
+            }
+            let wait = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return None;
+                    }
+                    left.min(IDLE_INTERVAL)
+                }
+                None => IDLE_INTERVAL,
+            };
+            self.device.idle(wait);
+        }
+    }
+
+    /// Run an event loop with 60 ticks per second.
+    ///
+    /// # Parameters
+    /// * `handler` - Called with the environment and each event.
+    ///
+    /// # Note
+    /// See `run_fps`.
+    pub fn run(&mut self, handler: impl FnMut(&mut Self, Event) -> ControlFlow<()>) {
+        self.run_fps(60.0, handler);
+    }
+
+    /// Run an event loop.
+    ///
+    /// # Parameters
+    /// * `fps` - The number of `Event::Tick`s per second.
+    /// * `handler` - Called with the environment and each event.
+    ///
+    /// # Note
+    /// All pending events are handled before each tick. The window is updated after
+    /// each tick, so drawing in the handler also works with `Init::RenderManual`.
+    /// The CPU is yielded between the events and ticks.
+    ///
+    /// The loop ends when the handler returns `ControlFlow::Break`, after
+    /// `Event::CloseRequested` was handled, or when the environment stops running.
+    ///
+    /// An `fps` which is not positive, NaN, or too small for the length of a frame
+    /// to be represented gives no ticks: the loop only handles the events.
+    pub fn run_fps(
+        &mut self,
+        fps: f64,
+        mut handler: impl FnMut(&mut Self, Event) -> ControlFlow<()>,
+    ) {
+        let frame = Duration::try_from_secs_f64(1.0 / fps).ok();
+        let mut last = Instant::now();
+        let mut next = last;
+        loop {
+            while let Some(event) = self.poll_event() {
+                let close = event == Event::CloseRequested;
+                if handler(self, event).is_break() || close {
+                    return;
+                }
+            }
+            if !self.is_run() {
+                return;
+            }
+            let now = Instant::now();
+            match frame {
+                Some(frame) if now >= next => {
+                    if handler(self, Event::Tick { delta: now - last }).is_break() {
+                        return;
+                    }
+                    self.window.next_frame();
+                    self.device.present();
+                    last = now;
+                    // Skip the frames that were missed instead of catching up.
+                    next = (next + frame).max(now);
+                }
+                Some(_) => self.device.idle((next - now).min(IDLE_INTERVAL)),
+                None => self.device.idle(IDLE_INTERVAL),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ege")]
+impl EventSource for Screen {
+    fn is_run(&self) -> bool {
+        unsafe { xege_ffi::ege_is_run() }
+    }
+
+    fn is_headless(&self) -> bool {
+        false
+    }
+
+    /// Queue the changes of the window since the last check and the text input.
+    fn poll(&mut self, events: &mut VecDeque<Event>) {
+        let size = (self.getwidth(), self.getheight());
+        let focused = unsafe { GetForegroundWindow() == xege_ffi::ege_getHWnd() };
+        let run = self.is_run();
+        let state = &mut self.state;
+        if !run && !state.closed {
+            state.closed = true;
+            events.push_back(Event::CloseRequested);
+        }
+        if state.size.is_some_and(|old| old != size) {
+            events.push_back(Event::Resized {
+                width: size.0,
+                height: size.1,
+            });
+        }
+        state.size = Some(size);
+        if state.focused.is_some_and(|old| old != focused) {
+            events.push_back(Event::Focus(focused));
+        }
+        state.focused = Some(focused);
+        events.extend(std::iter::from_fn(next_text_event));
+    }
+
+    // Not `delay_ms`, which ends a frame of a recording or replay.
+    fn idle(&self, duration: Duration) {
+        unsafe { xege_ffi::ege_delay_ms(duration.as_millis().max(1) as i32) };
+    }
+
+    fn present(&mut self) {
+        unsafe { xege_ffi::ege_delay_ms(0) };
+    }
+}
+
+#[cfg(feature = "software")]
+impl EventSource for Canvas {
+    fn is_run(&self) -> bool {
+        true
+    }
+
+    fn is_headless(&self) -> bool {
+        true
+    }
+
+    fn poll(&mut self, _: &mut VecDeque<Event>) {}
+
+    fn idle(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn present(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_events_need_a_button() {
+        let pos = Point { x: 3, y: 4 };
+        let msg = |kind, button| MouseMsg::new(kind, button, pos, 0, KeyFlags::none());
+        assert_eq!(
+            Event::try_from(msg(MouseEventKind::Down, Some(MouseButton::Left))),
+            Ok(Event::MouseDown {
+                button: MouseButton::Left,
+                pos
+            })
+        );
+        assert_eq!(
+            Event::try_from(msg(MouseEventKind::Move, None)),
+            Ok(Event::MouseMove { pos })
+        );
+        for kind in [MouseEventKind::Down, MouseEventKind::Up] {
+            assert_eq!(Event::try_from(msg(kind, None)), Err(msg(kind, None)));
+        }
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn events_in_order() {
+        let mut xege = crate::headless(4, 4);
+        xege.push_event(Event::Char('a'));
+        xege.push_event(Event::Focus(false));
+        assert_eq!(xege.poll_event(), Some(Event::Char('a')));
+        assert_eq!(xege.poll_event(), Some(Event::Focus(false)));
+        assert_eq!(xege.poll_event(), None);
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn wait_event_times_out() {
+        let mut xege = crate::headless(4, 4);
+        let start = Instant::now();
+        assert_eq!(xege.wait_event(Some(Duration::from_millis(5))), None);
+        assert!(start.elapsed() >= Duration::from_millis(5));
+
+        xege.push_event(Event::Char('a'));
+        assert_eq!(
+            xege.wait_event(Some(Duration::ZERO)),
+            Some(Event::Char('a'))
+        );
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn run_ends_on_break() {
+        let mut xege = crate::headless(4, 4);
+        let mut ticks = 0;
+        xege.run_fps(1000.0, |_, event| {
+            assert!(matches!(event, Event::Tick { .. }));
+            ticks += 1;
+            if ticks == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(ticks, 3);
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn run_ends_after_close_requested() {
+        let mut xege = crate::headless(4, 4);
+        xege.push_event(Event::Char('a'));
+        xege.push_event(Event::CloseRequested);
+        xege.push_event(Event::Char('b'));
+        let mut events = vec![];
+        xege.run(|_, event| {
+            events.push(event);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(events, [Event::Char('a'), Event::CloseRequested]);
+        assert_eq!(xege.poll_event(), Some(Event::Char('b')));
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn run_without_ticks() {
+        for fps in [0.0, -60.0, f64::NAN, f64::MIN_POSITIVE] {
+            let mut xege = crate::headless(4, 4);
+            xege.push_event(Event::CloseRequested);
+            let mut events = vec![];
+            xege.run_fps(fps, |_, event| {
+                events.push(event);
+                ControlFlow::Continue(())
+            });
+            assert_eq!(events, [Event::CloseRequested], "fps {fps}");
+        }
+    }
+}
@@ -0,0 +1,545 @@
+//! Image processing filters.
+//!
+//! Every filter works in place on an `ImageViewMut`, so it can be applied to a
+//! whole image or to a region of it.
+//!
+//! ```no_run
+//! # #[cfg(feature = "ege")] {
+//! use xege::*;
+//!
+//! let mut image = Image::from_file("photo.png")?;
+//! filters::gaussian_blur(&mut image.as_view_mut(), 2.0);
+//! let face = Rect { x: 40, y: 30, width: 64, height: 64 };
+//! filters::median(&mut image.view_mut(face).unwrap(), 2);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::collections::VecDeque;
+
+use crate::convolve::par_rows;
+use crate::{ARGB, ApplyMask, ConvolveOptions, ImageViewMut, Kernel};
+
+/// Create a normalized one-dimensional Gaussian kernel.
+///
+/// # Parameters
+/// - `sigma`: The standard deviation in pixels.
+///
+/// # Returns
+/// The weights, `2 * ceil(3 * sigma) + 1` of them.
+pub fn gaussian_weights(sigma: f32) -> Vec<f32> {
+    let radius = (3.0 * sigma).ceil().max(0.0) as i32;
+    let weights = (-radius..=radius)
+        .map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+    let sum = weights.iter().sum::<f32>();
+    weights.into_iter().map(|w| w / sum).collect()
+}
+
+/// Blur with a Gaussian kernel.
+///
+/// # Parameters
+/// - `view`: The pixels to blur.
+/// - `sigma`: The standard deviation in pixels. Nothing is done if it is not positive.
+pub fn gaussian_blur(view: &mut ImageViewMut, sigma: f32) {
+    if sigma > 0.0 {
+        let weights = gaussian_weights(sigma);
+        view.convolve(
+            &Kernel::separable(&weights, &weights),
+            &ConvolveOptions::default(),
+        );
+    }
+}
+
+/// Sharpen by adding the difference to a blurred copy.
+///
+/// # Parameters
+/// - `view`: The pixels to sharpen.
+/// - `sigma`: The standard deviation of the blur in pixels.
+/// - `amount`: The strength, e.g. `0.5` to `1.5`.
+/// - `threshold`: The smallest difference of a channel which is sharpened, `0` sharpens all.
+///
+/// # Note
+/// The alpha channel is kept.
+pub fn unsharp_mask(view: &mut ImageViewMut, sigma: f32, amount: f32, threshold: u8) {
+    let original = view.as_view().to_vec();
+    gaussian_blur(view, sigma);
+    for (pixel, original) in view.rows_mut().flatten().zip(original) {
+        let (blurred, original) = (pixel.to_le_bytes(), original.to_le_bytes());
+        let mut result = original;
+        for c in 0..3 {
+            let diff = original[c] as f32 - blurred[c] as f32;
+            if diff.abs() >= threshold as f32 {
+                result[c] = (original[c] as f32 + amount * diff)
+                    .round()
+                    .clamp(0.0, 255.0) as u8;
+            }
+        }
+        *pixel = u32::from_le_bytes(result);
+    }
+}
+
+/// Replace each pixel by the gray of the given brightness, keeping its alpha.
+fn write_gray(view: &mut ImageViewMut, values: &[f32]) {
+    for (pixel, &value) in view.rows_mut().flatten().zip(values) {
+        let v = value.round().clamp(0.0, 255.0) as u32;
+        *pixel = (*pixel & 0xFF000000) | v << 16 | v << 8 | v;
+    }
+}
+
+/// The brightness of each pixel, with the Rec. 601 weights.
+fn luma(view: &ImageViewMut) -> Vec<f32> {
+    view.rows()
+        .flatten()
+        .map(|&c| {
+            let [b, g, r, _] = c.to_le_bytes();
+            0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+        })
+        .collect()
+}
+
+/// The Sobel gradients `(gx, gy)` of a plane, clamped at the edges.
+fn sobel_gradients(plane: &[f32], w: usize, h: usize) -> Vec<(f32, f32)> {
+    let at = |x: isize, y: isize| {
+        plane[y.clamp(0, h as isize - 1) as usize * w + x.clamp(0, w as isize - 1) as usize]
+    };
+    let mut gradients = vec![(0.0, 0.0); w * h];
+    par_rows(&mut gradients, w, 0, |y, row| {
+        let y = y as isize;
+        for (x, gradient) in row.iter_mut().enumerate() {
+            let x = x as isize;
+            let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x - 1, y)
+                - at(x - 1, y + 1);
+            let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x, y - 1)
+                - at(x + 1, y - 1);
+            *gradient = (gx, gy);
+        }
+    });
+    gradients
+}
+
+/// Blur a plane with a Gaussian kernel, clamped at the edges.
+fn blur_plane(plane: &[f32], w: usize, h: usize, sigma: f32) -> Vec<f32> {
+    let weights = gaussian_weights(sigma);
+    let radius = (weights.len() / 2) as isize;
+    let tap = |i: usize, k: usize, len: usize| {
+        (i as isize + k as isize - radius).clamp(0, len as isize - 1) as usize
+    };
+    let mut temp = vec![0.0; w * h];
+    par_rows(&mut temp, w, 0, |y, row| {
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = (weights.iter().enumerate())
+                .map(|(k, weight)| weight * plane[y * w + tap(x, k, w)])
+                .sum();
+        }
+    });
+    let mut out = vec![0.0; w * h];
+    par_rows(&mut out, w, 0, |y, row| {
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = (weights.iter().enumerate())
+                .map(|(k, weight)| weight * temp[tap(y, k, h) * w + x])
+                .sum();
+        }
+    });
+    out
+}
+
+/// Replace the pixels by the magnitude of their Sobel gradient.
+///
+/// # Parameters
+/// - `view`: The pixels to process.
+///
+/// # Note
+/// The gradient is computed on the brightness and written as gray, clamped to `255`.
+/// The alpha channel is kept.
+pub fn sobel(view: &mut ImageViewMut) {
+    let (w, h) = (view.width() as usize, view.height() as usize);
+    let magnitudes = sobel_gradients(&luma(view), w, h)
+        .into_iter()
+        .map(|(gx, gy)| gx.hypot(gy))
+        .collect::<Vec<_>>();
+    write_gray(view, &magnitudes);
+}
+
+/// Detect edges with the Canny algorithm.
+///
+/// # Parameters
+/// - `view`: The pixels to process.
+/// - `sigma`: The standard deviation of the blur applied first, `0` disables it.
+/// - `low`: The smallest gradient magnitude of an edge connected to a strong edge.
+/// - `high`: The smallest gradient magnitude of a strong edge.
+///
+/// # Note
+/// The magnitudes are on the scale of `sobel`, e.g. `low = 50.0` and `high = 150.0`.
+/// Edges are written white and everything else black. The alpha channel is kept.
+pub fn canny(view: &mut ImageViewMut, sigma: f32, low: f32, high: f32) {
+    let (w, h) = (view.width() as usize, view.height() as usize);
+    let mut plane = luma(view);
+    if sigma > 0.0 {
+        plane = blur_plane(&plane, w, h, sigma);
+    }
+    let gradients = sobel_gradients(&plane, w, h);
+    let magnitude = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x >= w as isize || y >= h as isize {
+            return 0.0;
+        }
+        let (gx, gy) = gradients[y as usize * w + x as usize];
+        gx.hypot(gy)
+    };
+
+    // Keep only the local maxima across the edge.
+    let mut strength = vec![0u8; w * h];
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            let m = magnitude(x, y);
+            if m < low {
+                continue;
+            }
+            let (gx, gy) = gradients[y as usize * w + x as usize];
+            let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+            let (dx, dy) = match angle {
+                a if !(22.5..157.5).contains(&a) => (1, 0),
+                a if a < 67.5 => (1, 1),
+                a if a < 112.5 => (0, 1),
+                _ => (-1, 1),
+            };
+            if m > magnitude(x + dx, y + dy) && m >= magnitude(x - dx, y - dy) {
+                strength[y as usize * w + x as usize] = if m >= high { 2 } else { 1 };
+            }
+        }
+    }
+
+    // Follow weak edges connected to strong ones.
+    let mut queue = (0..w * h)
+        .filter(|&i| strength[i] == 2)
+        .collect::<VecDeque<_>>();
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = ((i % w) as isize, (i / w) as isize);
+        for (nx, ny) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy))) {
+            if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+                continue;
+            }
+            let j = ny as usize * w + nx as usize;
+            if strength[j] == 1 {
+                strength[j] = 2;
+                queue.push_back(j);
+            }
+        }
+    }
+    let edges = strength
+        .into_iter()
+        .map(|s| if s == 2 { 255.0 } else { 0.0 })
+        .collect::<Vec<_>>();
+    write_gray(view, &edges);
+}
+
+/// Replace each channel by its minimum or maximum in a square, in two passes.
+fn morphology(view: &mut ImageViewMut, radius: u32, pick: fn(u8, u8) -> u8) {
+    let (w, h) = (view.width() as usize, view.height() as usize);
+    let r = radius as isize;
+    let pass = |input: &[ARGB], step: usize, len: usize, horizontal: bool| {
+        let mut out = input.to_vec();
+        par_rows(&mut out, w, 0, |y, row| {
+            for (x, out) in row.iter_mut().enumerate() {
+                let i = if horizontal { x } else { y } as isize;
+                let base = y * w + x - i as usize * step;
+                let mut result = input[y * w + x].to_le_bytes();
+                for j in (i - r).max(0)..=(i + r).min(len as isize - 1) {
+                    let other = input[base + j as usize * step].to_le_bytes();
+                    (0..4).for_each(|c| result[c] = pick(result[c], other[c]));
+                }
+                *out = u32::from_le_bytes(result);
+            }
+        });
+        out
+    };
+    let pixels = view.as_view().to_vec();
+    let pixels = pass(&pixels, 1, w, true);
+    let pixels = pass(&pixels, w, h, false);
+    for (pixel, value) in view.rows_mut().flatten().zip(pixels) {
+        *pixel = value;
+    }
+}
+
+/// Shrink bright areas, replacing each channel by its minimum in a square.
+///
+/// # Parameters
+/// - `view`: The pixels to process.
+/// - `radius`: The distance from the center to the edge of the square.
+pub fn erode(view: &mut ImageViewMut, radius: u32) {
+    morphology(view, radius, u8::min);
+}
+
+/// Grow bright areas, replacing each channel by its maximum in a square.
+///
+/// # Note
+/// See `erode`.
+pub fn dilate(view: &mut ImageViewMut, radius: u32) {
+    morphology(view, radius, u8::max);
+}
+
+/// Remove noise, replacing each channel by its median in a square.
+///
+/// # Parameters
+/// - `view`: The pixels to process.
+/// - `radius`: The distance from the center to the edge of the square.
+///
+/// # Note
+/// The square is cut off at the edges of the view.
+pub fn median(view: &mut ImageViewMut, radius: u32) {
+    let (w, h) = (view.width() as usize, view.height() as usize);
+    let r = radius as usize;
+    let input = view.as_view().to_vec();
+    let mut output = input.clone();
+    par_rows(&mut output, w, 0, |y, row| {
+        let mut channels = [const { Vec::new() }; 4];
+        for (x, out) in row.iter_mut().enumerate() {
+            channels.iter_mut().for_each(Vec::clear);
+            for sy in y.saturating_sub(r)..(y + r + 1).min(h) {
+                for &c in &input[sy * w + x.saturating_sub(r)..sy * w + (x + r + 1).min(w)] {
+                    let bytes = c.to_le_bytes();
+                    (0..4).for_each(|i| channels[i].push(bytes[i]));
+                }
+            }
+            let bytes = channels.each_mut().map(|values| {
+                let mid = values.len() / 2;
+                *values.select_nth_unstable(mid).1
+            });
+            *out = u32::from_le_bytes(bytes);
+        }
+    });
+    for (pixel, value) in view.rows_mut().flatten().zip(output) {
+        *pixel = value;
+    }
+}
+
+/// Make the pixels look raised, lit from the top-left.
+///
+/// # Note
+/// The alpha channel is kept.
+pub fn emboss(view: &mut ImageViewMut) {
+    let kernel = Kernel::new(3, 3, vec![-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0]);
+    let options = ConvolveOptions {
+        channels: ApplyMask::Rgb,
+        ..Default::default()
+    };
+    view.convolve(&kernel, &options);
+}
+
+/// A 4x5 matrix transforming colors.
+///
+/// The rows compute the red, green, blue and alpha channels. The columns are the
+/// factors of the red, green, blue and alpha channels, followed by an offset. The
+/// channels range from `0.0` to `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMatrix(pub [[f32; 5]; 4]);
+
+impl ColorMatrix {
+    pub const IDENTITY: Self = Self([
+        [1.0, 0.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0, 0.0],
+    ]);
+
+    /// Convert to gray with the Rec. 601 weights.
+    pub const GRAYSCALE: Self = Self::saturation(0.0);
+
+    pub const SEPIA: Self = Self([
+        [0.393, 0.769, 0.189, 0.0, 0.0],
+        [0.349, 0.686, 0.168, 0.0, 0.0],
+        [0.272, 0.534, 0.131, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0, 0.0],
+    ]);
+
+    /// Invert the colors, keeping the alpha.
+    pub const INVERT: Self = Self([
+        [-1.0, 0.0, 0.0, 0.0, 1.0],
+        [0.0, -1.0, 0.0, 0.0, 1.0],
+        [0.0, 0.0, -1.0, 0.0, 1.0],
+        [0.0, 0.0, 0.0, 1.0, 0.0],
+    ]);
+
+    /// Change the saturation, `0.0` is gray and `1.0` keeps the colors.
+    pub const fn saturation(s: f32) -> Self {
+        let (r, g, b) = (0.299 * (1.0 - s), 0.587 * (1.0 - s), 0.114 * (1.0 - s));
+        Self([
+            [r + s, g, b, 0.0, 0.0],
+            [r, g + s, b, 0.0, 0.0],
+            [r, g, b + s, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Add to the color channels, from `-1.0` to `1.0`.
+    pub const fn brightness(amount: f32) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix.0[0][4] = amount;
+        matrix.0[1][4] = amount;
+        matrix.0[2][4] = amount;
+        matrix
+    }
+
+    /// Scale the color channels around the middle gray, `1.0` keeps the colors.
+    pub const fn contrast(factor: f32) -> Self {
+        let offset = 0.5 * (1.0 - factor);
+        Self([
+            [factor, 0.0, 0.0, 0.0, offset],
+            [0.0, factor, 0.0, 0.0, offset],
+            [0.0, 0.0, factor, 0.0, offset],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Combine two matrices into one applying `self` first and `next` second.
+    pub fn then(&self, next: &Self) -> Self {
+        let mut result = [[0.0; 5]; 4];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| next.0[i][k] * self.0[k][j]).sum::<f32>();
+            }
+            row[4] += next.0[i][4];
+        }
+        Self(result)
+    }
+
+    /// Transform a color.
+    pub fn apply(&self, color: ARGB) -> ARGB {
+        let [b, g, r, a] = color.to_le_bytes();
+        let input = [r, g, b, a].map(|c| c as f32 / 255.0);
+        let [r, g, b, a] = self.0.map(|row| {
+            let value = row[4] + (0..4).map(|k| row[k] * input[k]).sum::<f32>();
+            (value * 255.0).round().clamp(0.0, 255.0) as u8
+        });
+        u32::from_le_bytes([b, g, r, a])
+    }
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Transform the colors with a color matrix.
+///
+/// # Note
+/// The matrix is applied to straight alpha colors.
+pub fn color_matrix(view: &mut ImageViewMut, matrix: &ColorMatrix) {
+    view.transform(|c| matrix.apply(c));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply a filter to pixels given as gray values, returning the gray values.
+    fn gray(width: u32, values: &[u8], filter: impl FnOnce(&mut ImageViewMut)) -> Vec<u8> {
+        let mut pixels = values
+            .iter()
+            .map(|&v| 0xFF000000 | ((v as u32) * 0x010101))
+            .collect::<Vec<_>>();
+        let height = values.len() as u32 / width;
+        filter(&mut ImageViewMut::new(
+            &mut pixels,
+            width as usize,
+            width,
+            height,
+        ));
+        pixels.iter().map(|&c| c as u8).collect()
+    }
+
+    #[test]
+    fn gaussian() {
+        let weights = gaussian_weights(1.0);
+        assert_eq!(weights.len(), 7);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(weights.iter().eq(weights.iter().rev()));
+        assert!(weights.windows(2).take(3).all(|w| w[0] < w[1]));
+
+        // A flat image stays flat, blurring an impulse spreads it symmetrically.
+        assert_eq!(gray(4, &[100; 12], |v| gaussian_blur(v, 1.5)), [100; 12]);
+        let mut impulse = [0; 25];
+        impulse[12] = 255;
+        let blurred = gray(5, &impulse, |v| gaussian_blur(v, 0.8));
+        assert!(blurred[12] < 255 && blurred[11] > 0);
+        assert_eq!(blurred[11], blurred[13]);
+        assert_eq!(blurred[7], blurred[17]);
+        // Not positive sigmas do nothing.
+        assert_eq!(gray(5, &impulse, |v| gaussian_blur(v, 0.0)), impulse);
+    }
+
+    #[test]
+    fn morphology() {
+        let mut dot = [0; 25];
+        dot[12] = 200;
+        let dilated = gray(5, &dot, |v| dilate(v, 1));
+        #[rustfmt::skip]
+        assert_eq!(dilated, [
+            0, 0, 0, 0, 0,
+            0, 200, 200, 200, 0,
+            0, 200, 200, 200, 0,
+            0, 200, 200, 200, 0,
+            0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(gray(5, &dilated, |v| erode(v, 1)), dot);
+        assert_eq!(gray(5, &dot, |v| erode(v, 2)), [0; 25]);
+    }
+
+    #[test]
+    fn median_removes_salt() {
+        let mut salt = [50; 25];
+        salt[6] = 255;
+        salt[18] = 0;
+        assert_eq!(gray(5, &salt, |v| median(v, 1)), [50; 25]);
+    }
+
+    #[test]
+    fn edges() {
+        // A vertical step from black to white.
+        let step = [0, 0, 255, 255].repeat(4);
+        let magnitudes = gray(4, &step, sobel);
+        assert!(magnitudes.chunks(4).all(|row| row == [0, 255, 255, 0]));
+        assert_eq!(gray(4, &[80; 16], sobel), [0; 16]);
+
+        let edges = gray(4, &step, |v| canny(v, 0.0, 50.0, 150.0));
+        assert!(edges.chunks(4).all(|row| row[0] == 0 && row[3] == 0));
+        assert!(edges.chunks(4).all(|row| row[1..3].contains(&255)));
+    }
+
+    #[test]
+    fn color_matrices() {
+        let color = 0x80336699;
+        assert_eq!(ColorMatrix::IDENTITY.apply(color), color);
+        assert_eq!(ColorMatrix::INVERT.apply(color), 0x80CC9966);
+        assert_eq!(
+            ColorMatrix::INVERT.then(&ColorMatrix::INVERT).apply(color),
+            color
+        );
+        let [b, g, r, a] = ColorMatrix::GRAYSCALE.apply(color).to_le_bytes();
+        assert_eq!((r, a), (g, 0x80));
+        assert_eq!(r, b);
+        assert_eq!(ColorMatrix::brightness(1.0).apply(color), 0x80FFFFFF);
+        assert_eq!(ColorMatrix::contrast(0.0).apply(color), 0x80808080);
+        assert_eq!(
+            ColorMatrix::brightness(0.2)
+                .then(&ColorMatrix::contrast(2.0))
+                .apply(color),
+            ColorMatrix::contrast(2.0).apply(ColorMatrix::brightness(0.2).apply(color))
+        );
+    }
+
+    #[test]
+    fn unsharp_keeps_flat_areas_and_alpha() {
+        let mut pixels = vec![0x40808080; 16];
+        pixels[5] = 0x40A0A0A0;
+        let mut view = ImageViewMut::new(&mut pixels, 4, 4, 4);
+        unsharp_mask(&mut view, 1.0, 1.0, 0);
+        assert!(pixels.iter().all(|&c| c >> 24 == 0x40));
+        assert!(pixels[5] & 0xFF > 0xA0);
+        assert!(pixels[15] & 0xFF <= 0x80);
+    }
+}
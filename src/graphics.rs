@@ -1,16 +1,60 @@
+use crate::backend;
 use crate::color::IntoARGB;
+#[cfg(feature = "ege")]
 use crate::mat::IntoEGEMatrix;
-use crate::{Color, Image, Path};
-use crate::{ImageError, enums::*};
+#[cfg(feature = "ege")]
+use crate::pathdata::FLATNESS;
+#[cfg(feature = "ege")]
+use crate::brush;
+#[cfg(feature = "ege")]
+use crate::composite::{self, BlendMode};
+#[cfg(feature = "ege")]
+use crate::resample::{Filter, Warp, draw_warped, resample};
+#[cfg(feature = "ege")]
+use crate::backend::DeviceState;
+#[cfg(feature = "ege")]
+use crate::{Brush, DashPattern, Image, ImageViewMut, Path, PathData, PathFillMode};
+#[cfg(feature = "ege")]
+use crate::ImageError;
+use crate::{Color, enums::*};
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
 pub type ARGB = u32;
 
 /// Base trait for drawable devices.
+///
+/// # Note
+/// The trait is implemented by the devices of this crate and cannot be
+/// implemented outside of it.
 pub trait DrawableDevice {
+    /// Get the backend drawing on the device.
+    #[doc(hidden)]
+    fn backend(&self) -> &dyn backend::Backend;
+
+    /// Get the mutable backend drawing on the device.
+    #[doc(hidden)]
+    fn backend_mut(&mut self) -> &mut dyn backend::Backend;
+}
+
+/// A device drawn on by EGE: the window or an `Image`.
+///
+/// # Note
+/// The operations that require EGE, e.g. text, `HighDraw` and `ImageDraw`,
+/// are only available on these devices.
+#[cfg(feature = "ege")]
+pub trait EgeDevice: DrawableDevice {
     fn mut_ptr(&mut self) -> *mut ege_IMAGE;
     fn const_ptr(&self) -> *const ege_IMAGE;
 
+    /// Get the drawing state EGE has no equivalent for.
+    #[doc(hidden)]
+    fn state(&self) -> &DeviceState;
+
+    /// Get the mutable drawing state EGE has no equivalent for.
+    #[doc(hidden)]
+    fn state_mut(&mut self) -> &mut DeviceState;
+
     #[allow(non_snake_case)]
     fn getHDC(&self) -> HDC {
         unsafe { ege_getHDC(self.const_ptr()) }
@@ -21,7 +65,7 @@ pub trait DrawableDevice {
 pub trait GraphicsEnvironment: DrawableDevice {
     /// Clear the device.
     fn clear(&mut self) {
-        unsafe { ege_cleardevice(self.mut_ptr()) };
+        self.backend_mut().clear()
     }
 
     /// Set current fill color.
@@ -29,7 +73,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `color` - The color to set.
     fn setfillcolor(&mut self, color: impl IntoARGB) {
-        unsafe { ege_setfillcolor(color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().setfillcolor(color.into_argb())
     }
 
     /// Set current color.
@@ -37,7 +81,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `color` - The color to set.
     fn setcolor(&mut self, color: impl IntoARGB) {
-        unsafe { ege_setcolor(color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().setcolor(color.into_argb())
     }
 
     /// Set current background color.
@@ -45,14 +89,18 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `color` - The color to set.
     fn setbkcolor(&mut self, color: impl IntoARGB) {
-        unsafe { ege_setbkcolor(color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().setbkcolor(color.into_argb())
     }
 
     /// Set current background mode.
     ///
     /// # Parameters
     /// * `mode` - The mode to set.
-    fn setbkmode(&mut self, mode: BkMode) {
+    #[cfg(feature = "ege")]
+    fn setbkmode(&mut self, mode: BkMode)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_setbkmode(mode as i32, self.mut_ptr()) };
     }
 
@@ -61,8 +109,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The color of the background.
     fn getbkcolor(&self) -> Color {
-        let argb = unsafe { ege_getbkcolor(self.const_ptr()) };
-        Color::from_argb(argb)
+        self.backend().getbkcolor()
     }
 
     /// Set current line color.
@@ -70,7 +117,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `color` - The color to set.
     fn setlinecolor(&mut self, color: impl IntoARGB) {
-        unsafe { ege_setlinecolor(color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().setlinecolor(color.into_argb())
     }
 
     /// Set current fill style.
@@ -79,7 +126,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// * `pattern` - The pattern to set.
     /// * `color` - The color to set.
     fn setfillstyle(&mut self, pattern: FillPattern, color: impl IntoARGB) {
-        unsafe { ege_setfillstyle(pattern as i32, color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().setfillstyle(pattern, color.into_argb())
     }
 
     /// Set current line style.
@@ -88,11 +135,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// * `style` - The style to set.
     /// * `width` - The line width to set.
     fn setlinestyle(&mut self, style: LineStyle, width: i32) {
-        if let LineStyle::UserDef(user_def) = style {
-            unsafe { ege_setlinestyle(style.into(), user_def, width, self.mut_ptr()) };
-        } else {
-            unsafe { ege_setlinestyle(style.into(), 0, width, self.mut_ptr()) };
-        }
+        self.backend_mut().setlinestyle(style, width)
     }
 
     /// Get current color.
@@ -100,8 +143,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The color of the environment.
     fn getcolor(&self) -> Color {
-        let argb = unsafe { ege_getcolor(self.const_ptr()) };
-        Color::from_argb(argb)
+        self.backend().getcolor()
     }
 
     /// Get current text color.
@@ -109,8 +151,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The color of the text.
     fn gettextcolor(&self) -> Color {
-        let argb = unsafe { ege_gettextcolor(self.const_ptr()) };
-        Color::from_argb(argb)
+        self.backend().gettextcolor()
     }
 
     /// Set current text color.
@@ -118,14 +159,18 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `color` - The color to set.
     fn settextcolor(&mut self, color: impl IntoARGB) {
-        unsafe { ege_settextcolor(color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().settextcolor(color.into_argb())
     }
 
     /// Set current font background color.
     ///
     /// # Parameters
     /// * `color` - The color to set.
-    fn setfontbkcolor(&mut self, color: impl IntoARGB) {
+    #[cfg(feature = "ege")]
+    fn setfontbkcolor(&mut self, color: impl IntoARGB)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_setfontbkcolor(color.into_argb(), self.mut_ptr()) };
     }
 
@@ -134,7 +179,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `start` - The start cap to set.
     /// * `end` - The end cap to set.
-    fn setlinecap(&mut self, start: LineCap, end: LineCap) {
+    #[cfg(feature = "ege")]
+    fn setlinecap(&mut self, start: LineCap, end: LineCap)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_setlinecap1(start as i32, end as i32, self.mut_ptr()) };
     }
 
@@ -143,7 +192,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `join` - The join to set.
     /// * `limit` - The limit to set.
-    fn setlinejoin(&mut self, join: LineJoin, limit: f32) {
+    #[cfg(feature = "ege")]
+    fn setlinejoin(&mut self, join: LineJoin, limit: f32)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_setlinejoin1(join as i32, limit, self.mut_ptr()) };
     }
 
@@ -152,7 +205,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `width` - The width to set.
     fn setlinewidth(&mut self, width: f32) {
-        unsafe { ege_setlinewidth(width, self.mut_ptr()) };
+        self.backend_mut().setlinewidth(width)
     }
 
     /// Get current fill color.
@@ -160,8 +213,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The color of the fill.
     fn getfillcolor(&self) -> Color {
-        let argb = unsafe { ege_getfillcolor(self.const_ptr()) };
-        Color::from_argb(argb)
+        self.backend().getfillcolor()
     }
 
     /// Get current line style.
@@ -169,28 +221,18 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The style of the line and the width of the line: `(style, width)`.
     fn getlinestyle(&self) -> (LineStyle, i32) {
-        let (mut linestyle, mut pattern, mut thickness) = (0i32, 0u16, 0i32);
-        unsafe {
-            ege_getlinestyle(
-                &mut linestyle as _,
-                &mut pattern as _,
-                &mut thickness as _,
-                self.const_ptr(),
-            );
-        }
-        let style = if linestyle == ege_line_styles_USERBIT_LINE {
-            LineStyle::UserDef(pattern)
-        } else {
-            LineStyle::try_from(linestyle).unwrap()
-        };
-        (style, thickness)
+        self.backend().getlinestyle()
     }
 
     /// Get current line cap style.
     ///
     /// # Return
     /// The start and end cap of the line: `(start, end)`.
-    fn getlinecap(&self) -> (LineCap, LineCap) {
+    #[cfg(feature = "ege")]
+    fn getlinecap(&self) -> (LineCap, LineCap)
+    where
+        Self: EgeDevice,
+    {
         let (mut start, mut end) = (0i32, 0i32);
         unsafe { ege_getlinecap(&mut start as _, &mut end as _, self.const_ptr()) }
         (
@@ -203,7 +245,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     ///
     /// # Return
     /// The join and limit of the line: `(join, limit)`.
-    fn getlinejoin(&self) -> (LineJoin, f32) {
+    #[cfg(feature = "ege")]
+    fn getlinejoin(&self) -> (LineJoin, f32)
+    where
+        Self: EgeDevice,
+    {
         let (mut join, mut limit) = (0i32, 0f32);
         unsafe { ege_getlinejoin(&mut join as _, &mut limit as _, self.const_ptr()) }
         (LineJoin::try_from(join).unwrap(), limit)
@@ -211,22 +257,22 @@ pub trait GraphicsEnvironment: DrawableDevice {
 
     /// Get device width.
     fn getwidth(&self) -> u32 {
-        unsafe { ege_getwidth(self.const_ptr()) as _ }
+        self.backend().getwidth()
     }
 
     /// Get device height.
     fn getheight(&self) -> u32 {
-        unsafe { ege_getheight(self.const_ptr()) as _ }
+        self.backend().getheight()
     }
 
     /// Get current x position.
     fn getx(&self) -> i32 {
-        unsafe { ege_getx(self.const_ptr()) }
+        self.backend().getx()
     }
 
     /// Get current y position.
     fn gety(&self) -> i32 {
-        unsafe { ege_gety(self.const_ptr()) }
+        self.backend().gety()
     }
 
     /// Move the current position by relative values.
@@ -235,7 +281,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// * `dx` - The relative x position.
     /// * `dy` - The relative y position.
     fn moverel(&mut self, dx: i32, dy: i32) {
-        unsafe { ege_moverel(dx, dy, self.mut_ptr()) };
+        self.backend_mut().moverel(dx, dy)
     }
 
     /// Move the current position to absolute values.
@@ -244,14 +290,18 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// * `x` - The absolute x position.
     /// * `y` - The absolute y position.
     fn moveto(&mut self, x: i32, y: i32) {
-        unsafe { ege_moveto(x, y, self.mut_ptr()) };
+        self.backend_mut().moveto(x, y)
     }
 
     /// Get current font.
     ///
     /// # Return
     /// The current font.
-    fn getfont(&self) -> Font {
+    #[cfg(feature = "ege")]
+    fn getfont(&self) -> Font
+    where
+        Self: EgeDevice,
+    {
         let mut lfont = LOGFONTW {
             lfHeight: 0,
             lfWidth: 0,
@@ -276,7 +326,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     ///
     /// # Parameters
     /// * `font` - The font to set.
-    fn setfont(&mut self, font: Font) {
+    #[cfg(feature = "ege")]
+    fn setfont(&mut self, font: Font)
+    where
+        Self: EgeDevice,
+    {
         let lfont = font.into();
         unsafe { ege_setfont6(&lfont as _, self.mut_ptr()) };
     }
@@ -288,7 +342,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     ///
     /// # Return
     /// The height of the text.
-    fn textheight(&mut self, text: &str) -> u32 {
+    #[cfg(feature = "ege")]
+    fn textheight(&mut self, text: &str) -> u32
+    where
+        Self: EgeDevice,
+    {
         let wchar = text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
         unsafe { ege_textheight1(wchar.as_ptr(), self.mut_ptr()) as _ }
     }
@@ -300,7 +358,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     ///
     /// # Return
     /// The width of the text.
-    fn textwidth(&mut self, text: &str) -> u32 {
+    #[cfg(feature = "ege")]
+    fn textwidth(&mut self, text: &str) -> u32
+    where
+        Self: EgeDevice,
+    {
         let wchar = text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
         unsafe { ege_textwidth1(wchar.as_ptr(), self.mut_ptr()) as _ }
     }
@@ -310,7 +372,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Parameters
     /// * `horiz` - The horizontal alignment.
     /// * `vert` - The vertical alignment.
-    fn settextjustify(&mut self, horiz: TextHAlign, vert: TextVAlign) {
+    #[cfg(feature = "ege")]
+    fn settextjustify(&mut self, horiz: TextHAlign, vert: TextVAlign)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_settextjustify(horiz as i32, vert as i32, self.mut_ptr()) };
     }
 
@@ -319,10 +385,7 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The pixel buffer.
     fn getbuffer(&self) -> &[ARGB] {
-        let ptr = unsafe { ege_getbuffer(self.const_ptr() as _) };
-        let width = self.getwidth();
-        let height = self.getheight();
-        unsafe { std::slice::from_raw_parts(ptr, (width * height) as usize) }
+        self.backend().getbuffer()
     }
 
     /// Get mutable image pixel buffer.
@@ -330,14 +393,15 @@ pub trait GraphicsEnvironment: DrawableDevice {
     /// # Return
     /// The mutable pixel buffer.
     fn getbuffer_mut(&mut self) -> &mut [ARGB] {
-        let ptr = unsafe { ege_getbuffer(self.mut_ptr()) };
-        let width = self.getwidth();
-        let height = self.getheight();
-        unsafe { std::slice::from_raw_parts_mut(ptr, (width * height) as usize) }
+        self.backend_mut().getbuffer_mut()
     }
 
     /// Set global alpha transparency.
-    fn set_alpha(&mut self, alpha: u8) {
+    #[cfg(feature = "ege")]
+    fn set_alpha(&mut self, alpha: u8)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_ege_setalpha(alpha as _, self.mut_ptr()) };
     }
 
@@ -345,7 +409,11 @@ pub trait GraphicsEnvironment: DrawableDevice {
     ///
     /// # Parameters
     /// * `matrix` - The transformation matrix.
-    fn set_transform(&mut self, matrix: impl IntoEGEMatrix) {
+    #[cfg(feature = "ege")]
+    fn set_transform(&mut self, matrix: impl IntoEGEMatrix)
+    where
+        Self: EgeDevice,
+    {
         let mat = matrix.into_ege_matrix();
         unsafe { ege_ege_set_transform(&mat, self.mut_ptr()) };
     }
@@ -370,6 +438,7 @@ pub struct Line {
 
 /// Point
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point<T = i32> {
     pub x: T,
     pub y: T,
@@ -401,7 +470,11 @@ pub trait Draw: DrawableDevice {
     /// // DPan
     /// xege.setwritemode(|pen, dst| pen & dst);
     /// ```
-    fn setwritemode(&mut self, gen_rop2: impl Fn(u8, u8) -> u8) {
+    #[cfg(feature = "ege")]
+    fn setwritemode(&mut self, gen_rop2: impl Fn(u8, u8) -> u8)
+    where
+        Self: EgeDevice,
+    {
         let mask = gen_rop2(0b1100, 0b1010) + 1;
         unsafe { ege_setwritemode(mask as _, self.mut_ptr()) };
     }
@@ -415,8 +488,7 @@ pub trait Draw: DrawableDevice {
     /// # Return
     /// The color of the pixel.
     fn getpixel(&self, x: i32, y: i32) -> Color {
-        let argb = unsafe { ege_getpixel(x, y, self.const_ptr()) };
-        Color::from_argb(argb)
+        self.backend().getpixel(x, y)
     }
 
     /// Draw a pixel at the specified position.
@@ -426,7 +498,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position.
     /// * `color` - The color to draw.
     fn putpixel(&mut self, x: i32, y: i32, color: impl IntoARGB) {
-        unsafe { ege_putpixel(x, y, color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().putpixel(x, y, color.into_argb())
     }
 
     /// Set the color of pixels while preserving the original alpha value
@@ -436,7 +508,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position.
     /// * `color` - The color to draw.
     fn putpixel_savealpha(&mut self, x: i32, y: i32, color: impl IntoARGB) {
-        unsafe { ege_putpixel_savealpha(x, y, color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().putpixel_savealpha(x, y, color.into_argb())
     }
 
     /// Draw pixel points
@@ -447,7 +519,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position.
     /// * `color` - The color to draw.
     fn putpixel_withalpha(&mut self, x: i32, y: i32, color: impl IntoARGB) {
-        unsafe { ege_putpixel_withalpha(x, y, color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().putpixel_withalpha(x, y, color.into_argb())
     }
 
     /// Draw pixel points
@@ -459,7 +531,7 @@ pub trait Draw: DrawableDevice {
     /// * `color` - The color to draw.
     /// * `factor` - The alpha factor.
     fn putpixel_alphablend(&mut self, x: i32, y: i32, color: impl IntoARGB, factor: u8) {
-        unsafe { ege_putpixel_alphablend1(x, y, color.into_argb(), factor, self.mut_ptr()) };
+        self.backend_mut().putpixel_alphablend(x, y, color.into_argb(), factor)
     }
 
     /// Draw multiple pixels
@@ -468,15 +540,14 @@ pub trait Draw: DrawableDevice {
     /// * `array` - The array of pixels to draw.
     fn putpixels<T: IntoARGB + Copy>(&mut self, array: &[PixelPoint<T>]) {
         let array = array
-            .into_iter()
-            .map(|&PixelPoint { x, y, color }| {
-                let argb = color.into_argb();
-                let argb = unsafe { *(&argb as *const u32 as *const i32) };
-                [x, y, argb]
+            .iter()
+            .map(|&PixelPoint { x, y, color }| PixelPoint {
+                x,
+                y,
+                color: color.into_argb(),
             })
-            .flatten()
             .collect::<Vec<_>>();
-        unsafe { ege_putpixels(array.len() as _, array.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().putpixels(&array)
     }
 
     /// Draw a arc.
@@ -488,7 +559,7 @@ pub trait Draw: DrawableDevice {
     /// * `end` - The end angle in degrees.
     /// * `radius` - The radius of the arc.
     fn arc(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
-        unsafe { ege_arc(x, y, start, end, radius, self.mut_ptr()) };
+        self.backend_mut().arc(x, y, start, end, radius)
     }
 
     /// Draw a arc.
@@ -500,7 +571,7 @@ pub trait Draw: DrawableDevice {
     /// * `end` - The end angle in degrees.
     /// * `radius` - The radius of the arc.
     fn arcf(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
-        unsafe { ege_arcf(x, y, start, end, radius, self.mut_ptr()) };
+        self.backend_mut().arcf(x, y, start, end, radius)
     }
 
     /// Draw a ellipse.
@@ -513,7 +584,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn ellipse(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
-        unsafe { ege_ellipse(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().ellipse(x, y, start, end, rx, ry)
     }
 
     /// Draw a ellipse.
@@ -526,7 +597,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn ellipsef(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
-        unsafe { ege_ellipsef(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().ellipsef(x, y, start, end, rx, ry)
     }
 
     /// Draw a line between two points.
@@ -537,7 +608,7 @@ pub trait Draw: DrawableDevice {
     /// * `x2` - The x position of the second point.
     /// * `y2` - The y position of the second point.
     fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
-        unsafe { ege_line(x1, y1, x2, y2, self.mut_ptr()) };
+        self.backend_mut().line(x1, y1, x2, y2)
     }
 
     /// Draw a line between current position and the specified relative coordinates.
@@ -546,7 +617,7 @@ pub trait Draw: DrawableDevice {
     /// * `dx` - The relative x position.
     /// * `dy` - The relative y position.
     fn linerel(&mut self, dx: i32, dy: i32) {
-        unsafe { ege_linerel(dx, dy, self.mut_ptr()) };
+        self.backend_mut().linerel(dx, dy)
     }
 
     /// Draw a line between current position and the specified absolute coordinates.
@@ -555,7 +626,7 @@ pub trait Draw: DrawableDevice {
     /// * `x` - The absolute x position.
     /// * `y` - The absolute y position.
     fn lineto(&mut self, x: i32, y: i32) {
-        unsafe { ege_lineto(x, y, self.mut_ptr()) };
+        self.backend_mut().lineto(x, y)
     }
 
     /// Draw multiple lines.
@@ -563,12 +634,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `lines` - The array of lines to draw.
     fn drawlines(&mut self, lines: &[Line]) {
-        let lines = lines
-            .into_iter()
-            .map(|&Line { x1, y1, x2, y2 }| [x1, y1, x2, y2])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_drawlines(lines.len() as _, lines.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().drawlines(lines)
     }
 
     /// Draw a polyline.
@@ -576,14 +642,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The array of points to draw.
     fn polyline(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 2);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_polyline(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().polyline(points)
     }
 
     /// Draw a polyline.
@@ -591,14 +650,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The array of points to draw.
     fn drawpoly(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 2);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_drawpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().drawpoly(points)
     }
 
     /// Draw a bezier curve.
@@ -606,14 +658,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The array of control points to draw.
     fn drawbezier(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 4 && points.len() % 3 == 1);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_drawbezier(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().drawbezier(points)
     }
 
     /// Draw a rectangle.
@@ -624,7 +669,7 @@ pub trait Draw: DrawableDevice {
     /// * `right` - The right position of the rectangle.
     /// * `bottom` - The bottom position of the rectangle.
     fn rectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
-        unsafe { ege_rectangle(left, top, right, bottom, self.mut_ptr()) };
+        self.backend_mut().rectangle(left, top, right, bottom)
     }
 
     /// Draw a fill rectangle.
@@ -635,7 +680,7 @@ pub trait Draw: DrawableDevice {
     /// * `right` - The right position of the rectangle.
     /// * `bottom` - The bottom position of the rectangle.
     fn fillrect(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
-        unsafe { ege_fillrect(left, top, right, bottom, self.mut_ptr()) };
+        self.backend_mut().fillrect(left, top, right, bottom)
     }
 
     /// Draw a solid rectangle.
@@ -646,7 +691,7 @@ pub trait Draw: DrawableDevice {
     /// * `right` - The right position of the rectangle.
     /// * `bottom` - The bottom position of the rectangle.
     fn solidrectangle(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
-        unsafe { ege_solidrect(left, top, right, bottom, self.mut_ptr()) };
+        self.backend_mut().solidrectangle(left, top, right, bottom)
     }
 
     /// Draw a rectangle.
@@ -657,7 +702,7 @@ pub trait Draw: DrawableDevice {
     /// * `right` - The right position of the rectangle.
     /// * `bottom` - The bottom position of the rectangle.
     fn bar(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
-        unsafe { ege_bar(left, top, right, bottom, self.mut_ptr()) };
+        self.backend_mut().bar(left, top, right, bottom)
     }
 
     /// Draw a block.
@@ -669,7 +714,11 @@ pub trait Draw: DrawableDevice {
     /// * `bottom` - The bottom position of the block.
     /// * `depth` - The depth of the block.
     /// * `topflag` - Whether to fill the top of the block.
-    fn bar3d(&mut self, left: i32, top: i32, right: i32, bottom: i32, depth: i32, topflag: bool) {
+    #[cfg(feature = "ege")]
+    fn bar3d(&mut self, left: i32, top: i32, right: i32, bottom: i32, depth: i32, topflag: bool)
+    where
+        Self: EgeDevice,
+    {
         unsafe {
             ege_bar3d(
                 left,
@@ -688,14 +737,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The vertices of the polygon.
     fn polygon(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 3);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_polygon(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().polygon(points)
     }
 
     /// Draw a filled polygon.
@@ -703,14 +745,7 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The vertices of the polygon.
     fn fillpoly(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 3);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_fillpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().fillpoly(points)
     }
 
     /// Draw a solid polygon.
@@ -718,21 +753,18 @@ pub trait Draw: DrawableDevice {
     /// # Parameters
     /// * `points` - The vertices of the polygon.
     fn solidpoly(&mut self, points: &[Point]) {
-        #[cfg(debug_assertions)]
-        assert!(points.len() >= 3);
-        let points = points
-            .into_iter()
-            .map(|&Point { x, y }| [x, y])
-            .flatten()
-            .collect::<Vec<_>>();
-        unsafe { ege_solidpoly(points.len() as _, points.as_ptr(), self.mut_ptr()) };
+        self.backend_mut().solidpoly(points)
     }
 
     /// Draw a gradient polygon.
     ///
     /// # Parameters
     /// * `points` - The vertices of the polygon.
-    fn fillpoly_gradient<T: IntoARGB + Copy>(&mut self, points: &[PixelPoint<T, f32>]) {
+    #[cfg(feature = "ege")]
+    fn fillpoly_gradient<T: IntoARGB + Copy>(&mut self, points: &[PixelPoint<T, f32>])
+    where
+        Self: EgeDevice,
+    {
         #[cfg(debug_assertions)]
         assert!(points.len() >= 3);
         let points = points
@@ -753,7 +785,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn circle(&mut self, x: i32, y: i32, radius: i32) {
-        unsafe { ege_circle(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().circle(x, y, radius)
     }
 
     /// Draw a circle.
@@ -763,7 +795,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn circlef(&mut self, x: f32, y: f32, radius: f32) {
-        unsafe { ege_circlef(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().circlef(x, y, radius)
     }
 
     /// Draw a filled circle.
@@ -773,7 +805,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn fillcircle(&mut self, x: i32, y: i32, radius: i32) {
-        unsafe { ege_fillcircle(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().fillcircle(x, y, radius)
     }
 
     /// Draw a filled circle.
@@ -783,7 +815,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn fillcirclef(&mut self, x: f32, y: f32, radius: f32) {
-        unsafe { ege_fillcirclef(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().fillcirclef(x, y, radius)
     }
 
     /// Draw a ellipse.
@@ -794,7 +826,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn fillellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
-        unsafe { ege_fillellipse(x, y, rx, ry, self.mut_ptr()) };
+        self.backend_mut().fillellipse(x, y, rx, ry)
     }
 
     /// Draw a ellipse.
@@ -805,7 +837,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn fillellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
-        unsafe { ege_fillellipsef(x, y, rx, ry, self.mut_ptr()) };
+        self.backend_mut().fillellipsef(x, y, rx, ry)
     }
 
     /// Draw a solid circle.
@@ -815,7 +847,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn solidcircle(&mut self, x: i32, y: i32, radius: i32) {
-        unsafe { ege_solidcircle(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().solidcircle(x, y, radius)
     }
 
     /// Draw a solid circle.
@@ -825,7 +857,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - The y position of the center.
     /// * `radius` - The radius of the circle.
     fn solidcirclef(&mut self, x: f32, y: f32, radius: f32) {
-        unsafe { ege_solidcirclef(x, y, radius, self.mut_ptr()) };
+        self.backend_mut().solidcirclef(x, y, radius)
     }
 
     /// Draw a solid ellipse.
@@ -836,7 +868,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn solidellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32) {
-        unsafe { ege_solidellipse(x, y, rx, ry, self.mut_ptr()) };
+        self.backend_mut().solidellipse(x, y, rx, ry)
     }
 
     /// Draw a solid ellipse.
@@ -847,7 +879,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the ellipse.
     /// * `ry` - The y radius of the ellipse.
     fn solidellipsef(&mut self, x: f32, y: f32, rx: f32, ry: f32) {
-        unsafe { ege_solidellipsef(x, y, rx, ry, self.mut_ptr()) };
+        self.backend_mut().solidellipsef(x, y, rx, ry)
     }
 
     /// Draw a pie.
@@ -860,7 +892,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn pie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
-        unsafe { ege_pie(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().pie(x, y, start, end, rx, ry)
     }
 
     /// Draw a pie.
@@ -873,7 +905,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn pief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
-        unsafe { ege_pief(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().pief(x, y, start, end, rx, ry)
     }
 
     /// Draw a filled pie.
@@ -886,7 +918,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn fillpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
-        unsafe { ege_fillpie(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().fillpie(x, y, start, end, rx, ry)
     }
 
     /// Draw a filled pie.
@@ -899,7 +931,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn fillpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
-        unsafe { ege_fillpief(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().fillpief(x, y, start, end, rx, ry)
     }
 
     /// Draw a solid pie.
@@ -912,7 +944,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn solidpie(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
-        unsafe { ege_solidpie(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().solidpie(x, y, start, end, rx, ry)
     }
 
     /// Draw a solid pie.
@@ -925,7 +957,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the pie.
     /// * `ry` - The y radius of the pie.
     fn solidpief(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
-        unsafe { ege_solidpief(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().solidpief(x, y, start, end, rx, ry)
     }
 
     /// Draw a sector.
@@ -938,7 +970,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the sector.
     /// * `ry` - The y radius of the sector.
     fn sector(&mut self, x: i32, y: i32, start: i32, end: i32, rx: i32, ry: i32) {
-        unsafe { ege_sector(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().sector(x, y, start, end, rx, ry)
     }
 
     /// Draw a sector.
@@ -951,7 +983,7 @@ pub trait Draw: DrawableDevice {
     /// * `rx` - The x radius of the sector.
     /// * `ry` - The y radius of the sector.
     fn sectorf(&mut self, x: f32, y: f32, start: f32, end: f32, rx: f32, ry: f32) {
-        unsafe { ege_sectorf(x, y, start, end, rx, ry, self.mut_ptr()) };
+        self.backend_mut().sectorf(x, y, start, end, rx, ry)
     }
 
     /// Draw a pieslice.
@@ -963,7 +995,7 @@ pub trait Draw: DrawableDevice {
     /// * `end` - The end angle of the pieslice.
     /// * `radius` - The radius of the pieslice.
     fn pieslice(&mut self, x: i32, y: i32, start: i32, end: i32, radius: i32) {
-        unsafe { ege_pieslice(x, y, start, end, radius, self.mut_ptr()) };
+        self.backend_mut().pieslice(x, y, start, end, radius)
     }
 
     /// Draw a pieslice.
@@ -975,7 +1007,7 @@ pub trait Draw: DrawableDevice {
     /// * `end` - The end angle of the pieslice.
     /// * `radius` - The radius of the pieslice.
     fn pieslicef(&mut self, x: f32, y: f32, start: f32, end: f32, radius: f32) {
-        unsafe { ege_pieslicef(x, y, start, end, radius, self.mut_ptr()) };
+        self.backend_mut().pieslicef(x, y, start, end, radius)
     }
 
     /// Draw a rounded rectangle.
@@ -987,7 +1019,11 @@ pub trait Draw: DrawableDevice {
     /// * `bottom` - The bottom position of the rectangle.
     /// * `rx` - The x radius of the rounded corners.
     /// * `ry` - The y radius of the rounded corners.
-    fn roundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32) {
+    #[cfg(feature = "ege")]
+    fn roundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_roundrect1(left, top, right, bottom, rx, ry, self.mut_ptr()) };
     }
 
@@ -1000,7 +1036,11 @@ pub trait Draw: DrawableDevice {
     /// * `bottom` - The bottom position of the rectangle.
     /// * `rx` - The x radius of the rounded corners.
     /// * `ry` - The y radius of the rounded corners.
-    fn fillroundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32) {
+    #[cfg(feature = "ege")]
+    fn fillroundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_fillroundrect1(left, top, right, bottom, rx, ry, self.mut_ptr()) };
     }
 
@@ -1013,7 +1053,11 @@ pub trait Draw: DrawableDevice {
     /// * `bottom` - The bottom position of the rectangle.
     /// * `rx` - The x radius of the rounded corners.
     /// * `ry` - The y radius of the rounded corners.
-    fn solidroundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32) {
+    #[cfg(feature = "ege")]
+    fn solidroundrect(&mut self, left: i32, top: i32, right: i32, bottom: i32, rx: i32, ry: i32)
+    where
+        Self: EgeDevice,
+    {
         unsafe { ege_solidroundrect1(left, top, right, bottom, rx, ry, self.mut_ptr()) };
     }
 
@@ -1024,9 +1068,7 @@ pub trait Draw: DrawableDevice {
     /// * `y` - Fill the y-position of any point within the region.
     /// * `color` - Fill the color of the boundary of the area.
     fn floodfill(&mut self, x: i32, y: i32, color: impl IntoARGB) {
-        let color = color.into_argb();
-        let color = unsafe { *(&color as *const _ as *const i32) };
-        unsafe { ege_floodfill(x, y, color, self.mut_ptr()) };
+        self.backend_mut().floodfill(x, y, color.into_argb())
     }
 
     /// Specify the surface color filling area.
@@ -1036,14 +1078,18 @@ pub trait Draw: DrawableDevice {
     /// * `y` - Fill the y-position of any point within the region.
     /// * `color` - Fill the color of the surface of the area.
     fn floodfillsurface(&mut self, x: i32, y: i32, color: impl IntoARGB) {
-        unsafe { ege_floodfillsurface(x, y, color.into_argb(), self.mut_ptr()) };
+        self.backend_mut().floodfillsurface(x, y, color.into_argb())
     }
 
     /// Output text at current position.
     ///
     /// # Parameters
     /// * `text` - The text to output.
-    fn outtext(&mut self, text: &str) {
+    #[cfg(feature = "ege")]
+    fn outtext(&mut self, text: &str)
+    where
+        Self: EgeDevice,
+    {
         let text = text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
         unsafe { ege_outtext1(text.as_ptr(), self.mut_ptr()) };
     }
@@ -1056,7 +1102,11 @@ pub trait Draw: DrawableDevice {
     /// * `w` - The width of the rectangle.
     /// * `h` - The height of the rectangle.
     /// * `text` - The text to output.
-    fn outtextrect(&mut self, x: i32, y: i32, w: i32, h: i32, text: &str) {
+    #[cfg(feature = "ege")]
+    fn outtextrect(&mut self, x: i32, y: i32, w: i32, h: i32, text: &str)
+    where
+        Self: EgeDevice,
+    {
         let text = text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
         unsafe { ege_outtextrect1(x, y, w, h, text.as_ptr(), self.mut_ptr()) };
     }
@@ -1067,17 +1117,22 @@ pub trait Draw: DrawableDevice {
     /// * `x` - The x position.
     /// * `y` - The y position.
     /// * `text` - The text to output.
-    fn outtextxy(&mut self, x: i32, y: i32, text: &str) {
+    #[cfg(feature = "ege")]
+    fn outtextxy(&mut self, x: i32, y: i32, text: &str)
+    where
+        Self: EgeDevice,
+    {
         let text = text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
         unsafe { ege_outtextxy1(x, y, text.as_ptr(), self.mut_ptr()) };
     }
 }
 
+#[cfg(feature = "ege")]
 /// High-quality drawing functions.
 ///
 /// # Note
 /// They support anti aliasing and transparent channels.
-pub trait HighDraw: DrawableDevice {
+pub trait HighDraw: EgeDevice {
     /// Enable anti aliasing.
     ///
     /// # Parameters
@@ -1086,6 +1141,51 @@ pub trait HighDraw: DrawableDevice {
         unsafe { ege_ege_enable_aa(enable as _, self.mut_ptr()) };
     }
 
+    /// Set the dash pattern of the lines.
+    ///
+    /// # Parameters
+    /// * `dash` - The dash pattern, `None` draws solid lines.
+    ///
+    /// # Note
+    /// The pattern is part of the line style of `line`, `drawpoly`, `bezier`,
+    /// `drawpath` and `drawpath_at`. They are flattened and every dash is drawn
+    /// as a polyline with the current line width and caps. The pattern restarts
+    /// at every subpath.
+    fn setlinedash(&mut self, dash: Option<DashPattern>) {
+        self.state_mut().dash = dash;
+    }
+
+    /// Get the dash pattern of the lines.
+    ///
+    /// # Return
+    /// The dash pattern, `None` if lines are solid.
+    fn getlinedash(&self) -> Option<&DashPattern> {
+        self.state().dash.as_ref()
+    }
+
+    /// Set the brush of the fills.
+    ///
+    /// # Parameters
+    /// * `brush` - The brush, `None` fills with the fill color.
+    ///
+    /// # Note
+    /// The brush is part of the fill style of `fillrect`, `fillroundrect`, `fillpoly`,
+    /// `fillpie`, `fillellipse`, `fillclosedcurve`, `fillpath` and `fillpath_at`.
+    /// Their shapes are flattened, rasterized in Rust with anti aliasing and blended
+    /// into the pixel buffer. The transformation matrix and the viewport are applied,
+    /// the coordinates of the brush are those of the shapes.
+    fn setfillbrush(&mut self, brush: Option<Brush>) {
+        self.state_mut().brush = brush;
+    }
+
+    /// Get the brush of the fills.
+    ///
+    /// # Return
+    /// The brush, `None` if the fill color is used.
+    fn getfillbrush(&self) -> Option<&Brush> {
+        self.state().brush.as_ref()
+    }
+
     /// Draw a line.
     ///
     /// # Parameters
@@ -1094,6 +1194,10 @@ pub trait HighDraw: DrawableDevice {
     /// * `x2` - The x position of the end point.
     /// * `y2` - The y position of the end point.
     fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        let points = vec![Point { x: x1, y: y1 }, Point { x: x2, y: y2 }];
+        if drawdashed(self, || vec![(points, false)]) {
+            return;
+        }
         unsafe { ege_ege_line(x1, y1, x2, y2, self.mut_ptr()) };
     }
 
@@ -1189,6 +1293,9 @@ pub trait HighDraw: DrawableDevice {
     fn drawpoly(&mut self, points: &[Point<f32>]) {
         #[cfg(debug_assertions)]
         assert!(points.len() >= 2);
+        if drawdashed(self, || vec![(points.to_vec(), false)]) {
+            return;
+        }
         let points = points
             .into_iter()
             .map(|&Point { x, y }| ege_ege_point { x, y })
@@ -1199,10 +1306,32 @@ pub trait HighDraw: DrawableDevice {
     /// Draw a bezier curve.
     ///
     /// # Parameters
-    /// * `points` - The control points of the bezier curve.
+    /// * `points` - The control points of the bezier curve: the start point and
+    ///   three points for every segment.
+    ///
+    /// # Panics
+    /// In debug builds, if the number of points is not `3 * n + 1` with `n >= 1`.
+    ///
+    /// # Note
+    /// In release builds, nothing is drawn for such a number of points.
     fn bezier(&mut self, points: &[Point<f32>]) {
+        let valid = points.len() >= 4 && points.len() % 3 == 1;
         #[cfg(debug_assertions)]
-        assert!(points.len() >= 4 && points.len() % 3 == 1);
+        assert!(valid, "A bezier curve needs 3 * n + 1 points, got {}.", points.len());
+        if !valid {
+            return;
+        }
+        let dashed = drawdashed(self, || {
+            let mut data = PathData::new();
+            data.move_to(points[0].x, points[0].y);
+            for c in points[1..].chunks_exact(3) {
+                data.cubic_to(c[0].x, c[0].y, c[1].x, c[1].y, c[2].x, c[2].y);
+            }
+            data.polylines(FLATNESS)
+        });
+        if dashed {
+            return;
+        }
         let points = points
             .into_iter()
             .map(|&Point { x, y }| ege_ege_point { x, y })
@@ -1250,6 +1379,9 @@ pub trait HighDraw: DrawableDevice {
     /// * `w` - The width of the rectangle.
     /// * `h` - The height of the rectangle.
     fn fillrect(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        if fillbrushed(self, PathFillMode::Winding, || vec![rect_polygon(x, y, w, h)]) {
+            return;
+        }
         unsafe { ege_ege_fillrect(x, y, w, h, self.mut_ptr()) };
     }
 
@@ -1262,6 +1394,24 @@ pub trait HighDraw: DrawableDevice {
     /// * `h` - The height of the rectangle.
     /// * `radius` - The radius of the rounded corners.
     fn fillroundrect(&mut self, x: f32, y: f32, w: f32, h: f32, radius: f32) {
+        let brushed = fillbrushed(self, PathFillMode::Winding, || {
+            let r = radius.clamp(0.0, w.abs().min(h.abs()) / 2.0);
+            let mut data = PathData::new();
+            data.move_to(x + r, y)
+                .line_to(x + w - r, y)
+                .arc_to(r, r, 0.0, false, true, x + w, y + r)
+                .line_to(x + w, y + h - r)
+                .arc_to(r, r, 0.0, false, true, x + w - r, y + h)
+                .line_to(x + r, y + h)
+                .arc_to(r, r, 0.0, false, true, x, y + h - r)
+                .line_to(x, y + r)
+                .arc_to(r, r, 0.0, false, true, x + r, y)
+                .close();
+            polygons(&data)
+        });
+        if brushed {
+            return;
+        }
         unsafe { ege_ege_fillroundrect(x, y, w, h, radius, self.mut_ptr()) };
     }
 
@@ -1272,6 +1422,9 @@ pub trait HighDraw: DrawableDevice {
     fn fillpoly(&mut self, points: &[Point<f32>]) {
         #[cfg(debug_assertions)]
         assert!(points.len() >= 3);
+        if fillbrushed(self, PathFillMode::Alternate, || vec![points.to_vec()]) {
+            return;
+        }
         let points = points
             .into_iter()
             .map(|&Point { x, y }| ege_ege_point { x, y })
@@ -1289,6 +1442,24 @@ pub trait HighDraw: DrawableDevice {
     /// * `start` - The start angle of the pie.
     /// * `sweep` - The sweep angle of the pie.
     fn fillpie(&mut self, x: f32, y: f32, w: f32, h: f32, start: f32, sweep: f32) {
+        let brushed = fillbrushed(self, PathFillMode::Winding, || {
+            let (rx, ry) = (w / 2.0, h / 2.0);
+            let (cx, cy) = (x + rx, y + ry);
+            let sweep = sweep.clamp(-360.0, 360.0);
+            let steps = ((sweep.abs() / 360.0 * rx.abs().max(ry.abs()) * 2.0).ceil() as usize).max(8);
+            let mut points = vec![Point { x: cx, y: cy }];
+            points.extend((0..=steps).map(|i| {
+                let angle = (start + sweep * i as f32 / steps as f32).to_radians();
+                Point {
+                    x: cx + rx * angle.cos(),
+                    y: cy + ry * angle.sin(),
+                }
+            }));
+            vec![points]
+        });
+        if brushed {
+            return;
+        }
         unsafe { ege_ege_fillpie(x, y, w, h, start, sweep, self.mut_ptr()) };
     }
 
@@ -1300,6 +1471,18 @@ pub trait HighDraw: DrawableDevice {
     /// * `w` - The width of the ellipse.
     /// * `h` - The height of the ellipse.
     fn fillellipse(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let brushed = fillbrushed(self, PathFillMode::Winding, || {
+            let (rx, ry) = (w / 2.0, h / 2.0);
+            let mut data = PathData::new();
+            data.move_to(x, y + ry)
+                .arc_to(rx, ry, 0.0, false, true, x + w, y + ry)
+                .arc_to(rx, ry, 0.0, false, true, x, y + ry)
+                .close();
+            polygons(&data)
+        });
+        if brushed {
+            return;
+        }
         unsafe { ege_ege_fillellipse(x, y, w, h, self.mut_ptr()) };
     }
 
@@ -1311,6 +1494,30 @@ pub trait HighDraw: DrawableDevice {
     fn fillclosedcurve(&mut self, points: &[Point<f32>], tension: f32) {
         #[cfg(debug_assertions)]
         assert!(points.len() >= 3);
+        let brushed = fillbrushed(self, PathFillMode::Alternate, || {
+            // The cardinal spline of GDI+ as bezier segments.
+            let n = points.len();
+            let k = tension / 3.0;
+            let mut data = PathData::new();
+            data.move_to(points[0].x, points[0].y);
+            for i in 0..n {
+                let (p0, p1) = (points[(i + n - 1) % n], points[i]);
+                let (p2, p3) = (points[(i + 1) % n], points[(i + 2) % n]);
+                data.cubic_to(
+                    p1.x + k * (p2.x - p0.x),
+                    p1.y + k * (p2.y - p0.y),
+                    p2.x - k * (p3.x - p1.x),
+                    p2.y - k * (p3.y - p1.y),
+                    p2.x,
+                    p2.y,
+                );
+            }
+            data.close();
+            polygons(&data)
+        });
+        if brushed {
+            return;
+        }
         let points = points
             .into_iter()
             .map(|&Point { x, y }| ege_ege_point { x, y })
@@ -1336,6 +1543,9 @@ pub trait HighDraw: DrawableDevice {
     /// # Parameters
     /// * `path` - The path to draw.
     fn drawpath(&mut self, path: &Path) {
+        if drawdashed(self, || PathData::from(path).polylines(FLATNESS)) {
+            return;
+        }
         unsafe { ege_ege_drawpath(path.ptr, self.mut_ptr()) };
     }
 
@@ -1346,6 +1556,17 @@ pub trait HighDraw: DrawableDevice {
     /// * `x` - The x position.
     /// * `y` - The y position.
     fn drawpath_at(&mut self, path: &Path, x: f32, y: f32) {
+        let dashed = drawdashed(self, || {
+            let mut polylines = PathData::from(path).polylines(FLATNESS);
+            for point in polylines.iter_mut().flat_map(|(points, _)| points) {
+                point.x += x;
+                point.y += y;
+            }
+            polylines
+        });
+        if dashed {
+            return;
+        }
         unsafe { ege_ege_drawpath1(path.ptr, x, y, self.mut_ptr()) };
     }
 
@@ -1354,6 +1575,9 @@ pub trait HighDraw: DrawableDevice {
     /// # Parameters
     /// * `path` - The path to draw.
     fn fillpath(&mut self, path: &Path) {
+        if fillbrushed(self, path.getfillmode(), || polygons(&PathData::from(path))) {
+            return;
+        }
         unsafe { ege_ege_fillpath(path.ptr, self.mut_ptr()) };
     }
 
@@ -1364,16 +1588,99 @@ pub trait HighDraw: DrawableDevice {
     /// * `x` - The x position.
     /// * `y` - The y position.
     fn fillpath_at(&mut self, path: &Path, x: f32, y: f32) {
+        let brushed = fillbrushed(self, path.getfillmode(), || {
+            let mut polygons = polygons(&PathData::from(path));
+            for point in polygons.iter_mut().flatten() {
+                point.x += x;
+                point.y += y;
+            }
+            polygons
+        });
+        if brushed {
+            return;
+        }
         unsafe { ege_ege_fillpath1(path.ptr, x, y, self.mut_ptr()) };
     }
 }
 
+#[cfg(feature = "ege")]
+/// Fill polygons with the brush of a device into its pixel buffer.
+///
+/// # Returns
+/// `false` if the device fills with the fill color, nothing is drawn then.
+fn fillbrushed(
+    device: &mut (impl EgeDevice + ?Sized),
+    mode: PathFillMode,
+    polygons: impl FnOnce() -> Vec<Vec<Point<f32>>>,
+) -> bool {
+    let Some(brush) = device.state_mut().brush.take() else {
+        return false;
+    };
+    let ptr = device.mut_ptr();
+    let mut matrix = ege_ege_transform_matrix {
+        m11: 1.0,
+        m12: 0.0,
+        m21: 0.0,
+        m22: 1.0,
+        m31: 0.0,
+        m32: 0.0,
+    };
+    let (mut left, mut top, mut right, mut bottom, mut clip) = (0, 0, 0, 0, 0);
+    unsafe {
+        ege_ege_get_transform(&mut matrix, ptr);
+        ege_getviewport(&mut left, &mut top, &mut right, &mut bottom, &mut clip, ptr);
+    }
+    let backend = device.backend_mut();
+    let (width, height) = (backend.getwidth(), backend.getheight());
+    let mut target = ImageViewMut::new(backend.getbuffer_mut(), width as usize, width, height);
+    // Without clipping the shapes may cover the whole device.
+    let (x0, y0, x1, y1) = match clip {
+        0 => (0, 0, width as i32, height as i32),
+        _ => (left.max(0), top.max(0), right.min(width as i32), bottom.min(height as i32)),
+    };
+    let rect = Rect {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    };
+    let view = target.view_mut(rect).filter(|_| rect.width > 0 && rect.height > 0);
+    if let Some(mut view) = view {
+        // The shapes are drawn relative to the viewport, the view starts at the clip.
+        let ege_ege_transform_matrix { m11, m12, m21, m22, m31, m32 } = matrix;
+        let (dx, dy) = ((left - x0) as f32, (top - y0) as f32);
+        let transform = [m11, m12, m21, m22, m31 + dx, m32 + dy];
+        brush::fill(&mut view, &polygons(), mode, &brush, transform);
+    }
+    device.state_mut().brush = Some(brush);
+    true
+}
+
+#[cfg(feature = "ege")]
+/// The closed polygons of a flattened path.
+fn polygons(data: &PathData) -> Vec<Vec<Point<f32>>> {
+    data.polylines(FLATNESS).into_iter().map(|(points, _)| points).collect()
+}
+
+#[cfg(feature = "ege")]
+/// The corners of a rectangle.
+fn rect_polygon(x: f32, y: f32, w: f32, h: f32) -> Vec<Point<f32>> {
+    vec![
+        Point { x, y },
+        Point { x: x + w, y },
+        Point { x: x + w, y: y + h },
+        Point { x, y: y + h },
+    ]
+}
+
+#[cfg(feature = "ege")]
 fn rop3(gen_rop3: impl Fn(u32, u32, u32) -> u32) -> u32 {
     gen_rop3(0xF00000, 0xCC0000, 0xAA0000)
 }
 
 /// Rectangle.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect<T = i32> {
     pub x: T,
     pub y: T,
@@ -1381,7 +1688,8 @@ pub struct Rect<T = i32> {
     pub height: T,
 }
 
-pub trait ImageDraw: DrawableDevice {
+#[cfg(feature = "ege")]
+pub trait ImageDraw: EgeDevice {
     /// Draw an image.
     ///
     /// # Parameters
@@ -1451,6 +1759,31 @@ pub trait ImageDraw: DrawableDevice {
         }
     }
 
+    /// Draw a region of an image.
+    ///
+    /// # Parameters
+    /// * `x` - The x position of the top-left corner.
+    /// * `y` - The y position of the top-left corner.
+    /// * `image` - The image to draw.
+    /// * `src` - The source region, e.g. a sprite of an `Atlas`.
+    /// * `gen_rop3` - A function to generate the ROP3 code.
+    fn putimage_region(
+        &mut self,
+        x: i32,
+        y: i32,
+        image: &Image,
+        src: Rect,
+        gen_rop3: impl Fn(u32, u32, u32) -> u32,
+    ) {
+        let dest = Rect {
+            x,
+            y,
+            width: src.width,
+            height: src.height,
+        };
+        self.putimage_with_size(dest, image, src.x, src.y, gen_rop3);
+    }
+
     /// Draw an image with a specified scale.
     ///
     /// # Parameters
@@ -1458,13 +1791,30 @@ pub trait ImageDraw: DrawableDevice {
     /// * `image` - The image to draw.
     /// * `src` - The source rectangle.
     /// * `gen_rop3` - A function to generate the ROP3 code.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// `Filter::Nearest` is drawn by EGE. With the other filters the region is scaled
+    /// by `resample` first and then combined with the ROP3 code by EGE.
     fn putimage_with_scale(
         &mut self,
         dest: Rect,
         image: &Image,
         src: Rect,
         gen_rop3: impl Fn(u32, u32, u32) -> u32,
+        filter: impl Into<Filter>,
     ) {
+        let filter = filter.into();
+        if filter != Filter::Nearest {
+            let Some(source) = image.view(region(image, src)) else {
+                return;
+            };
+            let mut scaled = Image::new(dest.width.max(0) as u32, dest.height.max(0) as u32);
+            resample(&source, &mut scaled.as_view_mut(), filter);
+            let src = whole(&scaled);
+            self.putimage_with_scale(dest, &scaled, src, gen_rop3, Filter::Nearest);
+            return;
+        }
         if self.mut_ptr().is_null() {
             unsafe {
                 ege_putimage2(
@@ -1505,14 +1855,33 @@ pub trait ImageDraw: DrawableDevice {
     /// * `image` - The image to draw.
     /// * `dest` - The destination rectangle.
     /// * `src` - The source rectangle.
-    /// * `smooth` - Whether to use smooth scaling.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// `Filter::Nearest` and `Filter::Bilinear` are drawn by EGE, the other filters
+    /// by `resample` into the pixel buffer.
     fn putimage_with_alpha(
         &mut self,
         image: &Image,
         dest: Rect,
         src: Rect,
-        smooth: bool,
-    ) -> Result<(), ImageError> {
+        filter: impl Into<Filter>,
+    ) -> Result<(), ImageError>
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        let Some(smooth) = filter.smooth() else {
+            let warp = Warp {
+                transform: scale_transform(image, dest, src),
+                filter,
+                opacity: 1.0,
+                alpha: Some(AlphaType::Straight),
+                clamp: true,
+            };
+            putimage_warped(self, image, src, warp);
+            return Ok(());
+        };
         let result = unsafe {
             ege_putimage_withalpha1(
                 self.mut_ptr(),
@@ -1538,17 +1907,35 @@ pub trait ImageDraw: DrawableDevice {
     /// * `dest` - The destination rectangle.
     /// * `alpha` - The alpha value.
     /// * `src` - The source rectangle.
-    /// * `smooth` - Whether to use smooth scaling.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
     /// * `alpha_type` - The alpha type.
+    ///
+    /// # Note
+    /// See `putimage_with_alpha`.
     fn putimage_alphablender(
         &mut self,
         image: &Image,
         dest: Rect,
         alpha: u8,
         src: Rect,
-        smooth: bool,
+        filter: impl Into<Filter>,
         alpha_type: AlphaType,
-    ) -> Result<(), ImageError> {
+    ) -> Result<(), ImageError>
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        let Some(smooth) = filter.smooth() else {
+            let warp = Warp {
+                transform: scale_transform(image, dest, src),
+                filter,
+                opacity: alpha as f32 / 255.0,
+                alpha: Some(alpha_type),
+                clamp: true,
+            };
+            putimage_warped(self, image, src, warp);
+            return Ok(());
+        };
         let result = unsafe {
             ege_putimage_alphablend3(
                 self.mut_ptr(),
@@ -1672,14 +2059,18 @@ pub trait ImageDraw: DrawableDevice {
     ///
     /// # Parameters
     /// * `image` - The image to draw.
-    /// * `x_dest` - The x position of the top-left corner of the destination image.
-    /// * `y_dest` - The y position of the top-left corner of the destination image.
-    /// * `x_center` - The x position of the center of the rotation.
-    /// * `y_center` - The y position of the center of the rotation.
-    /// * `radian` - The rotation angle in radian.
+    /// * `x_dest` - The x position the center of the rotation is drawn at.
+    /// * `y_dest` - The y position the center of the rotation is drawn at.
+    /// * `x_center` - The x position of the center of the rotation, from `0.0` to `1.0`.
+    /// * `y_center` - The y position of the center of the rotation, from `0.0` to `1.0`.
+    /// * `radian` - The clockwise rotation angle in radian.
     /// * `use_alpha` - Whether to use alpha blending.
     /// * `alpha` - The alpha value.
-    /// * `smooth` - Whether to use smooth scaling.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// `Filter::Nearest` and `Filter::Bilinear` are drawn by EGE, the other filters
+    /// by `warp` into the pixel buffer.
     fn putimage_rotate(
         &mut self,
         image: &Image,
@@ -1690,8 +2081,23 @@ pub trait ImageDraw: DrawableDevice {
         radian: f32,
         use_alpha: bool,
         alpha: Option<u8>,
-        smooth: bool,
-    ) -> Result<(), ImageError> {
+        filter: impl Into<Filter>,
+    ) -> Result<(), ImageError>
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        let Some(smooth) = filter.smooth() else {
+            let warp = Warp {
+                transform: rotate_transform(image, x_dest, y_dest, x_center, y_center, radian, 1.0),
+                filter,
+                opacity: alpha.map_or(1.0, |alpha| alpha as f32 / 255.0),
+                alpha: use_alpha.then_some(AlphaType::Straight),
+                clamp: false,
+            };
+            putimage_warped(self, image, whole(image), warp);
+            return Ok(());
+        };
         let result = unsafe {
             ege_putimage_rotate(
                 self.mut_ptr(),
@@ -1717,15 +2123,18 @@ pub trait ImageDraw: DrawableDevice {
     ///
     /// # Parameters
     /// * `image` - The image to draw.
-    /// * `x_dest` - The x position of the top-left corner of the destination image.
-    /// * `y_dest` - The y position of the top-left corner of the destination image.
-    /// * `x_center` - The x position of the center of the rotation.
-    /// * `y_center` - The y position of the center of the rotation.
-    /// * `radian` - The rotation angle in radian.
+    /// * `x_dest` - The x position the center of the rotation is drawn at.
+    /// * `y_dest` - The y position the center of the rotation is drawn at.
+    /// * `x_center` - The x position of the center of the rotation, from `0.0` to `1.0`.
+    /// * `y_center` - The y position of the center of the rotation, from `0.0` to `1.0`.
+    /// * `radian` - The clockwise rotation angle in radian.
     /// * `zoom` - The zoom factor.
     /// * `use_alpha` - Whether to use alpha blending.
     /// * `alpha` - The alpha value.
-    /// * `smooth` - Whether to use smooth scaling.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// See `putimage_rotate`.
     fn putimage_rotatezoom(
         &mut self,
         image: &Image,
@@ -1737,8 +2146,23 @@ pub trait ImageDraw: DrawableDevice {
         zoom: f32,
         use_alpha: bool,
         alpha: Option<u8>,
-        smooth: bool,
-    ) -> Result<(), ImageError> {
+        filter: impl Into<Filter>,
+    ) -> Result<(), ImageError>
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        let Some(smooth) = filter.smooth() else {
+            let warp = Warp {
+                transform: rotate_transform(image, x_dest, y_dest, x_center, y_center, radian, zoom),
+                filter,
+                opacity: alpha.map_or(1.0, |alpha| alpha as f32 / 255.0),
+                alpha: use_alpha.then_some(AlphaType::Straight),
+                clamp: false,
+            };
+            putimage_warped(self, image, whole(image), warp);
+            return Ok(());
+        };
         let result = unsafe {
             ege_putimage_rotatezoom(
                 self.mut_ptr(),
@@ -1773,6 +2197,11 @@ pub trait ImageDraw: DrawableDevice {
     /// * `transparent` - The alpha image.
     /// * `radian` - The rotation angle in radian.
     /// * `zoom` - The zoom factor.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// `Filter::Nearest` is drawn by EGE, the other filters by `warp` into the pixel
+    /// buffer. The transparent pixels are removed before they are interpolated.
     fn putimage_rotatetransparent(
         &mut self,
         image: &Image,
@@ -1784,7 +2213,37 @@ pub trait ImageDraw: DrawableDevice {
         transparent: impl IntoARGB,
         radian: f32,
         zoom: f32,
-    ) -> Result<(), ImageError> {
+        filter: impl Into<Filter>,
+    ) -> Result<(), ImageError>
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        if filter != Filter::Nearest {
+            let src = region(image, src);
+            let Some(source) = image.view(src) else {
+                return Ok(());
+            };
+            let transparent = transparent.into_argb();
+            let mut keyed = Image::new(src.width as u32, src.height as u32);
+            let mut pixels = keyed.as_view_mut();
+            pixels.copy_from(&source);
+            pixels.transform(|color| match (color ^ transparent) & 0xFFFFFF {
+                0 => 0,
+                _ => color | 0xFF000000,
+            });
+            let (cx, cy) = ((x_center_src - src.x) as f32, (y_center_src - src.y) as f32);
+            let (x, y) = (x_center_dest as f32, y_center_dest as f32);
+            let warp = Warp {
+                transform: rotation(x, y, cx, cy, radian, zoom),
+                filter,
+                opacity: 1.0,
+                alpha: Some(AlphaType::Straight),
+                clamp: false,
+            };
+            putimage_warped(self, &keyed, whole(&keyed), warp);
+            return Ok(());
+        }
         let result = unsafe {
             ege_putimage_rotatetransparent1(
                 self.mut_ptr(),
@@ -1837,6 +2296,34 @@ pub trait ImageDraw: DrawableDevice {
         Image::handle_result(result)
     }
 
+    /// Combine a region of an image with the pixels of the device.
+    ///
+    /// # Parameters
+    /// * `image` - The image to draw.
+    /// * `src` - The region of the image.
+    /// * `dest` - The position of the top-left corner of the region.
+    /// * `mode` - How the pixels are combined.
+    /// * `opacity` - Multiplied with the alpha of the image, from `0.0` to `1.0`.
+    /// * `alpha_type` - How the image and the device store their alpha.
+    ///
+    /// # Note
+    /// The pixels are combined in Rust, see `composite`.
+    fn composite(
+        &mut self,
+        image: &Image,
+        src: Rect,
+        dest: Point,
+        mode: BlendMode,
+        opacity: f32,
+        alpha_type: AlphaType,
+    ) where
+        Self: Sized,
+    {
+        let (width, height) = (self.getwidth(), self.getheight());
+        let mut target = ImageViewMut::new(self.getbuffer_mut(), width as usize, width, height);
+        composite::composite(&mut target, &image.as_view(), src, dest, mode, opacity, alpha_type);
+    }
+
     /// Draw an image with a specified position.
     ///
     /// # Parameters
@@ -1847,13 +2334,53 @@ pub trait ImageDraw: DrawableDevice {
         unsafe { ege_ege_drawimage(image.const_ptr(), x, y, self.mut_ptr()) };
     }
 
+    /// Draw a region of an image.
+    ///
+    /// # Parameters
+    /// * `image` - The image to draw.
+    /// * `x` - The x position of the top-left corner.
+    /// * `y` - The y position of the top-left corner.
+    /// * `src` - The source region, e.g. a sprite of an `Atlas`.
+    fn drawimage_region(&mut self, image: &Image, x: i32, y: i32, src: Rect)
+    where
+        Self: Sized,
+    {
+        let dest = Rect {
+            x,
+            y,
+            width: src.width,
+            height: src.height,
+        };
+        self.drawimage_with_scale(image, dest, src, Filter::Bilinear);
+    }
+
     /// Draw an image with scale.
     ///
     /// # Parameters
     /// * `image` - The image to draw.
     /// * `dest` - The destination rectangle.
-    /// * `src` - The source rectangle.
-    fn drawimage_with_scale(&mut self, image: &Image, dest: Rect, src: Rect) {
+    /// * `src` - The source rectangle, e.g. a sprite of an `Atlas`.
+    /// * `filter` - The interpolation, `true` and `false` select `Bilinear` and `Nearest`.
+    ///
+    /// # Note
+    /// `Filter::Bilinear` is drawn by EGE, the other filters by `resample` into the
+    /// pixel buffer.
+    fn drawimage_with_scale(&mut self, image: &Image, dest: Rect, src: Rect, filter: impl Into<Filter>)
+    where
+        Self: Sized,
+    {
+        let filter = filter.into();
+        if filter != Filter::Bilinear {
+            let warp = Warp {
+                transform: scale_transform(image, dest, src),
+                filter,
+                opacity: 1.0,
+                alpha: Some(AlphaType::Straight),
+                clamp: true,
+            };
+            putimage_warped(self, image, src, warp);
+            return;
+        }
         unsafe {
             ege_ege_drawimage1(
                 image.const_ptr(),
@@ -1871,7 +2398,120 @@ pub trait ImageDraw: DrawableDevice {
     }
 }
 
+#[cfg(feature = "ege")]
+/// The region of a whole image.
+fn whole(image: &Image) -> Rect {
+    Rect {
+        x: 0,
+        y: 0,
+        width: image.getwidth() as i32,
+        height: image.getheight() as i32,
+    }
+}
+
+#[cfg(feature = "ege")]
+/// The transformation of a scaled blit, in image coordinates.
+///
+/// Like EGE, an empty source size selects the size of the image.
+fn scale_transform(image: &Image, dest: Rect, src: Rect) -> [[f32; 3]; 2] {
+    let size = |len: i32, whole: u32| if len == 0 { whole as f32 } else { len as f32 };
+    let sx = dest.width as f32 / size(src.width, image.getwidth());
+    let sy = dest.height as f32 / size(src.height, image.getheight());
+    [
+        [sx, 0.0, dest.x as f32 - src.x as f32 * sx],
+        [0.0, sy, dest.y as f32 - src.y as f32 * sy],
+    ]
+}
+
+#[cfg(feature = "ege")]
+/// The transformation of a rotated blit, matching `putimage_rotatezoom` of EGE.
+fn rotate_transform(
+    image: &Image,
+    x_dest: i32,
+    y_dest: i32,
+    x_center: f32,
+    y_center: f32,
+    radian: f32,
+    zoom: f32,
+) -> [[f32; 3]; 2] {
+    let cx = x_center * image.getwidth() as f32;
+    let cy = y_center * image.getheight() as f32;
+    rotation(x_dest as f32, y_dest as f32, cx, cy, radian, zoom)
+}
+
+#[cfg(feature = "ege")]
+/// The transformation turning `(cx, cy)` of an image by `radian` clockwise around
+/// `(x, y)` of the device, then zooming.
+fn rotation(x: f32, y: f32, cx: f32, cy: f32, radian: f32, zoom: f32) -> [[f32; 3]; 2] {
+    let (sin, cos) = radian.sin_cos();
+    [
+        [cos * zoom, -sin * zoom, x - zoom * (cos * cx - sin * cy)],
+        [sin * zoom, cos * zoom, y - zoom * (sin * cx + cos * cy)],
+    ]
+}
+
+#[cfg(feature = "ege")]
+/// Clamp a region to an image, an empty size selects the rest of the image.
+fn region(image: &Image, src: Rect) -> Rect {
+    let (width, height) = (image.getwidth() as i32, image.getheight() as i32);
+    let size = |len: i32, start: i32, whole: i32| if len == 0 { whole - start } else { len };
+    let (x0, y0) = (src.x.clamp(0, width), src.y.clamp(0, height));
+    let x1 = (src.x + size(src.width, src.x, width)).clamp(x0, width);
+    let y1 = (src.y + size(src.height, src.y, height)).clamp(y0, height);
+    Rect {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    }
+}
+
+#[cfg(feature = "ege")]
+/// Draw a region of an image into the pixel buffer of a device.
+///
+/// # Parameters
+/// - `src`: The region of the image, an empty size selects the rest of the image.
+/// - `warp`: Its transformation maps image coordinates to device coordinates.
+fn putimage_warped(device: &mut impl GraphicsEnvironment, image: &Image, src: Rect, mut warp: Warp) {
+    let region = region(image, src);
+    let Some(source) = image.view(region) else {
+        return;
+    };
+    let [[a, b, c], [d, e, f]] = warp.transform;
+    let (x, y) = (region.x as f32, region.y as f32);
+    warp.transform = [[a, b, c + a * x + b * y], [d, e, f + d * x + e * y]];
+    let (dw, dh) = (device.getwidth(), device.getheight());
+    let mut target = ImageViewMut::new(device.getbuffer_mut(), dw as usize, dw, dh);
+    draw_warped(&source, &mut target, &warp);
+}
+
+#[cfg(feature = "ege")]
+/// Draw polylines with the dash pattern of a device and the current pen.
+///
+/// # Returns
+/// `false` if the device draws solid lines, nothing is drawn then.
+fn drawdashed(
+    device: &mut (impl EgeDevice + ?Sized),
+    polylines: impl FnOnce() -> Vec<(Vec<Point<f32>>, bool)>,
+) -> bool {
+    let Some(dash) = &device.state().dash else {
+        return false;
+    };
+    let dashes = dash.apply(&polylines());
+    let ptr = device.mut_ptr();
+    for dash in dashes.into_iter().filter(|dash| dash.len() >= 2) {
+        let points = dash
+            .into_iter()
+            .map(|Point { x, y }| ege_ege_point { x, y })
+            .collect::<Vec<_>>();
+        unsafe { ege_ege_drawpoly(points.len() as _, points.as_ptr(), ptr) };
+    }
+    true
+}
+
 impl<T: DrawableDevice> GraphicsEnvironment for T {}
 impl<T: DrawableDevice> Draw for T {}
-impl<T: DrawableDevice> HighDraw for T {}
-impl<T: DrawableDevice> ImageDraw for T {}
+#[cfg(feature = "ege")]
+impl<T: EgeDevice> HighDraw for T {}
+#[cfg(feature = "ege")]
+impl<T: EgeDevice> ImageDraw for T {}
@@ -1,38 +1,124 @@
 use mats::Mat;
+#[cfg(feature = "ege")]
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
-use crate::{DrawableDevice, GraphicsEnvironment, ImageDraw};
+#[cfg(feature = "ege")]
+use crate::backend::{self, DeviceState};
+use crate::ImageView;
+use crate::convolve::edge_index;
+#[cfg(any(feature = "ege", test))]
+use crate::AlphaType;
+#[cfg(feature = "ege")]
+use crate::{DrawableDevice, EgeDevice, GraphicsEnvironment, ImageDraw};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ImageError {
     #[error("Memory allocation failed during read operation.")]
     AllocError,
-    #[error("The file does not exist.")]
-    FileNotFound,
+    #[error("The file{} does not exist.", at(path))]
+    FileNotFound { path: Option<PathBuf> },
     #[error("Pointer conversion failed.")]
     NullPointer,
-    #[error("Reading failed.")]
-    IOError,
-    #[error("Other Unknown error.")]
-    UnknownError,
-    #[error("Path parsing error.")]
-    PathParserError,
+    #[error("Reading or writing{} failed.", at(path))]
+    IOError {
+        path: Option<PathBuf>,
+        #[source]
+        source: Option<std::io::Error>,
+    },
+    #[error("EGE reported the error code {code}{}.", at(path))]
+    GraphicsError { code: i32, path: Option<PathBuf> },
+    #[error("The path `{}` is not valid Unicode.", .0.display())]
+    PathParserError(PathBuf),
+    #[error("The image has no pixels.")]
+    EmptyImage,
+    #[error("Encoding or decoding{} failed: {source}", at(path))]
+    CodecError {
+        path: Option<PathBuf>,
+        #[source]
+        source: image::ImageError,
+    },
+}
+
+/// Format an optional path for error messages.
+fn at(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| format!(" `{}`", path.display()))
+        .unwrap_or_default()
+}
+
+impl ImageError {
+    /// Get the file involved in the failed operation, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::FileNotFound { path }
+            | Self::IOError { path, .. }
+            | Self::GraphicsError { path, .. }
+            | Self::CodecError { path, .. } => path.as_deref(),
+            Self::PathParserError(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Attach the file involved in the failed operation.
+    #[cfg(any(feature = "ege", feature = "software"))]
+    pub(crate) fn with_path(mut self, file: &Path) -> Self {
+        match &mut self {
+            Self::FileNotFound { path }
+            | Self::IOError { path, .. }
+            | Self::GraphicsError { path, .. }
+            | Self::CodecError { path, .. } => *path = Some(file.to_path_buf()),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl From<image::ImageError> for ImageError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::FileNotFound { path: None }
+            }
+            image::ImageError::IoError(e) => Self::IOError {
+                path: None,
+                source: Some(e),
+            },
+            source => Self::CodecError { path: None, source },
+        }
+    }
 }
 
 /// Image
+#[cfg(feature = "ege")]
 #[derive(Debug)]
 pub struct Image {
     ptr: *mut ege_IMAGE,
+    state: DeviceState,
 }
 
+#[cfg(feature = "ege")]
 impl Display for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Image({:p})", self.ptr)
     }
 }
 
+#[cfg(feature = "ege")]
 impl DrawableDevice for Image {
+    fn backend(&self) -> &dyn backend::Backend {
+        self
+    }
+
+    fn backend_mut(&mut self) -> &mut dyn backend::Backend {
+        self
+    }
+}
+
+#[cfg(feature = "ege")]
+impl EgeDevice for Image {
     fn const_ptr(&self) -> *const ege_IMAGE {
         self.ptr as *const _
     }
@@ -40,14 +126,24 @@ impl DrawableDevice for Image {
     fn mut_ptr(&mut self) -> *mut ege_IMAGE {
         self.ptr
     }
+
+    fn state(&self) -> &DeviceState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut DeviceState {
+        &mut self.state
+    }
 }
 
+#[cfg(feature = "ege")]
 impl Drop for Image {
     fn drop(&mut self) {
         unsafe { ege_delimage(self.ptr) };
     }
 }
 
+#[cfg(feature = "ege")]
 impl Image {
     /// Create a new image.
     ///
@@ -60,20 +156,34 @@ impl Image {
     pub fn new(width: u32, height: u32) -> Self {
         Self {
             ptr: unsafe { ege_newimage1(width.max(1) as _, height.max(1) as _) },
+            state: DeviceState::default(),
         }
     }
 
     pub(crate) fn handle_result(result: i32) -> Result<(), ImageError> {
         match result {
             xege_ffi::ege_graphics_errors_grOk => Ok(()),
-            xege_ffi::ege_graphics_errors_grFileNotFound => Err(ImageError::FileNotFound),
+            xege_ffi::ege_graphics_errors_grFileNotFound => {
+                Err(ImageError::FileNotFound { path: None })
+            }
             xege_ffi::ege_graphics_errors_grAllocError => Err(ImageError::AllocError),
             xege_ffi::ege_graphics_errors_grNullPointer => Err(ImageError::NullPointer),
-            xege_ffi::ege_graphics_errors_grIOerror => Err(ImageError::IOError),
-            _ => Err(ImageError::UnknownError),
+            xege_ffi::ege_graphics_errors_grIOerror => Err(ImageError::IOError {
+                path: None,
+                source: None,
+            }),
+            code => Err(ImageError::GraphicsError { code, path: None }),
         }
     }
 
+    /// Encode a path for EGE.
+    fn wide_path(path: &Path) -> Result<Vec<u16>, ImageError> {
+        let text = path
+            .to_str()
+            .ok_or_else(|| ImageError::PathParserError(path.to_path_buf()))?;
+        Ok(text.encode_utf16().chain(Some(0)).collect())
+    }
+
     /// Load an image from a file.
     ///
     /// # Parameters
@@ -82,16 +192,16 @@ impl Image {
     /// # Returns
     /// A new `Image` object. Or an error.
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ImageError> {
-        let ptr = unsafe { ege_newimage() };
-        let path = path
-            .as_ref()
-            .to_str()
-            .map_or_else(|| Err(ImageError::PathParserError), |s| Ok(s))?
-            .encode_utf16()
-            .chain(Some(0))
-            .collect::<Vec<u16>>();
-        let result = unsafe { ege_getimage3(ptr, path.as_ptr(), 0, 0) };
-        Self::handle_result(result).map(|_| Self { ptr })
+        let path = path.as_ref();
+        let wide = Self::wide_path(path)?;
+        let image = Self {
+            ptr: unsafe { ege_newimage() },
+            state: DeviceState::default(),
+        };
+        let result = unsafe { ege_getimage3(image.ptr, wide.as_ptr(), 0, 0) };
+        Self::handle_result(result)
+            .map(|_| image)
+            .map_err(|e| e.with_path(path))
     }
 
     /// Load an image from a window.
@@ -107,7 +217,7 @@ impl Image {
     pub fn from_window(x: i32, y: i32, width: i32, height: i32) -> Result<Self, ImageError> {
         let ptr = unsafe { ege_newimage() };
         let result = unsafe { ege_getimage(ptr, x, y, width, height) };
-        Self::handle_result(result).map(|_| Self { ptr })
+        Self::handle_result(result).map(|_| Self { ptr, state: DeviceState::default() })
     }
 
     /// Load an image from another image.
@@ -130,7 +240,7 @@ impl Image {
     ) -> Result<Self, ImageError> {
         let ptr = unsafe { ege_newimage() };
         let result = unsafe { ege_getimage1(ptr, image.ptr, x, y, width, height) };
-        Self::handle_result(result).map(|_| Self { ptr })
+        Self::handle_result(result).map(|_| Self { ptr, state: DeviceState::default() })
     }
 
     /// Save an image to a file.
@@ -146,24 +256,23 @@ impl Image {
         path: P,
         with_alpha: bool,
     ) -> Result<(), ImageError> {
-        let path = path
-            .as_ref()
-            .to_str()
-            .map_or_else(|| Err(ImageError::PathParserError), |s| Ok(s))?
-            .encode_utf16()
-            .chain(Some(0))
-            .collect::<Vec<u16>>();
-        let result = unsafe { ege_saveimage1(self.ptr, path.as_ptr(), with_alpha) };
-        Self::handle_result(result)
+        let path = path.as_ref();
+        let wide = Self::wide_path(path)?;
+        let result = unsafe { ege_saveimage1(self.ptr, wide.as_ptr(), with_alpha) };
+        Self::handle_result(result).map_err(|e| e.with_path(path))
     }
 }
 
+#[cfg(feature = "ege")]
 impl Image {
     /// Resize the image.
     ///
     /// # Parameters
     /// - `width`: The new width of the image.
     /// - `height`: The new height of the image.
+    ///
+    /// # Note
+    /// The pixels are not scaled but cleared. Use `resized` to scale the image.
     pub fn resize(&mut self, width: i32, height: i32) -> Result<(), ImageError> {
         let result = unsafe { ege_resize(self.ptr, width, height) };
         Self::handle_result(result)
@@ -174,12 +283,17 @@ impl Image {
     /// # Parameters
     /// - `width`: The new width of the image.
     /// - `height`: The new height of the image.
+    ///
+    /// # Safety
+    /// The pixels are neither scaled nor cleared, their values are undefined until
+    /// they are drawn.
     pub unsafe fn resize_f(&mut self, width: i32, height: i32) -> Result<(), ImageError> {
         let result = unsafe { ege_resize_f(self.ptr, width, height) };
         Self::handle_result(result)
     }
 }
 
+#[cfg(feature = "ege")]
 impl Clone for Image {
     fn clone(&self) -> Self {
         let mut img = Image::new(self.getwidth(), self.getheight());
@@ -194,6 +308,7 @@ use crate::ARGB;
 /// 
 /// It is used to describe the handling method when
 /// the template element position does not match the pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TemplateMode {
     /// Treat non-existent pixels as white(0xFFFFFF).
     White,
@@ -203,6 +318,12 @@ pub enum TemplateMode {
     Ignore,
     /// Ignore pixel regions that cannot be perfectly matched by the template.
     DotCare,
+    /// Use the nearest edge pixel.
+    Clamp,
+    /// Use the pixel from the opposite edge, as if the image were tiled.
+    Wrap,
+    /// Use the pixel mirrored at the edge, without repeating the edge pixel.
+    Mirror,
 }
 
 /// Mask channels to apply.
@@ -211,9 +332,12 @@ pub enum ApplyMask {
     Red,
     Blue,
     Green,
+    Alpha,
+    Rgb = 7,
     All = 15,
 }
 
+#[cfg(feature = "ege")]
 impl Image {
     /// Apply a transformation to the image.
     ///
@@ -238,6 +362,10 @@ impl Image {
     ///
     /// # Returns
     /// A new `Image` object.
+    ///
+    /// # Note
+    /// The alpha of the result is always `0xFF`. See `Image::convolve` for a faster
+    /// convolution which also handles the alpha channel.
     pub fn template<const N: usize>(
         &mut self,
         mask: Mat<f32, N, N>,
@@ -249,53 +377,392 @@ impl Image {
         let width = self.getwidth();
         let height = self.getheight();
         let mut img = Image::new(width, height);
-        let src = self.getbuffer();
+        let src = ImageView::new(self.getbuffer(), width as usize, width, height);
         let dst = img.getbuffer_mut();
         for i in 0..height {
             for j in 0..width {
-                let (mut sum_red, mut sum_green, mut sum_blue) = (0.0, 0.0, 0.0);
-                let mut is_dont_care = false;
-                'dor_care: for m in 0..N as u32 {
-                    for n in 0..N as u32 {
-                        let x = (i + m) as i32 - N as i32 / 2;
-                        let y = (j + n) as i32 - N as i32 / 2;
-                        let c;
-                        if x < 0 || x >= height as i32 || y < 0 || y >= width as i32 {
-                            match mode {
-                                TemplateMode::White => c = 0xFFFFFF,
-                                TemplateMode::Black => c = 0x0,
-                                TemplateMode::Ignore => continue,
-                                TemplateMode::DotCare => {
-                                    is_dont_care = true;
-                                    break 'dor_care;
-                                }
-                            }
-                        } else {
-                            c = src[(x as u32 * width + y as u32) as usize];
-                        }
-                        if apply.contains(ApplyMask::Red) {
-                            sum_red += mask[m as usize][n as usize] * ((c >> 16) & 0xFF) as f32;
-                        }
-                        if apply.contains(ApplyMask::Green) {
-                            sum_green += mask[m as usize][n as usize] * ((c >> 8) & 0xFF) as f32;
-                        }
-                        if apply.contains(ApplyMask::Blue) {
-                            sum_blue += mask[m as usize][n as usize] * (c & 0xFF) as f32;
-                        }
-                    }
-                }
-                if !is_dont_care {
-                    let red = sum_red.max(0.0).min(255.0) as u8;
-                    let green = sum_green.max(0.0).min(255.0) as u8;
-                    let blue = sum_blue.max(0.0).min(255.0) as u8;
-                    let alpha = 0xFF;
-                    dst[(i * width + j) as usize] = ((alpha as u32) << 24)
-                        | ((red as u32) << 16)
-                        | ((green as u32) << 8)
-                        | blue as u32;
+                if let Some(c) = template_pixel(&src, i, j, &mask, apply, &mode) {
+                    dst[(i * width + j) as usize] = c;
                 }
             }
         }
         img
     }
 }
+
+/// Apply a template to the pixel at row `i` and column `j`.
+///
+/// Returns `None` if the pixel is not matched in `TemplateMode::DotCare`.
+pub(crate) fn template_pixel<const N: usize>(
+    src: &ImageView,
+    i: u32,
+    j: u32,
+    mask: &Mat<f32, N, N>,
+    apply: ApplyMask,
+    mode: &TemplateMode,
+) -> Option<ARGB> {
+    let (width, height) = (src.width(), src.height());
+    let (mut sum_red, mut sum_green, mut sum_blue) = (0.0, 0.0, 0.0);
+    for m in 0..N as u32 {
+        for n in 0..N as u32 {
+            let x = (i + m) as i32 - N as i32 / 2;
+            let y = (j + n) as i32 - N as i32 / 2;
+            let c = match (edge_index(x, height, *mode), edge_index(y, width, *mode)) {
+                (Some(x), Some(y)) => src.get(y, x)?,
+                _ => match mode {
+                    TemplateMode::White => 0xFFFFFF,
+                    TemplateMode::Black => 0x0,
+                    TemplateMode::Ignore => continue,
+                    _ => return None,
+                },
+            };
+            if apply.contains(ApplyMask::Red) {
+                sum_red += mask[m as usize][n as usize] * ((c >> 16) & 0xFF) as f32;
+            }
+            if apply.contains(ApplyMask::Green) {
+                sum_green += mask[m as usize][n as usize] * ((c >> 8) & 0xFF) as f32;
+            }
+            if apply.contains(ApplyMask::Blue) {
+                sum_blue += mask[m as usize][n as usize] * (c & 0xFF) as f32;
+            }
+        }
+    }
+    let red = sum_red.clamp(0.0, 255.0) as u8;
+    let green = sum_green.clamp(0.0, 255.0) as u8;
+    let blue = sum_blue.clamp(0.0, 255.0) as u8;
+    let alpha = 0xFF;
+    Some(((alpha as u32) << 24) | ((red as u32) << 16) | ((green as u32) << 8) | blue as u32)
+}
+
+/// Convert an ARGB buffer into an `image::RgbaImage`.
+pub(crate) fn argb_to_rgba(buffer: &[ARGB], width: u32, height: u32) -> image::RgbaImage {
+    let pixels = buffer.iter().flat_map(|p| {
+        let [b, g, r, a] = p.to_le_bytes();
+        [r, g, b, a]
+    });
+    image::RgbaImage::from_vec(width, height, pixels.collect())
+        .expect("The buffer size does not match the image size.")
+}
+
+/// Convert an `image::RgbaImage` into an ARGB buffer.
+pub(crate) fn rgba_to_argb(image: &image::RgbaImage) -> Vec<ARGB> {
+    image
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            u32::from_le_bytes([b, g, r, a])
+        })
+        .collect()
+}
+
+/// Convert a straight alpha ARGB pixel into a premultiplied one.
+pub(crate) fn premultiply(pixel: ARGB) -> ARGB {
+    let [b, g, r, a] = pixel.to_le_bytes();
+    let mul = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+    u32::from_le_bytes([mul(b), mul(g), mul(r), a])
+}
+
+/// Convert a premultiplied ARGB pixel into a straight alpha one.
+pub(crate) fn unpremultiply(pixel: ARGB) -> ARGB {
+    let [b, g, r, a] = pixel.to_le_bytes();
+    if a == 0 {
+        return 0;
+    }
+    let div = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+    u32::from_le_bytes([div(b), div(g), div(r), a])
+}
+
+/// Convert an ARGB buffer with the given alpha into a straight alpha `image::RgbaImage`.
+#[cfg(any(feature = "ege", test))]
+fn to_straight_rgba(
+    buffer: &[ARGB],
+    width: u32,
+    height: u32,
+    alpha_type: AlphaType,
+) -> image::RgbaImage {
+    match alpha_type {
+        AlphaType::Straight => argb_to_rgba(buffer, width, height),
+        AlphaType::PreMultiplied => {
+            let buffer = buffer.iter().map(|&p| unpremultiply(p)).collect::<Vec<_>>();
+            argb_to_rgba(&buffer, width, height)
+        }
+    }
+}
+
+/// Convert a straight alpha `image::RgbaImage` into an ARGB buffer with the given alpha.
+#[cfg(any(feature = "ege", test))]
+fn from_straight_rgba(image: &image::RgbaImage, alpha_type: AlphaType) -> Vec<ARGB> {
+    let mut pixels = rgba_to_argb(image);
+    if alpha_type == AlphaType::PreMultiplied {
+        pixels.iter_mut().for_each(|p| *p = premultiply(*p));
+    }
+    pixels
+}
+
+#[cfg(feature = "ege")]
+impl Image {
+    /// Convert the image into an `image::RgbaImage`.
+    ///
+    /// # Parameters
+    /// - `alpha_type`: How the pixels of this image store their alpha.
+    ///
+    /// # Returns
+    /// A new `RgbaImage` with straight alpha.
+    pub fn to_rgba_image(&self, alpha_type: AlphaType) -> image::RgbaImage {
+        let (width, height) = (self.getwidth(), self.getheight());
+        to_straight_rgba(self.getbuffer(), width, height, alpha_type)
+    }
+
+    /// Create an image from an `image::RgbaImage`.
+    ///
+    /// # Parameters
+    /// - `image`: The source image with straight alpha.
+    /// - `alpha_type`: How the pixels of the new image should store their alpha.
+    ///
+    /// # Returns
+    /// A new `Image` object. Or an error if the source image is empty or the
+    /// allocation fails.
+    pub fn from_rgba_image(
+        image: &image::RgbaImage,
+        alpha_type: AlphaType,
+    ) -> Result<Self, ImageError> {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(ImageError::EmptyImage);
+        }
+        let mut result = Image::new(width, height);
+        if result.ptr.is_null() {
+            return Err(ImageError::AllocError);
+        }
+        result
+            .getbuffer_mut()
+            .copy_from_slice(&from_straight_rgba(image, alpha_type));
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "ege")]
+impl From<&Image> for image::RgbaImage {
+    /// Convert the image, assuming it has straight alpha.
+    ///
+    /// Use `Image::to_rgba_image` for premultiplied images.
+    fn from(image: &Image) -> Self {
+        image.to_rgba_image(AlphaType::Straight)
+    }
+}
+
+#[cfg(feature = "ege")]
+impl TryFrom<image::DynamicImage> for Image {
+    type Error = ImageError;
+
+    /// Convert the image into a straight alpha `Image`.
+    ///
+    /// Use `Image::from_rgba_image` to create a premultiplied image.
+    fn try_from(image: image::DynamicImage) -> Result<Self, Self::Error> {
+        Self::from_rgba_image(&image.into_rgba8(), AlphaType::Straight)
+    }
+}
+
+/// Encoded image formats supported by `Image::from_bytes` and `Image::encode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Bmp,
+    /// JPEG, encoded with the quality of `EncodeOptions`. The alpha channel is dropped.
+    Jpeg,
+    /// The Quite OK Image format.
+    Qoi,
+}
+
+impl From<ImageFormat> for image::ImageFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Bmp => image::ImageFormat::Bmp,
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFormat::Qoi => image::ImageFormat::Qoi,
+        }
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Image {
+    /// Load an image from encoded bytes in memory.
+    ///
+    /// # Parameters
+    /// - `bytes`: The encoded image, e.g. the content of a PNG file.
+    /// - `format`: The format of the encoded image.
+    ///
+    /// # Returns
+    /// A new `Image` object with straight alpha. Or an error.
+    pub fn from_bytes(bytes: &[u8], format: ImageFormat) -> Result<Self, ImageError> {
+        let image = image::load_from_memory_with_format(bytes, format.into())?;
+        Self::try_from(image)
+    }
+
+    /// Encode the image into bytes in memory.
+    ///
+    /// # Parameters
+    /// - `format`: The format to encode.
+    ///
+    /// # Returns
+    /// The encoded image. Or an error.
+    ///
+    /// # Note
+    /// Encodes with the default `EncodeOptions`, see `encode_with`.
+    pub fn encode(&self, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+        self.encode_with(format, &EncodeOptions::default())
+    }
+
+    /// Encode the image into bytes in memory.
+    ///
+    /// # Parameters
+    /// - `format`: The format to encode.
+    /// - `options`: How to encode, see `EncodeOptions`.
+    ///
+    /// # Returns
+    /// The encoded image. Or an error.
+    ///
+    /// # Note
+    /// The pixels are treated as straight alpha. JPEG never keeps the alpha channel.
+    pub fn encode_with(
+        &self,
+        format: ImageFormat,
+        options: &EncodeOptions,
+    ) -> Result<Vec<u8>, ImageError> {
+        encode_rgba(self.to_rgba_image(AlphaType::Straight), format, options)
+    }
+}
+
+/// Encode a straight alpha `image::RgbaImage`, see `Image::encode_with`.
+#[cfg(any(feature = "ege", test))]
+fn encode_rgba(
+    rgba: image::RgbaImage,
+    format: ImageFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, ImageError> {
+    let mut bytes = std::io::Cursor::new(vec![]);
+    match format {
+        ImageFormat::Jpeg => {
+            let rgb = image::DynamicImage::ImageRgba8(rgba).into_rgb8();
+            let quality = options.quality.clamp(1, 100);
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality)
+                .encode_image(&rgb)?;
+        }
+        _ if options.with_alpha => rgba.write_to(&mut bytes, format.into())?,
+        _ => image::DynamicImage::ImageRgba8(rgba)
+            .into_rgb8()
+            .write_to(&mut bytes, format.into())?,
+    }
+    Ok(bytes.into_inner())
+}
+
+/// Options of `Image::encode_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodeOptions {
+    /// Whether to keep the alpha channel, like the `with_alpha` of `Image::save`.
+    pub with_alpha: bool,
+    /// The quality of JPEG, from `1` to `100`. Ignored by the other formats.
+    pub quality: u8,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            with_alpha: true,
+            quality: 90,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8], format: ImageFormat) -> image::RgbaImage {
+        image::load_from_memory_with_format(bytes, format.into())
+            .unwrap()
+            .into_rgba8()
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let straight = [
+            0xFF123456, 0x80FF8000, 0x01FFFFFF, 0x00000000, 0xC0406080, 0xFF000000,
+        ];
+        let premultiplied = straight.map(premultiply);
+        for (pixels, alpha_type) in [
+            (straight, AlphaType::Straight),
+            (premultiplied, AlphaType::PreMultiplied),
+        ] {
+            let rgba = to_straight_rgba(&pixels, 3, 2, alpha_type);
+            let bytes = encode_rgba(rgba, ImageFormat::Png, &EncodeOptions::default()).unwrap();
+            let decoded = decode(&bytes, ImageFormat::Png);
+            assert_eq!(decoded.dimensions(), (3, 2));
+            assert_eq!(from_straight_rgba(&decoded, alpha_type), pixels);
+        }
+    }
+
+    #[test]
+    fn encode_options() {
+        let pixels = [0x80FF8000, 0x00123456];
+        let options = EncodeOptions {
+            with_alpha: false,
+            ..Default::default()
+        };
+        let bytes = encode_rgba(argb_to_rgba(&pixels, 2, 1), ImageFormat::Png, &options).unwrap();
+        assert_eq!(
+            rgba_to_argb(&decode(&bytes, ImageFormat::Png)),
+            [0xFFFF8000, 0xFF123456]
+        );
+        let rgba = argb_to_rgba(&[0xFF808080; 64], 8, 8);
+        let bytes = encode_rgba(rgba, ImageFormat::Jpeg, &EncodeOptions::default()).unwrap();
+        let decoded = decode(&bytes, ImageFormat::Jpeg);
+        assert_eq!(decoded.dimensions(), (8, 8));
+        assert!(
+            decoded
+                .pixels()
+                .all(|p| p.0[3] == 255 && p.0[0].abs_diff(128) <= 2)
+        );
+    }
+
+    #[test]
+    fn premultiply_round_trip() {
+        assert_eq!(premultiply(0x00FF8040), 0);
+        assert_eq!(unpremultiply(0x00FF8040), 0);
+        assert_eq!(premultiply(0x01FF8040), 0x01010100);
+        assert_eq!(unpremultiply(0x01010100), 0x01FFFF00);
+        assert_eq!(premultiply(0xFF123456), 0xFF123456);
+        assert_eq!(unpremultiply(0xFF123456), 0xFF123456);
+        // Every premultiplied color survives the round trip through straight alpha.
+        for a in [0, 1, 2, 127, 128, 254, 255] {
+            for c in 0..=a {
+                let pixel = u32::from_le_bytes([c, c / 2, a - c, a]);
+                assert_eq!(premultiply(unpremultiply(pixel)), pixel, "{pixel:08X}");
+            }
+        }
+        // Opaque straight colors survive it, at a low alpha the channels are lost.
+        for c in 0..=255 {
+            let pixel = u32::from_le_bytes([c, 255 - c, c / 3, 255]);
+            assert_eq!(unpremultiply(premultiply(pixel)), pixel);
+        }
+        assert_eq!(unpremultiply(premultiply(0x01804020)), 0x01FF0000);
+    }
+
+    #[test]
+    fn rgba_round_trip() {
+        let pixels = [0x00FF8040, 0x01020304, 0xFF102030, 0x80FFFFFF];
+        let rgba = argb_to_rgba(&pixels, 2, 2);
+        assert_eq!(rgba.get_pixel(0, 0).0, [0xFF, 0x80, 0x40, 0x00]);
+        assert_eq!(rgba.get_pixel(1, 0).0, [0x02, 0x03, 0x04, 0x01]);
+        assert_eq!(rgba.get_pixel(0, 1).0, [0x10, 0x20, 0x30, 0xFF]);
+        assert_eq!(rgba.get_pixel(1, 1).0, [0xFF, 0xFF, 0xFF, 0x80]);
+        assert_eq!(rgba_to_argb(&rgba), pixels);
+    }
+
+    #[test]
+    #[should_panic(expected = "The buffer size does not match the image size.")]
+    fn rgba_size_mismatch() {
+        argb_to_rgba(&[0; 3], 2, 2);
+    }
+}
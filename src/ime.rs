@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::ptr::null_mut;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use xege_ffi::*;
+
+use crate::{Event, Point, Window};
+
+// EGE loads imm32 at runtime, the input method functions are linked here.
+#[cfg_attr(windows, link(name = "imm32"))]
+unsafe extern "C" {}
+
+/// The message the text input requests are sent to the window thread with.
+const WM_TEXT_INPUT: u32 = WM_APP + 0x45;
+const REQUEST_STOP: WPARAM = 0;
+const REQUEST_START: WPARAM = 1;
+const REQUEST_POSITION: WPARAM = 2;
+
+/// The state of the text input, shared with the window thread.
+struct TextInput {
+    active: bool,
+    /// The window procedure of EGE, once it is replaced.
+    previous: Option<LONG_PTR>,
+    pos: Option<Point>,
+    events: VecDeque<Event>,
+}
+
+lazy_static::lazy_static! {
+    static ref TEXT_INPUT: Mutex<TextInput> = Mutex::new(TextInput {
+        active: false,
+        previous: None,
+        pos: None,
+        events: VecDeque::new(),
+    });
+}
+
+/// Lock the state of the text input.
+///
+/// The state stays consistent when a thread panics while holding the lock, so
+/// a poisoned lock is used anyway. The window procedure must not panic.
+fn state() -> MutexGuard<'static, TextInput> {
+    TEXT_INPUT.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Take the next text input event.
+pub(crate) fn next_text_event() -> Option<Event> {
+    state().events.pop_front()
+}
+
+fn push(event: Event) {
+    state().events.push_back(event);
+}
+
+/// An error of `Window::start_text_input`.
+#[derive(Debug, thiserror::Error)]
+pub enum TextInputError {
+    #[error("There is no window, the graphics environment is not initialized.")]
+    NoWindow,
+    #[error("Failed to hook the window procedure, error code {0}.")]
+    Hook(u32),
+}
+
+/// Read a composition string of the input context.
+///
+/// # Returns
+/// The string and the cursor position in it in bytes.
+unsafe fn composition_string(himc: HIMC, kind: u32) -> (String, usize) {
+    let bytes = unsafe { ImmGetCompositionStringW(himc, kind, null_mut(), 0) };
+    if bytes <= 0 {
+        return (String::new(), 0);
+    }
+    let mut buffer = vec![0u16; bytes as usize / 2];
+    unsafe { ImmGetCompositionStringW(himc, kind, buffer.as_mut_ptr() as _, bytes as _) };
+    let cursor = unsafe { ImmGetCompositionStringW(himc, GCS_CURSORPOS, null_mut(), 0) };
+    let cursor = (cursor.max(0) as usize).min(buffer.len());
+    let cursor = String::from_utf16_lossy(&buffer[..cursor]).len();
+    (String::from_utf16_lossy(&buffer), cursor)
+}
+
+/// Move the candidate window of the input method.
+///
+/// The composition window is hidden instead of moved: `WM_IME_COMPOSITION` is not
+/// passed on, the application draws the composition from `Event::CompositionUpdate`.
+unsafe fn set_position(hwnd: HWND, pos: Point) {
+    let himc = unsafe { ImmGetContext(hwnd) };
+    if himc.is_null() {
+        return;
+    }
+    let mut candidate = CANDIDATEFORM {
+        dwIndex: 0,
+        dwStyle: CFS_CANDIDATEPOS,
+        ptCurrentPos: POINT { x: pos.x, y: pos.y },
+        rcArea: RECT {
+            left: 0,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        },
+    };
+    unsafe {
+        ImmSetCandidateWindow(himc, &mut candidate);
+        ImmReleaseContext(hwnd, himc);
+    }
+}
+
+/// Handle a text input request, on the window thread.
+unsafe fn request(hwnd: HWND, request: WPARAM, pos: Option<Point>) {
+    match request {
+        REQUEST_START => unsafe {
+            ImmAssociateContextEx(hwnd, null_mut(), IACE_DEFAULT);
+        },
+        REQUEST_STOP => unsafe {
+            let himc = ImmGetContext(hwnd);
+            if !himc.is_null() {
+                ImmNotifyIME(himc, NI_COMPOSITIONSTR, CPS_CANCEL, 0);
+                ImmReleaseContext(hwnd, himc);
+            }
+            ImmAssociateContextEx(hwnd, null_mut(), 0);
+            return;
+        },
+        _ => {}
+    }
+    if let Some(pos) = pos {
+        unsafe { set_position(hwnd, pos) };
+    }
+}
+
+/// Handle a message of the input method, on the window thread.
+///
+/// # Returns
+/// `true` if the message must not be passed on.
+unsafe fn handle(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> bool {
+    let (active, pos) = {
+        let state = state();
+        (state.active, state.pos)
+    };
+    match msg {
+        WM_TEXT_INPUT => {
+            unsafe { request(hwnd, wparam, pos) };
+            true
+        }
+        _ if !active => false,
+        WM_IME_STARTCOMPOSITION => {
+            push(Event::CompositionStart);
+            if let Some(pos) = pos {
+                unsafe { set_position(hwnd, pos) };
+            }
+            false
+        }
+        WM_IME_COMPOSITION => {
+            let himc = unsafe { ImmGetContext(hwnd) };
+            if himc.is_null() {
+                return false;
+            }
+            let flags = lparam as u32;
+            if flags & GCS_RESULTSTR != 0 {
+                let (text, _) = unsafe { composition_string(himc, GCS_RESULTSTR) };
+                push(Event::TextCommit(text));
+            }
+            if flags & GCS_COMPSTR != 0 {
+                let (text, cursor) = unsafe { composition_string(himc, GCS_COMPSTR) };
+                push(Event::CompositionUpdate { text, cursor });
+            }
+            unsafe { ImmReleaseContext(hwnd, himc) };
+            // The committed text would also arrive as `Event::Char` otherwise, and the
+            // composition window of the input method would show the text a second time.
+            true
+        }
+        WM_IME_ENDCOMPOSITION => {
+            push(Event::CompositionEnd);
+            false
+        }
+        _ => false,
+    }
+}
+
+/// The window procedure replacing the one of EGE.
+unsafe extern "C" fn text_input_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let ime_message = matches!(
+        msg,
+        WM_TEXT_INPUT | WM_IME_STARTCOMPOSITION | WM_IME_COMPOSITION | WM_IME_ENDCOMPOSITION
+    );
+    if ime_message && unsafe { handle(hwnd, msg, wparam, lparam) } {
+        return 0;
+    }
+    // The composition is drawn by the application, the input method only shows candidates.
+    let lparam = match msg {
+        WM_IME_SETCONTEXT if state().active => lparam & !(ISC_SHOWUICOMPOSITIONWINDOW as LPARAM),
+        _ => lparam,
+    };
+    let previous = state().previous;
+    match previous {
+        Some(previous) => unsafe {
+            let previous = std::mem::transmute::<LONG_PTR, WNDPROC>(previous);
+            CallWindowProcW(previous, hwnd, msg, wparam, lparam)
+        },
+        None => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
+
+impl Window {
+    /// Start text input.
+    ///
+    /// # Returns
+    /// * `Ok(())` if text input is started.
+    /// * `Err(TextInputError)` if there is no window or its messages cannot be hooked,
+    ///   starting again retries.
+    ///
+    /// # Note
+    /// The input method of the window is enabled and its composition is reported
+    /// by `XEGE::poll_event`: `Event::CompositionStart`, `Event::CompositionUpdate`
+    /// with the text being composed, `Event::TextCommit` with the result and
+    /// `Event::CompositionEnd`. The committed text is not reported as `Event::Char`,
+    /// characters typed without the input method still are.
+    ///
+    /// The input method does not show the text being composed, draw it from
+    /// `Event::CompositionUpdate`. Only its candidate window is shown, see
+    /// `set_text_input_pos`.
+    pub fn start_text_input(&self) -> Result<(), TextInputError> {
+        let hwnd = unsafe { ege_getHWnd() };
+        if !self.has_window() || hwnd.is_null() {
+            return Err(TextInputError::NoWindow);
+        }
+        {
+            let mut state = state();
+            if state.previous.is_none() {
+                // The lock is held so the new procedure finds the previous one.
+                let proc = text_input_proc as unsafe extern "C" fn(_, _, _, _) -> _;
+                let previous = unsafe {
+                    SetLastError(0);
+                    SetWindowLongPtrW(hwnd, GWLP_WNDPROC, proc as usize as LONG_PTR)
+                };
+                // A window always has a procedure, zero is only returned on failure.
+                if previous == 0 {
+                    return Err(TextInputError::Hook(unsafe { GetLastError() }));
+                }
+                state.previous = Some(previous);
+            }
+            state.active = true;
+        }
+        unsafe { SendMessageW(hwnd, WM_TEXT_INPUT, REQUEST_START, 0) };
+        Ok(())
+    }
+
+    /// Stop text input.
+    ///
+    /// # Note
+    /// A running composition is cancelled and the input method of the window
+    /// is disabled until `start_text_input` is called again.
+    pub fn stop_text_input(&self) {
+        if !self.is_text_input() {
+            return;
+        }
+        // Still active while cancelling, so the end of the composition is reported.
+        unsafe { SendMessageW(ege_getHWnd(), WM_TEXT_INPUT, REQUEST_STOP, 0) };
+        state().active = false;
+    }
+
+    /// Checks if text input is started.
+    pub fn is_text_input(&self) -> bool {
+        state().active
+    }
+
+    /// Set where the input method shows its candidates.
+    ///
+    /// # Parameters
+    /// * `pos` - The position relative to the window, usually the text cursor.
+    ///
+    /// # Note
+    /// The position is kept and applied whenever a composition starts.
+    pub fn set_text_input_pos(&self, pos: Point) {
+        let active = {
+            let mut state = state();
+            state.pos = Some(pos);
+            state.active
+        };
+        if active {
+            let hwnd = unsafe { ege_getHWnd() };
+            unsafe { SendMessageW(hwnd, WM_TEXT_INPUT, REQUEST_POSITION, 0) };
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Event, Key, MouseButton, Point};
+
+/// A key or mouse button that triggers an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl From<Key> for Binding {
+    fn from(key: Key) -> Self {
+        Binding::Key(key)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Binding::Mouse(button)
+    }
+}
+
+/// The pressed and released keys or buttons.
+#[derive(Debug, Clone)]
+struct Buttons<T> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Self {
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + std::hash::Hash> Buttons<T> {
+    fn press(&mut self, button: T) {
+        // Repeated key downs do not press the key again.
+        if self.down.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.down.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+    }
+
+    fn next_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
+
+/// The state of the keyboard and the mouse, built from events.
+///
+/// # Note
+/// Pass every event to `handle` and call `next_frame` at the end of each frame,
+/// e.g. in `XEGE::run`:
+///
+/// ```no_run
+/// # #[cfg(feature = "ege")] {
+/// # use std::ops::ControlFlow;
+/// # use xege::*;
+/// let mut xege = initgraph(640, 480, Init::Default).unwrap();
+/// let mut input = InputState::new();
+/// input.bind("jump", Key::Space);
+/// xege.run(|_, event| {
+///     input.handle(&event);
+///     if let Event::Tick { .. } = event {
+///         if input.action_pressed("jump") {
+///             println!("jump");
+///         }
+///         input.next_frame();
+///     }
+///     ControlFlow::Continue(())
+/// });
+/// # }
+/// ```
+///
+/// "Pressed" and "released" refer to the current frame. A key pressed and released
+/// within one frame is both pressed and released, but not down. When the window
+/// loses the focus, all keys and buttons are released.
+#[derive(Debug, Clone)]
+pub struct InputState {
+    keys: Buttons<Key>,
+    buttons: Buttons<MouseButton>,
+    pos: Option<Point>,
+    delta: Point,
+    wheel: i32,
+    text: String,
+    actions: HashMap<String, Vec<Binding>>,
+}
+
+impl InputState {
+    /// Create an empty input state without bindings.
+    pub fn new() -> Self {
+        Self {
+            keys: Buttons::default(),
+            buttons: Buttons::default(),
+            pos: None,
+            delta: Point { x: 0, y: 0 },
+            wheel: 0,
+            text: String::new(),
+            actions: HashMap::new(),
+        }
+    }
+
+    /// Update the state with an event.
+    ///
+    /// # Parameters
+    /// * `event` - The event, events other than input and focus events are ignored.
+    pub fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::KeyDown { key, .. } => self.keys.press(key),
+            Event::KeyUp { key, .. } => self.keys.release(key),
+            Event::Char(c) => self.text.push(c),
+            Event::TextCommit(ref text) => self.text.push_str(text),
+            Event::MouseMove { pos } => self.move_to(pos),
+            Event::MouseDown { button, pos } => {
+                self.move_to(pos);
+                self.buttons.press(button);
+            }
+            Event::MouseUp { button, pos } => {
+                self.move_to(pos);
+                self.buttons.release(button);
+            }
+            Event::MouseWheel { delta, pos } => {
+                self.move_to(pos);
+                self.wheel += delta;
+            }
+            Event::Focus(false) => {
+                self.keys.release_all();
+                self.buttons.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Start a new frame.
+    ///
+    /// Clears the pressed and released keys and buttons, the mouse delta,
+    /// the wheel and the text of the last frame.
+    pub fn next_frame(&mut self) {
+        self.keys.next_frame();
+        self.buttons.next_frame();
+        self.delta = Point { x: 0, y: 0 };
+        self.wheel = 0;
+        self.text.clear();
+    }
+
+    fn move_to(&mut self, pos: Point) {
+        if let Some(old) = self.pos {
+            self.delta.x += pos.x - old.x;
+            self.delta.y += pos.y - old.y;
+        }
+        self.pos = Some(pos);
+    }
+
+    /// Checks if a key is held down.
+    pub fn is_down(&self, key: Key) -> bool {
+        self.keys.down.contains(&key)
+    }
+
+    /// Checks if a key was pressed in this frame.
+    pub fn just_pressed(&self, key: Key) -> bool {
+        self.keys.pressed.contains(&key)
+    }
+
+    /// Checks if a key was released in this frame.
+    pub fn just_released(&self, key: Key) -> bool {
+        self.keys.released.contains(&key)
+    }
+
+    /// Checks if a mouse button is held down.
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons.down.contains(&button)
+    }
+
+    /// Checks if a mouse button was pressed in this frame.
+    pub fn button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.pressed.contains(&button)
+    }
+
+    /// Checks if a mouse button was released in this frame.
+    pub fn button_released(&self, button: MouseButton) -> bool {
+        self.buttons.released.contains(&button)
+    }
+
+    /// Get the last known mouse position.
+    ///
+    /// # Returns
+    /// * `Some(Point)` after the first mouse event, `None` before.
+    pub fn mouse_pos(&self) -> Option<Point> {
+        self.pos
+    }
+
+    /// Get the distance the mouse moved in this frame.
+    pub fn mouse_delta(&self) -> Point {
+        self.delta
+    }
+
+    /// Get the wheel delta of this frame, a notch is `120`.
+    pub fn wheel(&self) -> i32 {
+        self.wheel
+    }
+
+    /// Get the characters typed or committed by the input method in this frame.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Add a binding to an action.
+    ///
+    /// # Parameters
+    /// * `action` - The name of the action.
+    /// * `binding` - A `Key`, `MouseButton` or `Binding` triggering the action.
+    pub fn bind(&mut self, action: impl Into<String>, binding: impl Into<Binding>) {
+        let bindings = self.actions.entry(action.into()).or_default();
+        let binding = binding.into();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replace the bindings of an action.
+    ///
+    /// # Parameters
+    /// * `action` - The name of the action.
+    /// * `bindings` - The new bindings, none removes the action.
+    pub fn rebind(
+        &mut self,
+        action: impl Into<String>,
+        bindings: impl IntoIterator<Item = Binding>,
+    ) {
+        let action = action.into();
+        self.actions.remove(&action);
+        for binding in bindings {
+            self.bind(action.clone(), binding);
+        }
+    }
+
+    /// Remove an action and its bindings.
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    /// Get the bindings of an action.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Checks if any binding of an action is held down.
+    pub fn action_down(&self, action: &str) -> bool {
+        self.any_binding(action, |b| match b {
+            Binding::Key(key) => self.is_down(key),
+            Binding::Mouse(button) => self.is_button_down(button),
+        })
+    }
+
+    /// Checks if a binding of an action was pressed in this frame.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.any_binding(action, |b| match b {
+            Binding::Key(key) => self.just_pressed(key),
+            Binding::Mouse(button) => self.button_pressed(button),
+        })
+    }
+
+    /// Checks if a binding of an action was released in this frame.
+    pub fn action_released(&self, action: &str) -> bool {
+        self.any_binding(action, |b| match b {
+            Binding::Key(key) => self.just_released(key),
+            Binding::Mouse(button) => self.button_released(button),
+        })
+    }
+
+    fn any_binding(&self, action: &str, f: impl Fn(Binding) -> bool) -> bool {
+        self.bindings(action).iter().any(|&b| f(b))
+    }
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyFlags;
+
+    fn down(key: Key) -> Event {
+        Event::KeyDown {
+            key,
+            flags: KeyFlags::none(),
+        }
+    }
+
+    fn up(key: Key) -> Event {
+        Event::KeyUp {
+            key,
+            flags: KeyFlags::none(),
+        }
+    }
+
+    fn handle(input: &mut InputState, events: &[Event]) {
+        events.iter().for_each(|event| input.handle(event));
+    }
+
+    #[test]
+    fn press_and_release() {
+        let mut input = InputState::new();
+        input.handle(&down(Key::A));
+        assert!(input.is_down(Key::A) && input.just_pressed(Key::A));
+        assert!(!input.just_released(Key::A));
+
+        input.next_frame();
+        assert!(input.is_down(Key::A) && !input.just_pressed(Key::A));
+
+        input.handle(&up(Key::A));
+        assert!(!input.is_down(Key::A) && input.just_released(Key::A));
+        input.next_frame();
+        assert!(!input.just_released(Key::A));
+        // Releasing a key that is not down does nothing.
+        input.handle(&up(Key::A));
+        assert!(!input.just_released(Key::A));
+    }
+
+    #[test]
+    fn press_and_release_in_one_frame() {
+        let mut input = InputState::new();
+        handle(&mut input, &[down(Key::Space), up(Key::Space)]);
+        assert!(input.just_pressed(Key::Space));
+        assert!(input.just_released(Key::Space));
+        assert!(!input.is_down(Key::Space));
+    }
+
+    #[test]
+    fn repeat_does_not_press_again() {
+        let mut input = InputState::new();
+        input.handle(&down(Key::Left));
+        input.next_frame();
+        handle(&mut input, &[down(Key::Left), down(Key::Left)]);
+        assert!(input.is_down(Key::Left));
+        assert!(!input.just_pressed(Key::Left));
+    }
+
+    #[test]
+    fn focus_loss_releases_everything() {
+        let mut input = InputState::new();
+        let pos = Point { x: 1, y: 1 };
+        handle(
+            &mut input,
+            &[
+                down(Key::A),
+                down(Key::B),
+                Event::MouseDown {
+                    button: MouseButton::Right,
+                    pos,
+                },
+            ],
+        );
+        input.next_frame();
+        input.handle(&Event::Focus(false));
+        for key in [Key::A, Key::B] {
+            assert!(!input.is_down(key) && input.just_released(key));
+        }
+        assert!(!input.is_button_down(MouseButton::Right));
+        assert!(input.button_released(MouseButton::Right));
+        // Gaining the focus does not press anything.
+        input.next_frame();
+        input.handle(&Event::Focus(true));
+        assert!(!input.is_down(Key::A) && !input.just_pressed(Key::A));
+    }
+
+    #[test]
+    fn mouse() {
+        let mut input = InputState::new();
+        assert_eq!(input.mouse_pos(), None);
+        let at = |x, y| Point { x, y };
+        // The first position has no delta.
+        handle(
+            &mut input,
+            &[
+                Event::MouseMove { pos: at(10, 10) },
+                Event::MouseMove { pos: at(13, 8) },
+                Event::MouseDown {
+                    button: MouseButton::Left,
+                    pos: at(15, 9),
+                },
+                Event::MouseWheel {
+                    delta: 120,
+                    pos: at(15, 9),
+                },
+                Event::MouseWheel {
+                    delta: -240,
+                    pos: at(15, 9),
+                },
+            ],
+        );
+        assert_eq!(input.mouse_pos(), Some(at(15, 9)));
+        assert_eq!(input.mouse_delta(), at(5, -1));
+        assert_eq!(input.wheel(), -120);
+        assert!(input.button_pressed(MouseButton::Left));
+
+        input.next_frame();
+        assert_eq!(input.mouse_delta(), at(0, 0));
+        assert_eq!(input.wheel(), 0);
+        input.handle(&Event::MouseUp {
+            button: MouseButton::Left,
+            pos: at(12, 9),
+        });
+        assert_eq!(input.mouse_delta(), at(-3, 0));
+        assert!(input.button_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn text() {
+        let mut input = InputState::new();
+        handle(
+            &mut input,
+            &[
+                Event::Char('a'),
+                Event::TextCommit("文字".into()),
+                Event::Char('!'),
+            ],
+        );
+        assert_eq!(input.text(), "a文字!");
+        input.next_frame();
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn actions() {
+        let mut input = InputState::new();
+        input.bind("jump", Key::Space);
+        input.bind("jump", MouseButton::Left);
+        input.bind("jump", Key::Space);
+        assert_eq!(
+            input.bindings("jump"),
+            [Binding::Key(Key::Space), Binding::Mouse(MouseButton::Left)]
+        );
+
+        input.handle(&Event::MouseDown {
+            button: MouseButton::Left,
+            pos: Point { x: 0, y: 0 },
+        });
+        assert!(input.action_pressed("jump") && input.action_down("jump"));
+        assert!(!input.action_pressed("fire"));
+
+        input.rebind("jump", [Binding::Key(Key::W), Binding::Key(Key::Up)]);
+        assert_eq!(
+            input.bindings("jump"),
+            [Binding::Key(Key::W), Binding::Key(Key::Up)]
+        );
+        assert!(!input.action_down("jump"));
+        input.next_frame();
+        input.handle(&up(Key::W));
+        assert!(!input.action_released("jump"));
+        input.handle(&down(Key::Up));
+        assert!(input.action_pressed("jump"));
+        input.handle(&up(Key::Up));
+        assert!(input.action_released("jump"));
+
+        input.rebind("jump", []);
+        assert!(input.bindings("jump").is_empty());
+        input.bind("fire", Key::F);
+        input.unbind("fire");
+        assert!(input.bindings("fire").is_empty());
+        input.handle(&down(Key::F));
+        assert!(!input.action_pressed("fire"));
+    }
+}
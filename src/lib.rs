@@ -1,22 +1,66 @@
 mod env;
 mod window;
 pub mod color;
+mod backend;
+mod sys;
 mod graphics;
 mod enums;
 mod image;
+mod view;
+mod convolve;
+mod composite;
+mod resample;
+mod atlas;
 pub mod mat;
+#[cfg(feature = "ege")]
 mod path;
+mod pathdata;
+mod pathops;
+mod stroke;
+mod svgpath;
+mod brush;
 mod msg;
+mod event;
+mod input;
+mod record;
+#[cfg(feature = "ege")]
+mod ime;
+#[cfg(feature = "ege")]
 mod time;
+#[cfg(feature = "ege")]
 mod console;
+pub mod filters;
+pub mod testing;
+#[cfg(feature = "software")]
+mod canvas;
 
 pub use env::*;
 pub use window::*;
-pub use color::Color;
+pub use color::{Color, ParseColorError};
 pub use graphics::*;
 pub use enums::*;
 pub use image::*;
+pub use view::*;
+pub use convolve::*;
+pub use composite::*;
+pub use resample::*;
+pub use atlas::*;
+#[cfg(feature = "ege")]
 pub use path::*;
+pub use pathdata::*;
+pub use pathops::*;
+pub use stroke::*;
+pub use svgpath::*;
+pub use brush::*;
 pub use msg::*;
+pub use event::*;
+pub use input::*;
+pub use record::*;
+#[cfg(feature = "ege")]
+pub use ime::TextInputError;
+#[cfg(feature = "ege")]
 pub use time::*;
-pub use console::*;
\ No newline at end of file
+#[cfg(feature = "ege")]
+pub use console::*;
+#[cfg(feature = "software")]
+pub use canvas::*;
@@ -1,21 +1,40 @@
 // re-exported
 pub use mats::*;
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
 /// A trait for converting a other format matrix into an EGE matrix.
 pub trait IntoEGEMatrix {
-    fn into_ege_matrix(&self) -> ege_ege_transform_matrix;
-}
+    /// Get the affine transformation of the matrix.
+    ///
+    /// # Returns
+    /// The elements `[m11, m12, m21, m22, m31, m32]`, mapping `(x, y)` to
+    /// `(m11 * x + m21 * y + m31, m12 * x + m22 * y + m32)`.
+    fn to_affine(&self) -> [f32; 6];
 
-impl IntoEGEMatrix for Mat3<f32> {
+    #[cfg(feature = "ege")]
     fn into_ege_matrix(&self) -> ege_ege_transform_matrix {
+        let [m11, m12, m21, m22, m31, m32] = self.to_affine();
         ege_ege_transform_matrix {
-            m11: self[0][0],
-            m12: self[1][0],
-            m21: self[0][1],
-            m22: self[1][1],
-            m31: self[1][2],
-            m32: self[1][2],
+            m11,
+            m12,
+            m21,
+            m22,
+            m31,
+            m32,
         }
     }
 }
+
+impl IntoEGEMatrix for Mat3<f32> {
+    fn to_affine(&self) -> [f32; 6] {
+        [
+            self[0][0],
+            self[1][0],
+            self[0][1],
+            self[1][1],
+            self[0][2],
+            self[1][2],
+        ]
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
 use crate::{Key, Point};
@@ -11,65 +12,275 @@ pub enum KeyFlags {
 }
 
 /// Key event message
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyMsg {
     Down(Key),
     Up(Key),
     Char(char),
 }
 
+/// Mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    X1,
+    X2,
+}
+
+/// Mouse buttons
+#[bitmask_enum::bitmask(u8)]
+pub enum MouseButtons {
+    Left,
+    Right,
+    Middle,
+    X1,
+    X2,
+}
+
+impl From<MouseButton> for MouseButtons {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => Self::Left,
+            MouseButton::Right => Self::Right,
+            MouseButton::Middle => Self::Middle,
+            MouseButton::X1 => Self::X1,
+            MouseButton::X2 => Self::X2,
+        }
+    }
+}
+
+/// Mouse event kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEventKind {
+    /// The mouse moved.
+    Move,
+    /// A button was pressed.
+    Down,
+    /// A button was released.
+    Up,
+    /// The wheel was turned.
+    Wheel,
+}
+
 /// Mouse event message
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MouseMsg {
-    pub(crate) msg: ege_mouse_msg,
+    /// The kind of the event.
+    pub kind: MouseEventKind,
+    /// The button of a `Down` or `Up` event.
+    pub button: Option<MouseButton>,
+    /// The buttons held down during the event, with the button of a `Down` or `Up` event.
+    pub buttons: MouseButtons,
+    /// The position relative to the window.
+    pub pos: Point,
+    /// The wheel delta of a `Wheel` event, a notch is `120`.
+    pub wheel: i32,
+    /// The modifier keys held during the event, `Shift` and `Ctrl`.
+    pub modifiers: KeyFlags,
 }
 
 impl MouseMsg {
+    /// Create a mouse message.
+    ///
+    /// # Parameters
+    /// * `kind` - The kind of the event.
+    /// * `button` - The button of a `Down` or `Up` event.
+    /// * `pos` - The position relative to the window.
+    /// * `wheel` - The wheel delta of a `Wheel` event.
+    /// * `modifiers` - The modifier keys held during the event.
+    ///
+    /// # Note
+    /// `buttons` only holds `button`. Set it to the buttons held down, e.g. for the
+    /// move messages of a drag.
+    pub fn new(
+        kind: MouseEventKind,
+        button: Option<MouseButton>,
+        pos: Point,
+        wheel: i32,
+        modifiers: KeyFlags,
+    ) -> Self {
+        Self {
+            kind,
+            button,
+            buttons: button.map_or(MouseButtons::none(), MouseButtons::from),
+            pos,
+            wheel,
+            modifiers,
+        }
+    }
+
     /// Get mouse position
     pub fn pos(&self) -> Point {
-        Point {
-            x: self.msg.x,
-            y: self.msg.y,
-        }
+        self.pos
     }
 
     /// Is move event
     pub fn is_move(&self) -> bool {
-        self.msg.msg == ege_mouse_msg_e_mouse_msg_move
+        self.kind == MouseEventKind::Move
     }
 
     /// Is button down event
     pub fn is_down(&self) -> bool {
-        self.msg.msg == ege_mouse_msg_e_mouse_msg_down
+        self.kind == MouseEventKind::Down
     }
 
     /// Is button up event
     pub fn is_up(&self) -> bool {
-        self.msg.msg == ege_mouse_msg_e_mouse_msg_up
+        self.kind == MouseEventKind::Up
     }
 
-    /// Is left button event
+    /// Is left button event, or is the left button held down
     pub fn is_left(&self) -> bool {
-        self.msg.flags & ege_mouse_flag_e_mouse_flag_left as u32 != 0
+        self.buttons.contains(MouseButtons::Left)
     }
 
-    /// Is right button event
+    /// Is right button event, or is the right button held down
     pub fn is_right(&self) -> bool {
-        self.msg.flags & ege_mouse_flag_e_mouse_flag_right as u32 != 0
+        self.buttons.contains(MouseButtons::Right)
     }
 
-    /// Is middle button event
+    /// Is middle button event, or is the middle button held down
     pub fn is_middle(&self) -> bool {
-        self.msg.flags & ege_mouse_flag_e_mouse_flag_mid as u32 != 0
+        self.buttons.contains(MouseButtons::Middle)
+    }
+
+    /// Get the button of the event
+    ///
+    /// # Returns
+    /// * `Some(MouseButton)` for a `Down` or `Up` event, `None` otherwise.
+    pub fn button(&self) -> Option<MouseButton> {
+        self.button
     }
 
     /// Is wheel event
     pub fn is_wheel(&self) -> bool {
-        self.msg.flags & ege_mouse_msg_e_mouse_msg_wheel as u32 != 0
+        self.kind == MouseEventKind::Wheel
     }
 
     /// Get wheel delta
     pub fn wheel(&self) -> i32 {
-        self.msg.wheel
+        self.wheel
+    }
+}
+
+#[cfg(feature = "ege")]
+impl From<ege_mouse_msg> for MouseMsg {
+    fn from(msg: ege_mouse_msg) -> Self {
+        let kind = match msg.msg {
+            xege_ffi::ege_mouse_msg_e_mouse_msg_down => MouseEventKind::Down,
+            xege_ffi::ege_mouse_msg_e_mouse_msg_up => MouseEventKind::Up,
+            xege_ffi::ege_mouse_msg_e_mouse_msg_wheel => MouseEventKind::Wheel,
+            _ => MouseEventKind::Move,
+        };
+        let has = |flag: ege_mouse_flag_e| msg.flags & flag as u32 != 0;
+        // EGE also sets the flags of the held buttons on move and wheel messages.
+        let held = [
+            (ege_mouse_flag_e_mouse_flag_left, MouseButton::Left),
+            (ege_mouse_flag_e_mouse_flag_right, MouseButton::Right),
+            (ege_mouse_flag_e_mouse_flag_mid, MouseButton::Middle),
+            (ege_mouse_flag_e_mouse_flag_x1, MouseButton::X1),
+            (ege_mouse_flag_e_mouse_flag_x2, MouseButton::X2),
+        ]
+        .into_iter()
+        .filter_map(|(flag, button)| has(flag).then_some(button));
+        let button = held
+            .clone()
+            .next()
+            .filter(|_| matches!(kind, MouseEventKind::Down | MouseEventKind::Up));
+        let buttons = held.fold(MouseButtons::none(), |buttons, button| {
+            buttons | MouseButtons::from(button)
+        });
+        let mut modifiers = KeyFlags::none();
+        if has(ege_mouse_flag_e_mouse_flag_shift) {
+            modifiers |= KeyFlags::Shift;
+        }
+        if has(ege_mouse_flag_e_mouse_flag_ctrl) {
+            modifiers |= KeyFlags::Ctrl;
+        }
+        Self {
+            kind,
+            button,
+            buttons,
+            pos: Point { x: msg.x, y: msg.y },
+            wheel: msg.wheel,
+            modifiers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+
+    const POS: Point = Point { x: 3, y: 4 };
+
+    #[test]
+    fn new_sets_the_button() {
+        let msg = MouseMsg::new(
+            MouseEventKind::Down,
+            Some(MouseButton::Right),
+            POS,
+            0,
+            KeyFlags::Ctrl,
+        );
+        assert!(msg.is_down() && !msg.is_up() && !msg.is_move() && !msg.is_wheel());
+        assert_eq!(msg.button(), Some(MouseButton::Right));
+        assert_eq!(msg.buttons, MouseButtons::Right);
+        assert_eq!(
+            (msg.pos(), msg.wheel(), msg.modifiers),
+            (POS, 0, KeyFlags::Ctrl)
+        );
+        assert!(msg.is_right() && !msg.is_left() && !msg.is_middle());
+
+        let wheel = MouseMsg::new(MouseEventKind::Wheel, None, POS, -120, KeyFlags::none());
+        assert!(wheel.is_wheel());
+        assert_eq!(
+            (wheel.button(), wheel.buttons),
+            (None, MouseButtons::none())
+        );
+        assert_eq!(wheel.wheel(), -120);
+    }
+
+    #[test]
+    fn held_buttons_of_a_drag() {
+        let drag = MouseMsg {
+            buttons: MouseButtons::Left | MouseButtons::Middle,
+            ..MouseMsg::new(MouseEventKind::Move, None, POS, 0, KeyFlags::none())
+        };
+        assert!(drag.is_move());
+        assert_eq!(drag.button(), None);
+        assert!(drag.is_left() && drag.is_middle() && !drag.is_right());
+        assert_eq!(Event::try_from(drag), Ok(Event::MouseMove { pos: POS }));
+    }
+
+    #[test]
+    fn events_of_messages() {
+        let up = MouseMsg::new(
+            MouseEventKind::Up,
+            Some(MouseButton::X1),
+            POS,
+            0,
+            KeyFlags::none(),
+        );
+        assert_eq!(
+            Event::try_from(up),
+            Ok(Event::MouseUp {
+                button: MouseButton::X1,
+                pos: POS
+            })
+        );
+        let wheel = MouseMsg::new(MouseEventKind::Wheel, None, POS, 240, KeyFlags::Shift);
+        assert_eq!(
+            Event::try_from(wheel),
+            Ok(Event::MouseWheel {
+                delta: 240,
+                pos: POS
+            })
+        );
+        let down = MouseMsg::new(MouseEventKind::Down, None, POS, 0, KeyFlags::none());
+        assert_eq!(Event::try_from(down), Err(down));
     }
 }
@@ -2,12 +2,13 @@ use std::ptr::null;
 
 use xege_ffi::*;
 
-use crate::{DrawableDevice, Point, Rect, mat::IntoEGEMatrix};
+use crate::{DashPattern, EgeDevice, PathData, PathFillMode, Point, Rect, mat::IntoEGEMatrix};
 
 /// Graphics path.
 #[derive(Debug)]
 pub struct Path {
     pub(crate) ptr: *mut ege_ege_path,
+    pub(crate) fill_mode: PathFillMode,
 }
 
 impl Path {
@@ -15,6 +16,7 @@ impl Path {
     pub fn new() -> Self {
         Self {
             ptr: unsafe { ege_ege_path_create() },
+            fill_mode: PathFillMode::Default,
         }
     }
 }
@@ -29,22 +31,11 @@ impl Clone for Path {
     fn clone(&self) -> Self {
         Self {
             ptr: unsafe { ege_ege_path_clone(self.ptr) },
+            fill_mode: self.fill_mode,
         }
     }
 }
 
-/// Path fill mode.
-#[repr(i32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum PathFillMode {
-    /// Default fill mode.
-    Default = ege_fill_mode_FILLMODE_DEFAULT,
-    /// Winding fill mode.
-    Winding = ege_fill_mode_FILLMODE_WINDING,
-    /// Alternate fill mode.
-    Alternate = ege_fill_mode_FILLMODE_ALTERNATE,
-}
-
 /// Font styles.
 #[bitmask_enum::bitmask(i32)]
 pub enum FontStyle {
@@ -82,15 +73,24 @@ impl Path {
 
     /// Set the fill mode.
     pub fn setfillmode(&mut self, mode: PathFillMode) {
+        self.fill_mode = mode;
         unsafe { ege_ege_path_setfillmode(self.ptr, mode as i32) };
     }
 
+    /// Get the fill mode.
+    pub fn getfillmode(&self) -> PathFillMode {
+        self.fill_mode
+    }
+
     /// Widen the path.
     /// 
     /// # Parameters
     /// - `width`: The width of the stroke.
     /// - `matrix`: The transformation matrix.
     /// - `flatness`: The flatness of the stroke.
+    ///
+    /// # Note
+    /// The path is processed by GDI+. See `PathData::widen` for a pure-Rust version.
     pub fn widen(&mut self, width: f32, matrix: Option<impl IntoEGEMatrix>, flatness: f32) {
         let mut ptr = null();
         let mat;
@@ -103,11 +103,45 @@ impl Path {
         }
     }
 
+    /// Widen the path with a dash pattern.
+    ///
+    /// # Parameters
+    /// - `width`: The width of the stroke.
+    /// - `dash`: The dash pattern.
+    /// - `matrix`: The transformation matrix.
+    /// - `flatness`: The flatness of the stroke.
+    ///
+    /// # Note
+    /// The path is flattened and split into dashes in Rust, then widened by GDI+.
+    /// The pattern restarts at every subpath. See `StrokeStyle::dash` for a pure-Rust version.
+    pub fn widen_dashed(
+        &mut self,
+        width: f32,
+        dash: &DashPattern,
+        matrix: Option<impl IntoEGEMatrix>,
+        flatness: f32,
+    ) {
+        let mut dashes = PathData::new();
+        for points in dash.apply(&PathData::from(&*self).polylines(flatness)) {
+            dashes.move_to(points[0].x, points[0].y);
+            for p in &points[1..] {
+                dashes.line_to(p.x, p.y);
+            }
+        }
+        let mode = self.fill_mode;
+        *self = Path::from(&dashes);
+        self.setfillmode(mode);
+        self.widen(width, matrix, flatness);
+    }
+
     /// Flatten the path.
     /// 
     /// # Parameters
     /// - `matrix`: The transformation matrix.
     /// - `flatness`: The flatness of the stroke.
+    ///
+    /// # Note
+    /// The path is processed by GDI+. See `PathData::flatten` for a pure-Rust version.
     pub fn flatten(&mut self, matrix: Option<impl IntoEGEMatrix>, flatness: f32) {
         let mut ptr = null();
         let mat;
@@ -187,7 +221,10 @@ impl Path {
     /// - `x`: The x-coordinate of the point.
     /// - `y`: The y-coordinate of the point.
     /// - `image`: The image to test against.
-    pub fn inpath(&self, x: f32, y: f32, image: Option<&impl DrawableDevice>) -> bool {
+    ///
+    /// # Note
+    /// The test is done by GDI+. See `PathData::inpath` for a pure-Rust version.
+    pub fn inpath(&self, x: f32, y: f32, image: Option<&impl EgeDevice>) -> bool {
         if let Some(device) = image {
             unsafe { ege_ege_path_inpath1(self.ptr, x, y, device.const_ptr()) }
         } else {
@@ -201,7 +238,10 @@ impl Path {
     /// - `x`: The x-coordinate of the point.
     /// - `y`: The y-coordinate of the point.
     /// - `image`: The image to test against.
-    pub fn instroke(&self, x: f32, y: f32, image: Option<&impl DrawableDevice>) -> bool {
+    ///
+    /// # Note
+    /// The test is done by GDI+. See `PathData::instroke` for a pure-Rust version.
+    pub fn instroke(&self, x: f32, y: f32, image: Option<&impl EgeDevice>) -> bool {
         if let Some(device) = image {
             unsafe { ege_ege_path_instroke1(self.ptr, x, y, device.const_ptr()) }
         } else {
@@ -228,7 +268,7 @@ impl Path {
     pub fn getbounds(
         &self,
         matrix: Option<impl IntoEGEMatrix>,
-        image: Option<&impl DrawableDevice>,
+        image: Option<&impl EgeDevice>,
     ) -> Rect<f32> {
         let mut ptr = null();
         let mat;
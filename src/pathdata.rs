@@ -0,0 +1,767 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+#[cfg(feature = "ege")]
+use xege_ffi::*;
+
+#[cfg(feature = "ege")]
+use crate::Path;
+use crate::{Point, Rect, mat::IntoEGEMatrix};
+
+/// The flatness used when `Path` geometry is processed in Rust, the same as the GDI+ default.
+#[cfg(feature = "ege")]
+pub(crate) const FLATNESS: f32 = 0.25;
+
+/// A segment of a `PathData`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    /// Start a new subpath at the point.
+    MoveTo(Point<f32>),
+    /// Straight line to the point.
+    LineTo(Point<f32>),
+    /// Quadratic bezier curve with a control point and an end point.
+    QuadTo(Point<f32>, Point<f32>),
+    /// Cubic bezier curve with two control points and an end point.
+    CubicTo(Point<f32>, Point<f32>, Point<f32>),
+    /// Elliptical arc to the point, with the same parameters as the SVG `A` command.
+    ArcTo {
+        /// The x-radius of the ellipse.
+        rx: f32,
+        /// The y-radius of the ellipse.
+        ry: f32,
+        /// The rotation of the x-axis of the ellipse in degrees.
+        rotation: f32,
+        /// Whether the arc spans more than 180 degrees.
+        large_arc: bool,
+        /// Whether the arc is drawn in the positive-angle direction.
+        sweep: bool,
+        /// The end point.
+        to: Point<f32>,
+    },
+    /// Close the current subpath.
+    Close,
+}
+
+/// Graphics path geometry owned by Rust.
+///
+/// Unlike `Path`, the segments can be inspected and edited, and no EGE object is
+/// required until the geometry is converted with `Path::from`.
+///
+/// # Note
+/// Segments that are not preceded by `MoveTo` start at the origin, or at the
+/// start of the last subpath after `Close`, like in SVG.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathData {
+    segments: Vec<PathSegment>,
+}
+
+impl PathData {
+    /// Create a new empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new subpath.
+    ///
+    /// # Parameters
+    /// - `x`: The x-coordinate of the start point.
+    /// - `y`: The y-coordinate of the start point.
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.push(PathSegment::MoveTo(Point { x, y }))
+    }
+
+    /// Add a straight line.
+    ///
+    /// # Parameters
+    /// - `x`: The x-coordinate of the end point.
+    /// - `y`: The y-coordinate of the end point.
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.push(PathSegment::LineTo(Point { x, y }))
+    }
+
+    /// Add a quadratic bezier curve.
+    ///
+    /// # Parameters
+    /// - `x1`: The x-coordinate of the control point.
+    /// - `y1`: The y-coordinate of the control point.
+    /// - `x`: The x-coordinate of the end point.
+    /// - `y`: The y-coordinate of the end point.
+    pub fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) -> &mut Self {
+        self.push(PathSegment::QuadTo(Point { x: x1, y: y1 }, Point { x, y }))
+    }
+
+    /// Add a cubic bezier curve.
+    ///
+    /// # Parameters
+    /// - `x1`: The x-coordinate of the first control point.
+    /// - `y1`: The y-coordinate of the first control point.
+    /// - `x2`: The x-coordinate of the second control point.
+    /// - `y2`: The y-coordinate of the second control point.
+    /// - `x`: The x-coordinate of the end point.
+    /// - `y`: The y-coordinate of the end point.
+    pub fn cubic_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) -> &mut Self {
+        self.push(PathSegment::CubicTo(
+            Point { x: x1, y: y1 },
+            Point { x: x2, y: y2 },
+            Point { x, y },
+        ))
+    }
+
+    /// Add an elliptical arc.
+    ///
+    /// # Parameters
+    /// - `rx`: The x-radius of the ellipse.
+    /// - `ry`: The y-radius of the ellipse.
+    /// - `rotation`: The rotation of the x-axis of the ellipse in degrees.
+    /// - `large_arc`: Whether the arc spans more than 180 degrees.
+    /// - `sweep`: Whether the arc is drawn in the positive-angle direction.
+    /// - `x`: The x-coordinate of the end point.
+    /// - `y`: The y-coordinate of the end point.
+    ///
+    /// # Note
+    /// The parameters are the same as the SVG `A` command. Radii that are too small
+    /// to reach the end point are scaled up.
+    #[allow(clippy::too_many_arguments)]
+    pub fn arc_to(
+        &mut self,
+        rx: f32,
+        ry: f32,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        x: f32,
+        y: f32,
+    ) -> &mut Self {
+        self.push(PathSegment::ArcTo {
+            rx,
+            ry,
+            rotation,
+            large_arc,
+            sweep,
+            to: Point { x, y },
+        })
+    }
+
+    /// Close the current subpath.
+    pub fn close(&mut self) -> &mut Self {
+        self.push(PathSegment::Close)
+    }
+
+    /// Append a segment.
+    pub fn push(&mut self, segment: PathSegment) -> &mut Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Remove all segments.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    /// Get the segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Get the segments of the path for editing.
+    pub fn segments_mut(&mut self) -> &mut Vec<PathSegment> {
+        &mut self.segments
+    }
+
+    /// Iterate over the segments of the path.
+    pub fn iter(&self) -> std::slice::Iter<'_, PathSegment> {
+        self.segments.iter()
+    }
+
+    /// Get the number of segments.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Checks if the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Get the end point of the last segment.
+    ///
+    /// # Returns
+    /// `None` if the path is empty.
+    pub fn current_point(&self) -> Option<Point<f32>> {
+        (!self.is_empty()).then(|| self.walk(|_, _| {}))
+    }
+
+    /// Call `f` with the start point of every segment, resolving implicit starts
+    /// and `Close` like SVG does.
+    ///
+    /// # Returns
+    /// The end point of the last segment.
+    pub(crate) fn walk(&self, mut f: impl FnMut(Point<f32>, PathSegment)) -> Point<f32> {
+        let mut current = Point { x: 0.0, y: 0.0 };
+        let mut start = current;
+        for &segment in &self.segments {
+            f(current, segment);
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    current = p;
+                    start = p;
+                }
+                PathSegment::Close => current = start,
+                _ => current = end_point(segment).unwrap_or(current),
+            }
+        }
+        current
+    }
+
+    /// Get a copy of the path where quadratic curves and arcs are replaced by
+    /// cubic bezier curves.
+    pub fn to_cubics(&self) -> PathData {
+        let mut data = PathData::new();
+        self.walk(|from, segment| match segment {
+            PathSegment::QuadTo(c, to) => {
+                let (c1, c2) = quad_to_cubic(from, c, to);
+                data.push(PathSegment::CubicTo(c1, c2, to));
+            }
+            PathSegment::ArcTo {
+                rx,
+                ry,
+                rotation,
+                large_arc,
+                sweep,
+                to,
+            } => {
+                for (c1, c2, p) in arc_to_cubics(from, rx, ry, rotation, large_arc, sweep, to) {
+                    data.push(PathSegment::CubicTo(c1, c2, p));
+                }
+            }
+            segment => {
+                data.push(segment);
+            }
+        });
+        data
+    }
+
+    /// Transform the path.
+    ///
+    /// # Parameters
+    /// - `matrix`: The transformation matrix.
+    ///
+    /// # Note
+    /// Quadratic curves and arcs are converted to cubic bezier curves first, see `to_cubics`.
+    pub fn transform(&mut self, matrix: impl IntoEGEMatrix) {
+        let [m11, m12, m21, m22, m31, m32] = matrix.to_affine();
+        let apply = |p: Point<f32>| Point {
+            x: m11 * p.x + m21 * p.y + m31,
+            y: m12 * p.x + m22 * p.y + m32,
+        };
+        let mut data = self.to_cubics();
+        for segment in data.segments.iter_mut() {
+            *segment = match *segment {
+                PathSegment::MoveTo(p) => PathSegment::MoveTo(apply(p)),
+                PathSegment::LineTo(p) => PathSegment::LineTo(apply(p)),
+                PathSegment::CubicTo(c1, c2, p) => {
+                    PathSegment::CubicTo(apply(c1), apply(c2), apply(p))
+                }
+                segment => segment,
+            };
+        }
+        *self = data;
+    }
+
+    /// Get the bounding box of the control points of the path.
+    ///
+    /// # Returns
+    /// `None` if the path is empty.
+    ///
+    /// # Note
+    /// Quadratic curves and arcs are converted to cubic bezier curves first. The box
+    /// contains the curves but may be larger than them.
+    pub fn getbounds(&self) -> Option<Rect<f32>> {
+        let mut points = vec![];
+        self.to_cubics().walk(|from, segment| match segment {
+            PathSegment::MoveTo(p) => points.push(p),
+            PathSegment::LineTo(p) => points.extend([from, p]),
+            PathSegment::CubicTo(c1, c2, p) => points.extend([from, c1, c2, p]),
+            _ => {}
+        });
+        if points.is_empty() {
+            return None;
+        }
+        let (mut min, mut max) = (points[0], points[0]);
+        for p in &points {
+            min = Point {
+                x: min.x.min(p.x),
+                y: min.y.min(p.y),
+            };
+            max = Point {
+                x: max.x.max(p.x),
+                y: max.y.max(p.y),
+            };
+        }
+        Some(Rect {
+            x: min.x,
+            y: min.y,
+            width: max.x - min.x,
+            height: max.y - min.y,
+        })
+    }
+}
+
+impl PathData {
+    /// Flatten the path into polylines, one per subpath.
+    ///
+    /// # Parameters
+    /// - `tolerance`: The largest distance between a curve and its polyline.
+    ///
+    /// # Returns
+    /// The points of every subpath, with whether the subpath was closed. The start
+    /// point is not repeated at the end of a closed subpath.
+    pub(crate) fn polylines(&self, tolerance: f32) -> Vec<(Vec<Point<f32>>, bool)> {
+        let tolerance = tolerance.max(1e-3);
+        let mut polylines: Vec<(Vec<Point<f32>>, bool)> = vec![];
+        let mut current: Vec<Point<f32>> = vec![];
+        let mut finish = |current: &mut Vec<Point<f32>>, closed: bool| {
+            if closed && current.len() > 1 && current.first() == current.last() {
+                current.pop();
+            }
+            if !current.is_empty() {
+                polylines.push((std::mem::take(current), closed));
+            }
+        };
+        self.to_cubics().walk(|from, segment| {
+            if current.is_empty() && !matches!(segment, PathSegment::MoveTo(_) | PathSegment::Close)
+            {
+                current.push(from);
+            }
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    finish(&mut current, false);
+                    current.push(p);
+                }
+                PathSegment::LineTo(p) => current.push(p),
+                PathSegment::CubicTo(c1, c2, p) => {
+                    flatten_cubic(from, c1, c2, p, tolerance, &mut current);
+                }
+                PathSegment::Close => finish(&mut current, true),
+                _ => {}
+            }
+        });
+        finish(&mut current, false);
+        polylines
+    }
+}
+
+/// Append the points of a flattened cubic bezier curve, excluding the start point.
+pub(crate) fn flatten_cubic(
+    p0: Point<f32>,
+    p1: Point<f32>,
+    p2: Point<f32>,
+    p3: Point<f32>,
+    tolerance: f32,
+    points: &mut Vec<Point<f32>>,
+) {
+    // The distance to the chord is at most 3/4 of the largest second difference,
+    // and it shrinks with the square of the number of subdivisions.
+    let dd = |a: Point<f32>, b: Point<f32>, c: Point<f32>| {
+        (a.x - 2.0 * b.x + c.x).hypot(a.y - 2.0 * b.y + c.y)
+    };
+    let bound = 0.75 * dd(p0, p1, p2).max(dd(p1, p2, p3));
+    let count = (bound / tolerance).sqrt().ceil().clamp(1.0, 1024.0) as usize;
+    for i in 1..=count {
+        let t = i as f32 / count as f32;
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        points.push(Point {
+            x: a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+            y: a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+        });
+    }
+}
+
+impl<'a> IntoIterator for &'a PathData {
+    type Item = &'a PathSegment;
+    type IntoIter = std::slice::Iter<'a, PathSegment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+impl IntoIterator for PathData {
+    type Item = PathSegment;
+    type IntoIter = std::vec::IntoIter<PathSegment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.into_iter()
+    }
+}
+
+impl FromIterator<PathSegment> for PathData {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<PathSegment> for PathData {
+    fn extend<I: IntoIterator<Item = PathSegment>>(&mut self, iter: I) {
+        self.segments.extend(iter);
+    }
+}
+
+/// The end point of a segment, `None` for `Close`.
+pub(crate) fn end_point(segment: PathSegment) -> Option<Point<f32>> {
+    match segment {
+        PathSegment::MoveTo(p) | PathSegment::LineTo(p) => Some(p),
+        PathSegment::QuadTo(_, p) | PathSegment::CubicTo(_, _, p) => Some(p),
+        PathSegment::ArcTo { to, .. } => Some(to),
+        PathSegment::Close => None,
+    }
+}
+
+/// Get the control points of the cubic bezier curve equal to a quadratic one.
+pub(crate) fn quad_to_cubic(
+    from: Point<f32>,
+    c: Point<f32>,
+    to: Point<f32>,
+) -> (Point<f32>, Point<f32>) {
+    let c1 = Point {
+        x: from.x + 2.0 / 3.0 * (c.x - from.x),
+        y: from.y + 2.0 / 3.0 * (c.y - from.y),
+    };
+    let c2 = Point {
+        x: to.x + 2.0 / 3.0 * (c.x - to.x),
+        y: to.y + 2.0 / 3.0 * (c.y - to.y),
+    };
+    (c1, c2)
+}
+
+/// Approximate an SVG arc with cubic bezier curves of at most 90 degrees each.
+///
+/// Follows the endpoint to center conversion of the SVG specification (F.6.5).
+/// Degenerate arcs become a straight line, returned as a single flat curve.
+pub(crate) fn arc_to_cubics(
+    from: Point<f32>,
+    rx: f32,
+    ry: f32,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Point<f32>,
+) -> Vec<(Point<f32>, Point<f32>, Point<f32>)> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if from == to {
+        return vec![];
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return vec![(from, to, to)];
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from.x - to.x) / 2.0, (from.y - to.y) / 2.0);
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = (num / den).max(0.0).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let cx1 = coef * rx * y1 / ry;
+    let cy1 = -coef * ry * x1 / rx;
+    let cx = cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0;
+    let cy = sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0;
+
+    let angle = |ux: f32, uy: f32, vx: f32, vy: f32| {
+        let a = (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        if a.is_nan() { 0.0 } else { a }
+    };
+    let (ux, uy) = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let (vx, vy) = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let start = angle(1.0, 0.0, ux, uy);
+    let mut delta = angle(ux, uy, vx, vy) % (2.0 * PI);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    let count = (delta.abs() / FRAC_PI_2 - 1e-4).ceil().max(1.0) as usize;
+    let step = delta / count as f32;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    let point = |t: f32, rx: f32, ry: f32| {
+        let (s, c) = t.sin_cos();
+        (rx * c, ry * s)
+    };
+    let map = |(x, y): (f32, f32)| Point {
+        x: cos * x - sin * y + cx,
+        y: sin * x + cos * y + cy,
+    };
+    let mut curves = Vec::with_capacity(count);
+    let mut t = start;
+    for i in 0..count {
+        let t2 = t + step;
+        let (x0, y0) = point(t, rx, ry);
+        let (x3, y3) = point(t2, rx, ry);
+        let (dx0, dy0) = point(t + FRAC_PI_2, rx, ry);
+        let (dx3, dy3) = point(t2 + FRAC_PI_2, rx, ry);
+        let c1 = map((x0 + k * dx0, y0 + k * dy0));
+        let c2 = map((x3 - k * dx3, y3 - k * dy3));
+        let end = if i + 1 == count { to } else { map((x3, y3)) };
+        curves.push((c1, c2, end));
+        t = t2;
+    }
+    curves
+}
+
+#[cfg(feature = "ege")]
+impl From<&PathData> for Path {
+    /// Build an EGE path from the geometry.
+    ///
+    /// Quadratic curves and arcs are converted to cubic bezier curves.
+    fn from(data: &PathData) -> Self {
+        let path = Path::new();
+        let mut open = false;
+        data.to_cubics().walk(|from, segment| {
+            let ege = |p: Point<f32>| ege_ege_point { x: p.x, y: p.y };
+            match segment {
+                PathSegment::MoveTo(_) => {
+                    unsafe { ege_ege_path_start(path.ptr) };
+                    open = false;
+                }
+                PathSegment::LineTo(to) => {
+                    unsafe { ege_ege_path_addline(path.ptr, from.x, from.y, to.x, to.y) };
+                    open = true;
+                }
+                PathSegment::CubicTo(c1, c2, to) => {
+                    let points = [ege(from), ege(c1), ege(c2), ege(to)];
+                    unsafe { ege_ege_path_addbezier(path.ptr, 4, points.as_ptr()) };
+                    open = true;
+                }
+                PathSegment::Close if open => {
+                    unsafe { ege_ege_path_close(path.ptr) };
+                    open = false;
+                }
+                _ => {}
+            }
+        });
+        path
+    }
+}
+
+#[cfg(feature = "ege")]
+impl From<PathData> for Path {
+    fn from(data: PathData) -> Self {
+        Self::from(&data)
+    }
+}
+
+#[cfg(feature = "ege")]
+impl From<&Path> for PathData {
+    /// Read the geometry back from an EGE path.
+    ///
+    /// EGE stores lines and cubic bezier curves only, so the result contains no
+    /// quadratic curves or arcs.
+    fn from(path: &Path) -> Self {
+        let count = path.pointcount().max(0) as usize;
+        let mut points = vec![ege_ege_point { x: 0.0, y: 0.0 }; count];
+        let mut types = vec![0u8; count];
+        unsafe {
+            ege_ege_path_getpathpoints(path.ptr, points.as_mut_ptr());
+            ege_ege_path_getpathtypes(path.ptr, types.as_mut_ptr());
+        }
+        let points = points
+            .into_iter()
+            .map(|p| Point { x: p.x, y: p.y })
+            .collect::<Vec<_>>();
+        // GDI+ path point types.
+        const TYPE_MASK: u8 = 0x07;
+        const START: u8 = 0x00;
+        const BEZIER: u8 = 0x03;
+        const CLOSE: u8 = 0x80;
+        let mut data = PathData::new();
+        let mut i = 0;
+        while i < count {
+            let ty = types[i];
+            match ty & TYPE_MASK {
+                START => {
+                    data.push(PathSegment::MoveTo(points[i]));
+                }
+                BEZIER if i + 2 < count => {
+                    data.push(PathSegment::CubicTo(
+                        points[i],
+                        points[i + 1],
+                        points[i + 2],
+                    ));
+                    i += 2;
+                }
+                _ => {
+                    data.push(PathSegment::LineTo(points[i]));
+                }
+            }
+            if types[i] & CLOSE != 0 {
+                data.push(PathSegment::Close);
+            }
+            i += 1;
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mat::Mat3;
+
+    fn p(x: f32, y: f32) -> Point<f32> {
+        Point { x, y }
+    }
+
+    fn close_to(a: Point<f32>, b: Point<f32>) -> bool {
+        (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+    }
+
+    #[test]
+    fn builder() {
+        let mut path = PathData::new();
+        assert!(path.is_empty());
+        assert_eq!(path.current_point(), None);
+        path.move_to(1.0, 2.0)
+            .line_to(3.0, 2.0)
+            .quad_to(4.0, 3.0, 3.0, 4.0)
+            .close();
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::MoveTo(p(1.0, 2.0)),
+                PathSegment::LineTo(p(3.0, 2.0)),
+                PathSegment::QuadTo(p(4.0, 3.0), p(3.0, 4.0)),
+                PathSegment::Close,
+            ]
+        );
+        // `Close` returns to the start of the subpath.
+        assert_eq!(path.current_point(), Some(p(1.0, 2.0)));
+        assert_eq!(path.iter().copied().collect::<PathData>(), path);
+    }
+
+    #[test]
+    fn implicit_start() {
+        let mut path = PathData::new();
+        path.line_to(2.0, 0.0);
+        let mut starts = vec![];
+        path.walk(|from, _| starts.push(from));
+        assert_eq!(starts, [p(0.0, 0.0)]);
+
+        let bounds = path.getbounds().unwrap();
+        assert_eq!(
+            (bounds.x, bounds.y, bounds.width, bounds.height),
+            (0.0, 0.0, 2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn quad_to_cubic_is_exact() {
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0).quad_to(3.0, 3.0, 6.0, 0.0);
+        assert_eq!(
+            path.to_cubics().segments()[1],
+            PathSegment::CubicTo(p(2.0, 2.0), p(4.0, 2.0), p(6.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn arc_to_cubics() {
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0)
+            .arc_to(1.0, 1.0, 0.0, false, true, 2.0, 0.0);
+        let cubics = path.to_cubics();
+        let Some(&PathSegment::CubicTo(_, _, end)) = cubics.segments().last() else {
+            panic!("The arc was not converted: {cubics:?}");
+        };
+        assert!(close_to(end, p(2.0, 0.0)));
+        // The half circle around (1, 0) is flattened onto it.
+        let (points, closed) = &cubics.polylines(0.01)[0];
+        assert!(!closed);
+        let radius = |q: &Point<f32>| (q.x - 1.0).hypot(q.y);
+        assert!(points.iter().all(|q| (radius(q) - 1.0).abs() < 0.02));
+        assert!(points.iter().all(|q| q.y >= -1e-4) || points.iter().all(|q| q.y <= 1e-4));
+
+        // Radii that are too small are scaled up to reach the end point.
+        let mut small = PathData::new();
+        small
+            .move_to(0.0, 0.0)
+            .arc_to(0.1, 0.1, 0.0, false, true, 2.0, 0.0);
+        assert_eq!(small.to_cubics(), cubics);
+    }
+
+    #[test]
+    fn transform() {
+        let mut path = PathData::new();
+        path.move_to(1.0, 1.0).quad_to(2.0, 0.0, 3.0, 1.0);
+        let scale = Mat3::from([[2.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 1.0]]);
+        path.transform(scale);
+        assert_eq!(path.segments()[0], PathSegment::MoveTo(p(2.0, 3.0)));
+        let PathSegment::CubicTo(c1, c2, end) = path.segments()[1] else {
+            panic!("Unexpected segments: {path:?}");
+        };
+        assert!(close_to(c1, p(10.0 / 3.0, 1.0)));
+        assert!(close_to(c2, p(14.0 / 3.0, 1.0)));
+        assert_eq!(end, p(6.0, 3.0));
+    }
+
+    #[test]
+    fn transform_translates() {
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0).line_to(1.0, 2.0);
+        path.transform(Mat3::from([
+            [1.0, 0.0, 5.0],
+            [0.0, 1.0, 7.0],
+            [0.0, 0.0, 1.0],
+        ]));
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::MoveTo(p(5.0, 7.0)),
+                PathSegment::LineTo(p(6.0, 9.0)),
+            ]
+        );
+
+        // A quarter turn counterclockwise, then the translation.
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0).line_to(1.0, 2.0);
+        path.transform(Mat3::from([
+            [0.0, -1.0, 5.0],
+            [1.0, 0.0, 7.0],
+            [0.0, 0.0, 1.0],
+        ]));
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::MoveTo(p(5.0, 7.0)),
+                PathSegment::LineTo(p(3.0, 8.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn polylines() {
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0)
+            .line_to(1.0, 0.0)
+            .line_to(0.0, 0.0)
+            .close()
+            .move_to(5.0, 5.0)
+            .line_to(6.0, 5.0);
+        assert_eq!(
+            path.polylines(0.1),
+            [
+                (vec![p(0.0, 0.0), p(1.0, 0.0)], true),
+                (vec![p(5.0, 5.0), p(6.0, 5.0)], false),
+            ]
+        );
+    }
+}
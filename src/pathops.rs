@@ -0,0 +1,497 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "ege")]
+use crate::Path;
+#[cfg(feature = "ege")]
+use crate::pathdata::FLATNESS;
+use crate::{PathData, PathFillMode, PathSegment, Point};
+
+/// Boolean operation on two shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoolOp {
+    /// The area covered by either shape.
+    Union,
+    /// The area covered by both shapes.
+    Intersect,
+    /// The area covered by the first shape but not the second.
+    Difference,
+    /// The area covered by exactly one of the shapes.
+    Xor,
+}
+
+impl BoolOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Self::Union => a || b,
+            Self::Intersect => a && b,
+            Self::Difference => a && !b,
+            Self::Xor => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Vec2 {
+    x: f64,
+    y: f64,
+}
+
+impl Vec2 {
+    fn sub(self, o: Self) -> Self {
+        Vec2 {
+            x: self.x - o.x,
+            y: self.y - o.y,
+        }
+    }
+
+    fn cross(self, o: Self) -> f64 {
+        self.x * o.y - self.y * o.x
+    }
+
+    fn dot(self, o: Self) -> f64 {
+        self.x * o.x + self.y * o.y
+    }
+
+    fn lerp(self, o: Self, t: f64) -> Self {
+        Vec2 {
+            x: self.x + (o.x - self.x) * t,
+            y: self.y + (o.y - self.y) * t,
+        }
+    }
+
+    /// Key used to match equal vertices.
+    fn key(self) -> (i64, i64) {
+        ((self.x * 1e5).round() as i64, (self.y * 1e5).round() as i64)
+    }
+}
+
+type Edge = (Vec2, Vec2);
+
+/// The closed rings of a shape.
+fn rings(data: &PathData, tolerance: f32) -> Vec<Vec<Vec2>> {
+    data.polylines(tolerance)
+        .into_iter()
+        .map(|(points, _)| {
+            points
+                .into_iter()
+                .map(|p| Vec2 {
+                    x: p.x as f64,
+                    y: p.y as f64,
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+fn ring_edges(rings: &[Vec<Vec2>]) -> Vec<Edge> {
+    rings
+        .iter()
+        .flat_map(|ring| (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()])))
+        .filter(|(a, b)| a.key() != b.key())
+        .collect()
+}
+
+/// The winding number of the edges around a point.
+fn winding(edges: &[Edge], p: Vec2) -> i32 {
+    let mut w = 0;
+    for &(a, b) in edges {
+        let side = b.sub(a).cross(p.sub(a));
+        if a.y <= p.y && b.y > p.y && side > 0.0 {
+            w += 1;
+        } else if b.y <= p.y && a.y > p.y && side < 0.0 {
+            w -= 1;
+        }
+    }
+    w
+}
+
+fn is_inside(winding: i32, mode: PathFillMode) -> bool {
+    match mode {
+        PathFillMode::Winding => winding != 0,
+        PathFillMode::Default | PathFillMode::Alternate => winding % 2 != 0,
+    }
+}
+
+/// Split all edges at their intersections with each other.
+fn split_edges(edges: &[Edge]) -> Vec<Edge> {
+    const EPS: f64 = 1e-9;
+    let mut splits: Vec<Vec<(f64, Vec2)>> = edges.iter().map(|_| vec![]).collect();
+    // Parameter of `p` projected on the edge, if it lies strictly inside of it.
+    let on_edge = |(a, b): Edge, p: Vec2| {
+        let r = b.sub(a);
+        let len2 = r.dot(r);
+        let t = p.sub(a).dot(r) / len2;
+        let dist = r.cross(p.sub(a)).abs() / len2.sqrt();
+        (t > EPS && t < 1.0 - EPS && dist < 1e-7).then_some(t)
+    };
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (p0, p1) = edges[i];
+            let (q0, q1) = edges[j];
+            let (r, s) = (p1.sub(p0), q1.sub(q0));
+            // Endpoints lying on the other edge, this also covers collinear overlaps.
+            for q in [q0, q1] {
+                if let Some(t) = on_edge(edges[i], q) {
+                    splits[i].push((t, q));
+                }
+            }
+            for p in [p0, p1] {
+                if let Some(u) = on_edge(edges[j], p) {
+                    splits[j].push((u, p));
+                }
+            }
+            let d = r.cross(s);
+            if d.abs() <= EPS * r.dot(r).sqrt() * s.dot(s).sqrt() {
+                continue;
+            }
+            let qp = q0.sub(p0);
+            let t = qp.cross(s) / d;
+            let u = qp.cross(r) / d;
+            if t > EPS && t < 1.0 - EPS && u > EPS && u < 1.0 - EPS {
+                let point = p0.lerp(p1, t);
+                splits[i].push((t, point));
+                splits[j].push((u, point));
+            }
+        }
+    }
+    let mut result = vec![];
+    for (&(a, b), split) in edges.iter().zip(splits.iter_mut()) {
+        split.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut last = a;
+        for &(_, p) in split.iter().chain([&(1.0, b)]) {
+            if p.key() != last.key() {
+                result.push((last, p));
+                last = p;
+            }
+        }
+    }
+    result
+}
+
+/// Join directed edges into closed rings.
+fn join_rings(edges: Vec<Edge>) -> Vec<Vec<Vec2>> {
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, (a, _)) in edges.iter().enumerate() {
+        outgoing.entry(a.key()).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = vec![];
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let mut ring = vec![edges[first].0];
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let end = edges[current].1;
+            if end.key() == ring[0].key() {
+                break;
+            }
+            ring.push(end);
+            let next = outgoing
+                .get(&end.key())
+                .and_then(|list| list.iter().copied().find(|&i| !used[i]));
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        // Remove vertices in the middle of straight lines.
+        let mut i = 0;
+        while ring.len() >= 3 && i < ring.len() {
+            let n = ring.len();
+            let (prev, next) = (ring[(i + n - 1) % n], ring[(i + 1) % n]);
+            let (u, v) = (ring[i].sub(prev), next.sub(ring[i]));
+            if u.cross(v).abs() <= 1e-9 * u.dot(u).sqrt() * v.dot(v).sqrt() && u.dot(v) > 0.0 {
+                ring.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        if ring.len() >= 3 {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+impl PathData {
+    /// Combine two shapes with a boolean operation.
+    ///
+    /// # Parameters
+    /// - `mode`: The fill mode of this shape.
+    /// - `other`: The other shape.
+    /// - `other_mode`: The fill mode of the other shape.
+    /// - `op`: The boolean operation.
+    /// - `tolerance`: The flatness used to convert curves to lines.
+    ///
+    /// # Returns
+    /// The outline of the resulting area, made of closed polygons. It is filled the
+    /// same way with `PathFillMode::Winding` and `PathFillMode::Alternate`.
+    ///
+    /// # Note
+    /// All subpaths are treated as closed. `PathFillMode::Default` is the same as
+    /// `PathFillMode::Alternate`.
+    pub fn boolean(
+        &self,
+        mode: PathFillMode,
+        other: &PathData,
+        other_mode: PathFillMode,
+        op: BoolOp,
+        tolerance: f32,
+    ) -> PathData {
+        let a = ring_edges(&rings(self, tolerance));
+        let b = ring_edges(&rings(other, tolerance));
+        let all = a.iter().chain(&b).copied().collect::<Vec<_>>();
+
+        let mut seen = HashSet::new();
+        let mut kept = vec![];
+        for (p, q) in split_edges(&all) {
+            // Overlapping edges of both shapes are the same boundary.
+            let key = if p.key() < q.key() {
+                (p.key(), q.key())
+            } else {
+                (q.key(), p.key())
+            };
+            if !seen.insert(key) {
+                continue;
+            }
+            let d = q.sub(p);
+            let len = d.dot(d).sqrt();
+            let eps = len.min(1.0) * 1e-3;
+            let normal = Vec2 {
+                x: -d.y / len * eps,
+                y: d.x / len * eps,
+            };
+            let mid = p.lerp(q, 0.5);
+            let inside = |point: Vec2| {
+                op.apply(
+                    is_inside(winding(&a, point), mode),
+                    is_inside(winding(&b, point), other_mode),
+                )
+            };
+            let left = inside(Vec2 {
+                x: mid.x + normal.x,
+                y: mid.y + normal.y,
+            });
+            let right = inside(Vec2 {
+                x: mid.x - normal.x,
+                y: mid.y - normal.y,
+            });
+            // Keep the area on the left of every edge.
+            match (left, right) {
+                (true, false) => kept.push((p, q)),
+                (false, true) => kept.push((q, p)),
+                _ => {}
+            }
+        }
+
+        let mut data = PathData::new();
+        for ring in join_rings(kept) {
+            let point = |v: Vec2| Point {
+                x: v.x as f32,
+                y: v.y as f32,
+            };
+            data.push(PathSegment::MoveTo(point(ring[0])));
+            for &v in &ring[1..] {
+                data.push(PathSegment::LineTo(point(v)));
+            }
+            data.push(PathSegment::Close);
+        }
+        data
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Path {
+    fn boolean(&self, other: &Path, op: BoolOp) -> Path {
+        let data = PathData::from(self).boolean(
+            self.fill_mode,
+            &PathData::from(other),
+            other.fill_mode,
+            op,
+            FLATNESS,
+        );
+        let mut path = Path::from(&data);
+        path.setfillmode(PathFillMode::Winding);
+        path
+    }
+
+    /// Get the union of two paths.
+    ///
+    /// # Parameters
+    /// - `other`: The other path.
+    ///
+    /// # Returns
+    /// A new path covering the area of either path.
+    ///
+    /// # Note
+    /// The fill mode of each path decides its area. Curves are flattened, and the
+    /// result uses `PathFillMode::Winding`. See `PathData::boolean`.
+    pub fn union(&self, other: &Path) -> Path {
+        self.boolean(other, BoolOp::Union)
+    }
+
+    /// Get the intersection of two paths.
+    ///
+    /// # Parameters
+    /// - `other`: The other path.
+    ///
+    /// # Returns
+    /// A new path covering the area of both paths.
+    ///
+    /// # Note
+    /// See `Path::union`.
+    pub fn intersect(&self, other: &Path) -> Path {
+        self.boolean(other, BoolOp::Intersect)
+    }
+
+    /// Get the difference of two paths.
+    ///
+    /// # Parameters
+    /// - `other`: The path to subtract.
+    ///
+    /// # Returns
+    /// A new path covering the area of this path outside of `other`.
+    ///
+    /// # Note
+    /// See `Path::union`.
+    pub fn difference(&self, other: &Path) -> Path {
+        self.boolean(other, BoolOp::Difference)
+    }
+
+    /// Get the symmetric difference of two paths.
+    ///
+    /// # Parameters
+    /// - `other`: The other path.
+    ///
+    /// # Returns
+    /// A new path covering the area of exactly one of the paths.
+    ///
+    /// # Note
+    /// See `Path::union`.
+    pub fn xor(&self, other: &Path) -> Path {
+        self.boolean(other, BoolOp::Xor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> PathData {
+        let mut path = PathData::new();
+        path.move_to(x, y)
+            .line_to(x + width, y)
+            .line_to(x + width, y + height)
+            .line_to(x, y + height)
+            .close();
+        path
+    }
+
+    /// The area enclosed by the rings, counting holes as negative.
+    fn area(data: &PathData) -> f32 {
+        let signed = data
+            .polylines(0.1)
+            .iter()
+            .map(|(points, _)| {
+                (0..points.len())
+                    .map(|i| {
+                        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                        a.x * b.y - b.x * a.y
+                    })
+                    .sum::<f32>()
+                    / 2.0
+            })
+            .sum::<f32>();
+        signed.abs()
+    }
+
+    fn boolean(a: &PathData, b: &PathData, op: BoolOp) -> PathData {
+        a.boolean(PathFillMode::Winding, b, PathFillMode::Winding, op, 0.1)
+    }
+
+    fn inside(data: &PathData, x: f32, y: f32) -> bool {
+        data.inpath(x, y, PathFillMode::Alternate, 0.1)
+    }
+
+    #[test]
+    fn overlapping_rectangles() {
+        let a = rect(0.0, 0.0, 2.0, 2.0);
+        let b = rect(1.0, 1.0, 2.0, 2.0);
+        for (op, expected) in [
+            (BoolOp::Union, 7.0),
+            (BoolOp::Intersect, 1.0),
+            (BoolOp::Difference, 3.0),
+            (BoolOp::Xor, 6.0),
+        ] {
+            let result = boolean(&a, &b, op);
+            assert!((area(&result) - expected).abs() < 1e-4, "{op:?}: {result}");
+            for (x, y) in [(0.5, 0.5), (1.5, 1.5), (2.5, 2.5), (2.5, 0.5)] {
+                let a = inside(&a, x, y);
+                let b = inside(&b, x, y);
+                assert_eq!(
+                    inside(&result, x, y),
+                    op.apply(a, b),
+                    "{op:?} at ({x}, {y})"
+                );
+            }
+        }
+        // The union is a single ring without the inner edges.
+        let union = boolean(&a, &b, BoolOp::Union);
+        assert_eq!(union.polylines(0.1).len(), 1);
+        assert_eq!(union.polylines(0.1)[0].0.len(), 8);
+    }
+
+    #[test]
+    fn disjoint_rectangles() {
+        let a = rect(0.0, 0.0, 1.0, 1.0);
+        let b = rect(3.0, 0.0, 1.0, 1.0);
+        assert_eq!(boolean(&a, &b, BoolOp::Union).polylines(0.1).len(), 2);
+        assert!(boolean(&a, &b, BoolOp::Intersect).is_empty());
+        assert!((area(&boolean(&a, &b, BoolOp::Difference)) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn hole() {
+        let outer = rect(0.0, 0.0, 4.0, 4.0);
+        let inner = rect(1.0, 1.0, 2.0, 2.0);
+        let result = boolean(&outer, &inner, BoolOp::Difference);
+        assert_eq!(result.polylines(0.1).len(), 2);
+        assert!((area(&result) - 12.0).abs() < 1e-4);
+        for mode in [PathFillMode::Alternate, PathFillMode::Winding] {
+            assert!(!result.inpath(2.0, 2.0, mode, 0.1));
+            assert!(result.inpath(0.5, 2.0, mode, 0.1));
+        }
+        assert!(boolean(&inner, &outer, BoolOp::Difference).is_empty());
+        let intersection = boolean(&outer, &inner, BoolOp::Intersect);
+        assert_eq!(intersection.polylines(0.1).len(), 1);
+        assert!((area(&intersection) - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fill_mode_of_the_operands() {
+        // Two overlapping squares in one path.
+        let mut both = rect(0.0, 0.0, 2.0, 2.0);
+        both.extend(rect(1.0, 1.0, 2.0, 2.0));
+        let empty = PathData::new();
+        let alternate = both.boolean(
+            PathFillMode::Alternate,
+            &empty,
+            PathFillMode::Alternate,
+            BoolOp::Union,
+            0.1,
+        );
+        let winding = both.boolean(
+            PathFillMode::Winding,
+            &empty,
+            PathFillMode::Winding,
+            BoolOp::Union,
+            0.1,
+        );
+        assert!((area(&alternate) - 6.0).abs() < 1e-4);
+        assert!((area(&winding) - 7.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,635 @@
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{
+    Key, KeyFlags, KeyMsg, MouseButton, MouseButtons, MouseEventKind, MouseMsg, Point, Window,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("Reading or writing `{}` failed.", path.display())]
+    IOError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Invalid recording at line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+/// A key or mouse message of a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordedMsg {
+    Key(KeyMsg, KeyFlags),
+    Mouse(MouseMsg),
+    /// `keystate` reported a state of the key which the messages did not explain.
+    KeyState(Key, bool),
+    /// `mousepos` reported a position which the messages did not explain.
+    MousePos(Point),
+}
+
+/// A message and when it was received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecordedInput {
+    /// The frame in which the message was received, counted by `Window::next_frame`.
+    pub frame: u64,
+    pub msg: RecordedMsg,
+}
+
+/// A recorded input session.
+///
+/// # Note
+/// A recording is stored as text, one message per line:
+///
+/// ```text
+/// xege-recording 1
+/// <frame> down <virtual key> <key flags>
+/// <frame> up <virtual key> <key flags>
+/// <frame> char <code point>
+/// <frame> mouse <move|down|up|wheel> <left|right|middle|x1|x2|-> <x> <y> <wheel> <key flags> <buttons>
+/// <frame> keystate <virtual key> <0|1>
+/// <frame> mousepos <x> <y>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Recording {
+    /// The messages, in the order they were received.
+    pub inputs: Vec<RecordedInput>,
+}
+
+const HEADER: &str = "xege-recording 1";
+
+impl Recording {
+    /// Save the recording to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RecordingError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()).map_err(|source| RecordingError::IOError {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Load a recording from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|source| RecordingError::IOError {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .parse()
+    }
+}
+
+fn button_name(button: Option<MouseButton>) -> &'static str {
+    match button {
+        Some(MouseButton::Left) => "left",
+        Some(MouseButton::Right) => "right",
+        Some(MouseButton::Middle) => "middle",
+        Some(MouseButton::X1) => "x1",
+        Some(MouseButton::X2) => "x2",
+        None => "-",
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for input in &self.inputs {
+            write!(f, "{} ", input.frame)?;
+            match input.msg {
+                RecordedMsg::Key(KeyMsg::Down(key), flags) => {
+                    writeln!(f, "down {} {}", <Key as Into<u32>>::into(key), flags.bits())?
+                }
+                RecordedMsg::Key(KeyMsg::Up(key), flags) => {
+                    writeln!(f, "up {} {}", <Key as Into<u32>>::into(key), flags.bits())?
+                }
+                RecordedMsg::Key(KeyMsg::Char(c), _) => writeln!(f, "char {}", c as u32)?,
+                RecordedMsg::Mouse(msg) => {
+                    let kind = match msg.kind {
+                        MouseEventKind::Move => "move",
+                        MouseEventKind::Down => "down",
+                        MouseEventKind::Up => "up",
+                        MouseEventKind::Wheel => "wheel",
+                    };
+                    writeln!(
+                        f,
+                        "mouse {kind} {} {} {} {} {} {}",
+                        button_name(msg.button),
+                        msg.pos.x,
+                        msg.pos.y,
+                        msg.wheel,
+                        msg.modifiers.bits(),
+                        msg.buttons.bits()
+                    )?
+                }
+                RecordedMsg::KeyState(key, down) => writeln!(
+                    f,
+                    "keystate {} {}",
+                    <Key as Into<u32>>::into(key),
+                    down as u8
+                )?,
+                RecordedMsg::MousePos(pos) => writeln!(f, "mousepos {} {}", pos.x, pos.y)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn number<T: FromStr>(text: &str, name: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("Invalid {name} `{text}`."))
+}
+
+/// Parse a line of a recording.
+fn parse_input(line: &str) -> Result<RecordedInput, String> {
+    let mut fields = line.split_whitespace();
+    let mut next = |name: &str| fields.next().ok_or(format!("The {name} is missing."));
+    let frame = number(next("frame")?, "frame")?;
+    let flags =
+        |text: &str| number::<usize>(text, "flags").map(|bits| KeyFlags::from(bits).truncate());
+    let msg = match next("message")? {
+        "down" => RecordedMsg::Key(
+            KeyMsg::Down(Key::from(number::<u32>(next("key")?, "key")?)),
+            flags(next("flags")?)?,
+        ),
+        "up" => RecordedMsg::Key(
+            KeyMsg::Up(Key::from(number::<u32>(next("key")?, "key")?)),
+            flags(next("flags")?)?,
+        ),
+        "char" => {
+            let code = number::<u32>(next("character")?, "character")?;
+            let c = char::from_u32(code).ok_or(format!("Invalid character `{code}`."))?;
+            RecordedMsg::Key(KeyMsg::Char(c), KeyFlags::none())
+        }
+        "mouse" => {
+            let kind = match next("mouse message")? {
+                "move" => MouseEventKind::Move,
+                "down" => MouseEventKind::Down,
+                "up" => MouseEventKind::Up,
+                "wheel" => MouseEventKind::Wheel,
+                other => return Err(format!("Unknown mouse message `{other}`.")),
+            };
+            let button = match next("button")? {
+                "left" => Some(MouseButton::Left),
+                "right" => Some(MouseButton::Right),
+                "middle" => Some(MouseButton::Middle),
+                "x1" => Some(MouseButton::X1),
+                "x2" => Some(MouseButton::X2),
+                "-" => None,
+                other => return Err(format!("Unknown button `{other}`.")),
+            };
+            let x = number(next("x")?, "x")?;
+            let y = number(next("y")?, "y")?;
+            let wheel = number(next("wheel")?, "wheel")?;
+            let modifiers = flags(next("flags")?)?;
+            let buttons = number::<u8>(next("buttons")?, "buttons")?;
+            RecordedMsg::Mouse(MouseMsg {
+                buttons: MouseButtons::from(buttons).truncate(),
+                ..MouseMsg::new(kind, button, Point { x, y }, wheel, modifiers)
+            })
+        }
+        "keystate" => {
+            let key = Key::from(number::<u32>(next("key")?, "key")?);
+            let down = match next("state")? {
+                "0" => false,
+                "1" => true,
+                other => return Err(format!("Invalid state `{other}`.")),
+            };
+            RecordedMsg::KeyState(key, down)
+        }
+        "mousepos" => {
+            let x = number(next("x")?, "x")?;
+            let y = number(next("y")?, "y")?;
+            RecordedMsg::MousePos(Point { x, y })
+        }
+        other => return Err(format!("Unknown message `{other}`.")),
+    };
+    if let Some(extra) = fields.next() {
+        return Err(format!("Unexpected `{extra}`."));
+    }
+    Ok(RecordedInput { frame, msg })
+}
+
+impl FromStr for Recording {
+    type Err = RecordingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(RecordingError::Parse {
+                line: 1,
+                message: format!("The header `{HEADER}` is missing."),
+            });
+        }
+        let inputs = lines
+            .map(|(i, line)| {
+                parse_input(line).map_err(|message| RecordingError::Parse {
+                    line: i + 1,
+                    message,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { inputs })
+    }
+}
+
+/// The keys and the mouse position reported by `keystate` and `mousepos`.
+#[derive(Debug)]
+pub(crate) struct Held {
+    pub(crate) down: HashSet<Key>,
+    pub(crate) pos: Point,
+}
+
+impl Held {
+    fn new() -> Self {
+        Self {
+            down: HashSet::new(),
+            pos: Point { x: 0, y: 0 },
+        }
+    }
+
+    /// Follow a message.
+    ///
+    /// # Returns
+    /// * `true` if the keys or the mouse position changed, `false` otherwise.
+    fn apply(&mut self, msg: &RecordedMsg) -> bool {
+        let (key, down) = match *msg {
+            RecordedMsg::Key(KeyMsg::Down(key), _) => (Some(key), true),
+            RecordedMsg::Key(KeyMsg::Up(key), _) => (Some(key), false),
+            RecordedMsg::Key(KeyMsg::Char(_), _) => (None, false),
+            RecordedMsg::KeyState(key, down) => (Some(key), down),
+            RecordedMsg::Mouse(msg) => {
+                let moved = self.pos != msg.pos;
+                self.pos = msg.pos;
+                let key = msg.button.map(|button| match button {
+                    MouseButton::Left => Key::LeftButton,
+                    MouseButton::Right => Key::RightButton,
+                    MouseButton::Middle => Key::MiddleButton,
+                    MouseButton::X1 => Key::X1Button,
+                    MouseButton::X2 => Key::X2Button,
+                });
+                let pressed = match (msg.kind, key) {
+                    (MouseEventKind::Down, Some(key)) => self.down.insert(key),
+                    (MouseEventKind::Up, Some(key)) => self.down.remove(&key),
+                    _ => false,
+                };
+                return moved || pressed;
+            }
+            RecordedMsg::MousePos(pos) => {
+                let moved = self.pos != pos;
+                self.pos = pos;
+                return moved;
+            }
+        };
+        match key {
+            Some(key) if down => self.down.insert(key),
+            Some(key) => self.down.remove(&key),
+            None => false,
+        }
+    }
+}
+
+/// The state of a replay.
+#[derive(Debug)]
+pub(crate) struct Player {
+    frame: u64,
+    remaining: VecDeque<RecordedInput>,
+    pub(crate) keys: VecDeque<(KeyMsg, KeyFlags)>,
+    pub(crate) mice: VecDeque<MouseMsg>,
+    pub(crate) held: Held,
+}
+
+impl Player {
+    /// Deliver the messages of the current frame.
+    fn deliver(&mut self) {
+        while let Some(input) = self.remaining.front() {
+            if input.frame > self.frame {
+                break;
+            }
+            self.held.apply(&input.msg);
+            match input.msg {
+                RecordedMsg::Key(msg, flags) => self.keys.push_back((msg, flags)),
+                RecordedMsg::Mouse(msg) => self.mice.push_back(msg),
+                RecordedMsg::KeyState(..) | RecordedMsg::MousePos(_) => {}
+            }
+            self.remaining.pop_front();
+        }
+    }
+}
+
+enum Session {
+    Record {
+        frame: u64,
+        held: Held,
+        recording: Recording,
+    },
+    Replay(Player),
+}
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+}
+
+/// Access the player if a replay is running on this thread.
+pub(crate) fn replaying<R>(f: impl FnOnce(&mut Player) -> R) -> Option<R> {
+    SESSION.with_borrow_mut(|session| match session {
+        Some(Session::Replay(player)) => Some(f(player)),
+        _ => None,
+    })
+}
+
+/// Add a message to the recording if one is running on this thread.
+///
+/// # Note
+/// `RecordedMsg::KeyState` and `RecordedMsg::MousePos` are only added if they
+/// differ from what the recorded messages imply.
+pub(crate) fn record(msg: RecordedMsg) {
+    SESSION.with_borrow_mut(|session| {
+        if let Some(Session::Record {
+            frame,
+            held,
+            recording,
+        }) = session
+        {
+            let changed = held.apply(&msg);
+            if changed || matches!(msg, RecordedMsg::Key(..) | RecordedMsg::Mouse(_)) {
+                recording.inputs.push(RecordedInput { frame: *frame, msg });
+            }
+        }
+    })
+}
+
+/// Advance the frame of the recording or replay running on this thread.
+pub(crate) fn next_frame() {
+    SESSION.with_borrow_mut(|session| match session {
+        Some(Session::Record { frame, .. }) => *frame += 1,
+        Some(Session::Replay(player)) => {
+            player.frame += 1;
+            player.deliver();
+        }
+        None => {}
+    })
+}
+
+impl Window {
+    /// Start recording the key and mouse messages.
+    ///
+    /// # Note
+    /// The messages returned by `getmsg` and `getmouse` are recorded with the
+    /// current frame, see `next_frame`. So are the results of `keystate` and
+    /// `mousepos` that the messages do not explain, so polling apps replay too.
+    /// A running recording or replay is stopped. The session belongs to the
+    /// current thread.
+    pub fn start_recording(&self) {
+        SESSION.set(Some(Session::Record {
+            frame: 0,
+            held: Held::new(),
+            recording: Recording::default(),
+        }));
+    }
+
+    /// Stop recording.
+    ///
+    /// # Returns
+    /// * `Some(Recording)` if a recording was running, `None` otherwise.
+    pub fn stop_recording(&self) -> Option<Recording> {
+        SESSION.with_borrow_mut(|session| match session.take() {
+            Some(Session::Record { recording, .. }) => Some(recording),
+            other => {
+                *session = other;
+                None
+            }
+        })
+    }
+
+    /// Start replaying a recording.
+    ///
+    /// # Parameters
+    /// * `recording` - The recording to replay.
+    ///
+    /// # Note
+    /// While replaying, `getmsg`, `getmouse`, `mousepos` and `keystate` only report
+    /// the recorded messages and do not need a window, also in a headless environment.
+    /// The messages of a frame become available when `next_frame` reaches it,
+    /// `keystate` and `mousepos` follow the available messages. A running
+    /// recording or replay is stopped. The session belongs to the current thread.
+    pub fn start_replay(&self, recording: Recording) {
+        let mut inputs = recording.inputs;
+        inputs.sort_by_key(|input| input.frame);
+        let mut player = Player {
+            frame: 0,
+            remaining: inputs.into(),
+            keys: VecDeque::new(),
+            mice: VecDeque::new(),
+            held: Held::new(),
+        };
+        player.deliver();
+        SESSION.set(Some(Session::Replay(player)));
+    }
+
+    /// Stop replaying.
+    pub fn stop_replay(&self) {
+        SESSION.with_borrow_mut(|session| {
+            if let Some(Session::Replay(_)) = session {
+                *session = None;
+            }
+        })
+    }
+
+    /// Checks if a replay is running on this thread.
+    pub fn is_replaying(&self) -> bool {
+        replaying(|_| ()).is_some()
+    }
+
+    /// Checks if all messages of the replay have been read.
+    ///
+    /// # Returns
+    /// * `true` if no replay is running or all messages were returned, `false` otherwise.
+    pub fn replay_finished(&self) -> bool {
+        replaying(|player| {
+            player.remaining.is_empty() && player.keys.is_empty() && player.mice.is_empty()
+        })
+        .unwrap_or(true)
+    }
+
+    /// Advance the frame of a recording or replay.
+    ///
+    /// # Note
+    /// `XEGE::run`, `delay_ms`, `delay_fps` and `delay_jfps` call this once per
+    /// frame. Loops that use none of them must call it themselves, otherwise all
+    /// messages are recorded in frame 0 and a replay never gets past it.
+    /// Without a recording or replay this does nothing.
+    pub fn next_frame(&self) {
+        next_frame();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(frame: u64, msg: RecordedMsg) -> RecordedInput {
+        RecordedInput { frame, msg }
+    }
+
+    fn mouse(kind: MouseEventKind, button: Option<MouseButton>, x: i32, y: i32) -> RecordedMsg {
+        RecordedMsg::Mouse(MouseMsg::new(
+            kind,
+            button,
+            Point { x, y },
+            0,
+            KeyFlags::none(),
+        ))
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let recording = Recording {
+            inputs: vec![
+                input(
+                    0,
+                    RecordedMsg::Key(KeyMsg::Down(Key::A), KeyFlags::Shift | KeyFlags::First),
+                ),
+                input(0, RecordedMsg::Key(KeyMsg::Char('é'), KeyFlags::none())),
+                input(1, RecordedMsg::Key(KeyMsg::Up(Key::A), KeyFlags::Ctrl)),
+                input(1, mouse(MouseEventKind::Move, None, -3, 7)),
+                input(2, mouse(MouseEventKind::Down, Some(MouseButton::X2), 4, 5)),
+                input(
+                    2,
+                    RecordedMsg::Mouse(MouseMsg {
+                        buttons: MouseButtons::Left | MouseButtons::X2,
+                        ..MouseMsg::new(
+                            MouseEventKind::Move,
+                            None,
+                            Point { x: 5, y: 5 },
+                            0,
+                            KeyFlags::none(),
+                        )
+                    }),
+                ),
+                input(
+                    2,
+                    RecordedMsg::Mouse(MouseMsg::new(
+                        MouseEventKind::Wheel,
+                        None,
+                        Point { x: 1, y: 2 },
+                        -240,
+                        KeyFlags::Shift,
+                    )),
+                ),
+                input(3, RecordedMsg::KeyState(Key::Space, true)),
+                input(3, RecordedMsg::MousePos(Point { x: 10, y: -1 })),
+            ],
+        };
+        let text = recording.to_string();
+        assert!(text.starts_with(HEADER));
+        assert_eq!(text.parse::<Recording>().unwrap(), recording);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            "0 down 65 0".parse::<Recording>(),
+            Err(RecordingError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            format!("{HEADER}\n0 down 65 0\n\n1 keystate 65 2").parse::<Recording>(),
+            Err(RecordingError::Parse { line: 4, .. })
+        ));
+        assert!(matches!(
+            format!("{HEADER}\n0 mousepos 1 2 3").parse::<Recording>(),
+            Err(RecordingError::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn state_is_recorded_when_unexplained() {
+        let window = Window(std::ptr::null_mut(), true);
+        window.start_recording();
+        record(RecordedMsg::Key(KeyMsg::Down(Key::A), KeyFlags::none()));
+        // Explained by the message.
+        record(RecordedMsg::KeyState(Key::A, true));
+        record(RecordedMsg::MousePos(Point { x: 0, y: 0 }));
+        window.next_frame();
+        record(RecordedMsg::KeyState(Key::B, true));
+        record(RecordedMsg::MousePos(Point { x: 2, y: 3 }));
+        record(RecordedMsg::MousePos(Point { x: 2, y: 3 }));
+        let recording = window.stop_recording().unwrap();
+        assert_eq!(
+            recording.inputs,
+            [
+                input(0, RecordedMsg::Key(KeyMsg::Down(Key::A), KeyFlags::none())),
+                input(1, RecordedMsg::KeyState(Key::B, true)),
+                input(1, RecordedMsg::MousePos(Point { x: 2, y: 3 })),
+            ]
+        );
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn headless_replay() {
+        use crate::{Event, headless};
+
+        let mut xege = headless(4, 4);
+        xege.window.start_replay(Recording {
+            inputs: vec![
+                input(0, RecordedMsg::Key(KeyMsg::Down(Key::A), KeyFlags::First)),
+                input(
+                    1,
+                    mouse(MouseEventKind::Down, Some(MouseButton::Left), 2, 3),
+                ),
+                input(1, RecordedMsg::KeyState(Key::B, true)),
+                input(2, RecordedMsg::Key(KeyMsg::Up(Key::A), KeyFlags::none())),
+                input(2, RecordedMsg::MousePos(Point { x: 1, y: 1 })),
+            ],
+        });
+        assert!(xege.window.is_replaying());
+
+        assert_eq!(
+            xege.poll_event(),
+            Some(Event::KeyDown {
+                key: Key::A,
+                flags: KeyFlags::First
+            })
+        );
+        assert_eq!(xege.poll_event(), None);
+        assert!(xege.window.keystate(Key::A));
+        assert!(!xege.window.keystate(Key::B));
+
+        xege.window.next_frame();
+        assert_eq!(
+            xege.poll_event(),
+            Some(Event::MouseDown {
+                button: MouseButton::Left,
+                pos: Point { x: 2, y: 3 }
+            })
+        );
+        assert_eq!(xege.poll_event(), None);
+        assert!(xege.window.keystate(Key::LeftButton));
+        assert!(xege.window.keystate(Key::B));
+        assert_eq!(xege.window.mousepos(), Point { x: 2, y: 3 });
+        assert!(!xege.window.replay_finished());
+
+        xege.window.next_frame();
+        assert_eq!(
+            xege.poll_event(),
+            Some(Event::KeyUp {
+                key: Key::A,
+                flags: KeyFlags::none()
+            })
+        );
+        assert_eq!(xege.poll_event(), None);
+        assert!(!xege.window.keystate(Key::A));
+        assert_eq!(xege.window.mousepos(), Point { x: 1, y: 1 });
+        assert!(xege.window.replay_finished());
+
+        xege.window.stop_replay();
+        assert!(!xege.window.is_replaying());
+    }
+}
@@ -0,0 +1,465 @@
+#[cfg(feature = "ege")]
+use crate::Image;
+use crate::brush::blend;
+use crate::convolve::par_rows;
+use crate::image::{premultiply, unpremultiply};
+use crate::{ARGB, AlphaType, ImageView, ImageViewMut};
+
+/// The interpolation used when an image is scaled or rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Filter {
+    /// The closest pixel, blocky but fast.
+    Nearest,
+    /// Linear interpolation of the 2x2 closest pixels.
+    #[default]
+    Bilinear,
+    /// Catmull-Rom interpolation of the 4x4 closest pixels, sharper than `Bilinear`.
+    Bicubic,
+    /// Lanczos interpolation of the 6x6 closest pixels, the sharpest and slowest.
+    Lanczos3,
+    /// The average of the covered pixels, weighted by their covered area.
+    ///
+    /// Best for shrinking by large factors. Enlarged pixels stay square.
+    Area,
+}
+
+impl From<bool> for Filter {
+    /// Convert the `smooth` flag of EGE: `Bilinear` if set, `Nearest` otherwise.
+    fn from(smooth: bool) -> Self {
+        if smooth {
+            Self::Bilinear
+        } else {
+            Self::Nearest
+        }
+    }
+}
+
+impl Filter {
+    /// The radius of the filter, in source pixels when not shrinking.
+    pub fn support(&self) -> f32 {
+        match self {
+            Self::Nearest | Self::Area => 0.5,
+            Self::Bilinear => 1.0,
+            Self::Bicubic => 2.0,
+            Self::Lanczos3 => 3.0,
+        }
+    }
+
+    /// The weight of a pixel at the distance `x` from the sample position.
+    pub fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Self::Nearest | Self::Area => (x <= 0.5) as u8 as f32,
+            Self::Bilinear => (1.0 - x).max(0.0),
+            Self::Bicubic => match x {
+                x if x < 1.0 => (1.5 * x - 2.5) * x * x + 1.0,
+                x if x < 2.0 => ((-0.5 * x + 2.5) * x - 4.0) * x + 2.0,
+                _ => 0.0,
+            },
+            Self::Lanczos3 => match x {
+                0.0 => 1.0,
+                x if x < 3.0 => {
+                    let px = std::f32::consts::PI * x;
+                    3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+                }
+                _ => 0.0,
+            },
+        }
+    }
+
+    /// The `smooth` flag of EGE, or `None` if EGE has no equivalent.
+    #[cfg(feature = "ege")]
+    pub(crate) fn smooth(&self) -> Option<bool> {
+        match self {
+            Self::Nearest => Some(false),
+            Self::Bilinear => Some(true),
+            _ => None,
+        }
+    }
+
+    /// The weights of the source pixels around a sample position.
+    ///
+    /// # Parameters
+    /// - `center`: The sample position, pixel `i` covers `i..i + 1`.
+    /// - `scale`: The number of source pixels per destination pixel.
+    /// - `weights`: Replaced with the unnormalized weights, reused to avoid allocating.
+    ///
+    /// # Returns
+    /// The index of the first pixel.
+    fn taps(&self, center: f32, scale: f32, weights: &mut Vec<f32>) -> i32 {
+        weights.clear();
+        match self {
+            Self::Nearest => {
+                weights.push(1.0);
+                center.floor() as i32
+            }
+            Self::Area => {
+                let (lo, hi) = (center - scale / 2.0, center + scale / 2.0);
+                let first = lo.floor() as i32;
+                weights.extend(
+                    (first..hi.ceil() as i32)
+                        .map(|i| (hi.min(i as f32 + 1.0) - lo.max(i as f32)).max(0.0)),
+                );
+                first
+            }
+            _ => {
+                let scale = scale.max(1.0);
+                let radius = self.support() * scale;
+                let first = (center - radius).floor() as i32;
+                weights.extend(
+                    (first..=(center + radius).ceil() as i32)
+                        .map(|i| self.weight((i as f32 + 0.5 - center) / scale)),
+                );
+                first
+            }
+        }
+    }
+}
+
+type Pixel = [f32; 4];
+
+/// Convert an ARGB color to premultiplied `[r, g, b, a]` in `0.0..=1.0`.
+fn to_pixel(color: ARGB, alpha: Option<AlphaType>) -> Pixel {
+    let color = match alpha {
+        None => color | 0xFF000000,
+        Some(AlphaType::Straight) => premultiply(color),
+        Some(AlphaType::PreMultiplied) => color,
+    };
+    let [b, g, r, a] = color.to_le_bytes().map(|c| c as f32 / 255.0);
+    [r, g, b, a]
+}
+
+/// Convert a premultiplied pixel to a straight alpha ARGB color.
+fn from_pixel(pixel: Pixel) -> ARGB {
+    let a = pixel[3].clamp(0.0, 1.0);
+    let [r, g, b, a] =
+        [pixel[0], pixel[1], pixel[2], a].map(|c| (c.clamp(0.0, a) * 255.0).round() as u8);
+    unpremultiply(u32::from_le_bytes([b, g, r, a]))
+}
+
+/// The normalized weights of each destination pixel along one axis, clamped at the edges.
+fn axis_taps(filter: Filter, src_len: u32, dst_len: u32) -> Vec<Vec<(usize, f32)>> {
+    let scale = src_len as f32 / dst_len as f32;
+    let mut weights = vec![];
+    (0..dst_len)
+        .map(|i| {
+            let first = filter.taps((i as f32 + 0.5) * scale, scale, &mut weights);
+            let sum = weights.iter().sum::<f32>();
+            let mut taps = Vec::<(usize, f32)>::with_capacity(weights.len());
+            for (k, &weight) in weights.iter().enumerate() {
+                let index = (first + k as i32).clamp(0, src_len as i32 - 1) as usize;
+                match taps.last_mut() {
+                    Some(last) if last.0 == index => last.1 += weight / sum,
+                    _ => taps.push((index, weight / sum)),
+                }
+            }
+            taps
+        })
+        .collect()
+}
+
+/// Scale pixels into a view of another size.
+///
+/// # Parameters
+/// - `src`: The source pixels, with straight alpha.
+/// - `dst`: The destination pixels, which are replaced.
+/// - `filter`: The interpolation.
+///
+/// # Note
+/// The pixels are interpolated with premultiplied alpha, so transparent pixels do not
+/// bleed their color. Shrinking widens the filter to cover all source pixels.
+pub fn resample(src: &ImageView, dst: &mut ImageViewMut, filter: Filter) {
+    let (sw, sh) = (src.width(), src.height());
+    let (dw, dh) = (dst.width() as usize, dst.height() as usize);
+    if sw == 0 || sh == 0 || dw == 0 || dh == 0 {
+        return;
+    }
+    let input = src
+        .to_vec()
+        .into_iter()
+        .map(|c| to_pixel(c, Some(AlphaType::Straight)))
+        .collect::<Vec<_>>();
+    let (columns, rows) = (
+        axis_taps(filter, sw, dw as u32),
+        axis_taps(filter, sh, dh as u32),
+    );
+    let sum = |taps: &[(usize, f32)], pixel: &dyn Fn(usize) -> Pixel| {
+        let mut out = [0.0; 4];
+        for &(i, weight) in taps {
+            let p = pixel(i);
+            (0..4).for_each(|c| out[c] += weight * p[c]);
+        }
+        out
+    };
+    let mut temp = vec![[0.0; 4]; dw * sh as usize];
+    par_rows(&mut temp, dw, 0, |y, row| {
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = sum(&columns[x], &|i| input[y * sw as usize + i]);
+        }
+    });
+    let mut output = vec![0; dw * dh];
+    par_rows(&mut output, dw, 0, |y, row| {
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = from_pixel(sum(&rows[y], &|i| temp[i * dw + x]));
+        }
+    });
+    for (dst, src) in dst.rows_mut().zip(output.chunks(dw)) {
+        dst.copy_from_slice(src);
+    }
+}
+
+/// How `draw_warped` draws the source.
+pub(crate) struct Warp {
+    /// Maps source to destination coordinates, see `warp`.
+    pub transform: [[f32; 3]; 2],
+    pub filter: Filter,
+    /// Multiplied with the alpha of the source.
+    pub opacity: f32,
+    /// The alpha type of the source, `None` ignores its alpha channel.
+    pub alpha: Option<AlphaType>,
+    /// Clamp the filter at the edges of the source instead of fading them out.
+    pub clamp: bool,
+}
+
+/// Draw transformed pixels over a view.
+pub(crate) fn draw_warped(src: &ImageView, dst: &mut ImageViewMut, warp: &Warp) {
+    let Warp {
+        transform,
+        filter,
+        opacity,
+        alpha,
+        clamp,
+    } = *warp;
+    let [[a, b, c], [d, e, f]] = transform;
+    let det = a * e - b * d;
+    let (sw, sh) = (src.width() as i32, src.height() as i32);
+    if det.abs() < f32::EPSILON || sw == 0 || sh == 0 {
+        return;
+    }
+    // The inverse maps destination to source coordinates.
+    let (ia, ib, id, ie) = (e / det, -b / det, -d / det, a / det);
+    let (ic, if_) = (-(ia * c + ib * f), -(id * c + ie * f));
+    let (scale_x, scale_y) = (ia.hypot(ib), id.hypot(ie));
+
+    // Without clamping, the filter fades the edges out beyond the source.
+    let r = if clamp {
+        0.0
+    } else {
+        filter.support() * scale_x.max(scale_y).max(1.0)
+    };
+    let (w, h) = (sw as f32 + r, sh as f32 + r);
+    let corners =
+        [(-r, -r), (w, -r), (-r, h), (w, h)].map(|(u, v)| (a * u + b * v + c, d * u + e * v + f));
+    let bound = |values: [f32; 4], len: u32| {
+        let lo = values.iter().copied().fold(f32::INFINITY, f32::min).floor() - 1.0;
+        let hi = values
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            + 1.0;
+        (lo.max(0.0) as u32, hi.clamp(0.0, len as f32) as u32)
+    };
+    let (x0, x1) = bound(corners.map(|p| p.0), dst.width());
+    let (y0, y1) = bound(corners.map(|p| p.1), dst.height());
+
+    let (mut wx, mut wy) = (vec![], vec![]);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let (u, v) = (ia * px + ib * py + ic, id * px + ie * py + if_);
+            if clamp && !((0.0..sw as f32).contains(&u) && (0.0..sh as f32).contains(&v)) {
+                continue;
+            }
+            let fx = filter.taps(u, scale_x, &mut wx);
+            let fy = filter.taps(v, scale_y, &mut wy);
+            let total = wx.iter().sum::<f32>() * wy.iter().sum::<f32>();
+            if total <= 0.0 {
+                continue;
+            }
+            let mut sum = [0.0; 4];
+            for (j, wy) in wy.iter().enumerate() {
+                let sy = fy + j as i32;
+                let sy = if clamp { sy.clamp(0, sh - 1) } else { sy };
+                if !(0..sh).contains(&sy) {
+                    continue;
+                }
+                for (i, wx) in wx.iter().enumerate() {
+                    let sx = fx + i as i32;
+                    let sx = if clamp { sx.clamp(0, sw - 1) } else { sx };
+                    if !(0..sw).contains(&sx) {
+                        continue;
+                    }
+                    let p = to_pixel(src.get(sx as u32, sy as u32).unwrap_or(0), alpha);
+                    let weight = wx * wy / total;
+                    (0..4).for_each(|c| sum[c] += weight * p[c]);
+                }
+            }
+            if sum[3] <= 0.0 {
+                continue;
+            }
+            let alpha = sum[3].clamp(0.0, 1.0);
+            let sum = sum.map(|c| c.clamp(0.0, alpha) * opacity);
+            if let Some(pixel) = dst.get_mut(x, y) {
+                *pixel = blend(*pixel, sum);
+            }
+        }
+    }
+}
+
+/// Draw pixels transformed by an affine matrix over a view.
+///
+/// # Parameters
+/// - `src`: The source pixels, with straight alpha.
+/// - `dst`: The destination pixels.
+/// - `transform`: Maps a point `(u, v)` of the source to `(a * u + b * v + c, d * u + e * v + f)`
+///   of the destination, given as `[[a, b, c], [d, e, f]]`.
+/// - `filter`: The interpolation.
+///
+/// # Note
+/// The source is blended over the destination. Its edges are antialiased by the filter.
+pub fn warp(src: &ImageView, dst: &mut ImageViewMut, transform: [[f32; 3]; 2], filter: Filter) {
+    let warp = Warp {
+        transform,
+        filter,
+        opacity: 1.0,
+        alpha: Some(AlphaType::Straight),
+        clamp: false,
+    };
+    draw_warped(src, dst, &warp);
+}
+
+#[cfg(feature = "ege")]
+impl Image {
+    /// Create a scaled copy of the image.
+    ///
+    /// # Parameters
+    /// - `width`: The width of the copy.
+    /// - `height`: The height of the copy.
+    /// - `filter`: The interpolation.
+    ///
+    /// # Note
+    /// See `resample`.
+    pub fn resized(&self, width: u32, height: u32, filter: Filter) -> Image {
+        let mut image = Image::new(width, height);
+        resample(&self.as_view(), &mut image.as_view_mut(), filter);
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILTERS: [Filter; 5] = [
+        Filter::Nearest,
+        Filter::Bilinear,
+        Filter::Bicubic,
+        Filter::Lanczos3,
+        Filter::Area,
+    ];
+
+    /// Opaque pseudo-random pixels, with some fully transparent ones.
+    fn noise(len: usize) -> Vec<ARGB> {
+        let mut state = 0x9E3779B9u32;
+        (0..len)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                if i % 7 == 3 { 0 } else { state | 0xFF000000 }
+            })
+            .collect()
+    }
+
+    fn resampled(src: &[ARGB], width: u32, dw: u32, dh: u32, filter: Filter) -> Vec<ARGB> {
+        let height = src.len() as u32 / width;
+        let mut dst = vec![0; (dw * dh) as usize];
+        resample(
+            &ImageView::new(src, width as usize, width, height),
+            &mut ImageViewMut::new(&mut dst, dw as usize, dw, dh),
+            filter,
+        );
+        dst
+    }
+
+    #[test]
+    fn weights() {
+        for filter in FILTERS {
+            assert_eq!(filter.weight(0.0), 1.0, "{filter:?}");
+            assert_eq!(filter.weight(filter.support() + 0.01), 0.0, "{filter:?}");
+            assert_eq!(filter.weight(-0.3), filter.weight(0.3), "{filter:?}");
+        }
+        // The interpolating filters are zero at the other pixels.
+        for filter in [Filter::Bilinear, Filter::Bicubic, Filter::Lanczos3] {
+            for x in [1.0, 2.0] {
+                assert!(filter.weight(x).abs() < 1e-6, "{filter:?} at {x}");
+            }
+        }
+        assert_eq!(Filter::from(true), Filter::Bilinear);
+        assert_eq!(Filter::from(false), Filter::Nearest);
+    }
+
+    #[test]
+    fn identity_at_scale_one() {
+        let src = noise(13 * 9);
+        for filter in FILTERS {
+            assert_eq!(resampled(&src, 13, 13, 9, filter), src, "{filter:?}");
+
+            let mut dst = vec![0; src.len()];
+            warp(
+                &ImageView::new(&src, 13, 13, 9),
+                &mut ImageViewMut::new(&mut dst, 13, 13, 9),
+                [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                filter,
+            );
+            assert_eq!(dst, src, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn integer_translation() {
+        let src = noise(6 * 4);
+        for filter in FILTERS {
+            let mut dst = vec![0; 8 * 6];
+            warp(
+                &ImageView::new(&src, 6, 6, 4),
+                &mut ImageViewMut::new(&mut dst, 8, 8, 6),
+                [[1.0, 0.0, 2.0], [0.0, 1.0, 1.0]],
+                filter,
+            );
+            for y in 0..4 {
+                assert_eq!(dst[(y + 1) * 8 + 2..][..6], src[y * 6..][..6], "{filter:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn shrinking() {
+        // Area averages the covered pixels.
+        #[rustfmt::skip]
+        let src = [
+            0xFF000000, 0xFF0000FF, 0xFF00FF00, 0xFF00FF00,
+            0xFF00FF00, 0xFFFF00FF, 0xFF00FF00, 0xFF00FF00,
+        ];
+        assert_eq!(
+            resampled(&src, 4, 2, 1, Filter::Area),
+            [0xFF404080, 0xFF00FF00]
+        );
+        // A flat image stays flat with every filter.
+        let flat = [0xFF336699; 30];
+        for filter in FILTERS {
+            for (dw, dh) in [(2, 3), (5, 5), (11, 7)] {
+                let result = resampled(&flat, 6, dw, dh, filter);
+                assert!(result.iter().all(|&c| c == 0xFF336699), "{filter:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn transparent_pixels_do_not_bleed() {
+        let src = [0xFFFF0000, 0x0000FF00];
+        assert_eq!(resampled(&src, 2, 1, 1, Filter::Area), [0x80FF0000]);
+        assert_eq!(resampled(&src, 2, 1, 1, Filter::Bilinear), [0x80FF0000]);
+    }
+}
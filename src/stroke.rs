@@ -0,0 +1,638 @@
+use std::f32::consts::PI;
+
+use crate::{LineCap, LineJoin, PathData, PathFillMode, PathSegment, Point};
+
+/// A dash pattern of a stroke.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DashPattern {
+    /// The lengths of the dashes and the gaps between them, alternately.
+    ///
+    /// A pattern with an odd number of lengths is repeated twice, like in SVG.
+    pub dashes: Vec<f32>,
+    /// The distance into the pattern at which the stroke starts.
+    pub offset: f32,
+}
+
+impl DashPattern {
+    /// Create a dash pattern.
+    ///
+    /// # Parameters
+    /// - `dashes`: The lengths of the dashes and the gaps between them, alternately.
+    /// - `offset`: The distance into the pattern at which the stroke starts.
+    ///
+    /// # Note
+    /// Increasing `offset` over time moves the dashes backwards along the stroke,
+    /// which is how "marching ants" are animated.
+    pub fn new(dashes: &[f32], offset: f32) -> Self {
+        Self {
+            dashes: dashes.to_vec(),
+            offset,
+        }
+    }
+
+    /// Split polylines into dashes.
+    ///
+    /// # Returns
+    /// The open polylines of the dashes. The pattern restarts at every polyline.
+    /// An empty or invalid pattern (a negative length, or only zeros) returns the
+    /// polylines unchanged, with closed ones ending at their start point.
+    pub(crate) fn apply(&self, polylines: &[(Vec<Point<f32>>, bool)]) -> Vec<Vec<Point<f32>>> {
+        let mut dashes = self.dashes.clone();
+        if dashes.len() % 2 == 1 {
+            dashes.extend_from_slice(&self.dashes);
+        }
+        let total: f32 = dashes.iter().sum();
+        let valid = total > 0.0 && dashes.iter().all(|d| *d >= 0.0 && d.is_finite());
+        let mut result = vec![];
+        for (points, closed) in polylines {
+            let mut points = points.clone();
+            if *closed && !points.is_empty() {
+                points.push(points[0]);
+            }
+            if !valid {
+                result.push(points);
+                continue;
+            }
+            // Find the position in the pattern at the start.
+            let mut index = 0;
+            let mut left = self.offset.rem_euclid(total);
+            while left > dashes[index] || (left == dashes[index] && left > 0.0) {
+                left -= dashes[index];
+                index = (index + 1) % dashes.len();
+            }
+            left = dashes[index] - left;
+            let mut current = vec![];
+            if index % 2 == 0 {
+                current.push(points[0]);
+            }
+            for pair in points.windows(2) {
+                let (mut a, b) = (pair[0], pair[1]);
+                let mut length = (b.x - a.x).hypot(b.y - a.y);
+                while length > left {
+                    let t = left / length;
+                    let p = Point {
+                        x: a.x + (b.x - a.x) * t,
+                        y: a.y + (b.y - a.y) * t,
+                    };
+                    if index % 2 == 0 {
+                        current.push(p);
+                        result.push(std::mem::take(&mut current));
+                    } else {
+                        current.push(p);
+                    }
+                    length -= left;
+                    a = p;
+                    index = (index + 1) % dashes.len();
+                    left = dashes[index];
+                }
+                left -= length;
+                if index % 2 == 0 {
+                    current.push(b);
+                }
+            }
+            if index % 2 == 0 && current.len() >= 2 {
+                result.push(current);
+            }
+        }
+        result
+    }
+}
+
+/// The parameters used to stroke a path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    /// The width of the stroke.
+    pub width: f32,
+    /// The cap at the start of open subpaths.
+    pub start_cap: LineCap,
+    /// The cap at the end of open subpaths.
+    pub end_cap: LineCap,
+    /// The join between segments.
+    pub join: LineJoin,
+    /// The largest ratio of the miter length to the width of the stroke,
+    /// longer miters are drawn as bevels.
+    pub miter_limit: f32,
+    /// The dash pattern, `None` for a solid stroke.
+    pub dash: Option<DashPattern>,
+}
+
+impl Default for StrokeStyle {
+    /// A stroke of width 1 with flat caps and miter joins, like the EGE defaults.
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            start_cap: LineCap::Flat,
+            end_cap: LineCap::Flat,
+            join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: None,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// Create a stroke style with the default caps and joins.
+    ///
+    /// # Parameters
+    /// - `width`: The width of the stroke.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+}
+
+fn sub(a: Point<f32>, b: Point<f32>) -> Point<f32> {
+    Point {
+        x: a.x - b.x,
+        y: a.y - b.y,
+    }
+}
+
+fn offset(p: Point<f32>, d: Point<f32>, scale: f32) -> Point<f32> {
+    Point {
+        x: p.x + d.x * scale,
+        y: p.y + d.y * scale,
+    }
+}
+
+fn cross(a: Point<f32>, b: Point<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// The unit direction from `a` to `b`.
+fn direction(a: Point<f32>, b: Point<f32>) -> Point<f32> {
+    let d = sub(b, a);
+    let len = d.x.hypot(d.y);
+    Point {
+        x: d.x / len,
+        y: d.y / len,
+    }
+}
+
+/// The normal on the left of a direction.
+fn normal(d: Point<f32>) -> Point<f32> {
+    Point { x: -d.y, y: d.x }
+}
+
+/// Get a polygon approximating a circle.
+fn circle(center: Point<f32>, radius: f32, tolerance: f32) -> Vec<Point<f32>> {
+    let step = if radius > tolerance {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        PI / 2.0
+    };
+    let count = ((2.0 * PI / step).ceil() as usize).clamp(8, 1024);
+    (0..count)
+        .map(|i| {
+            let (sin, cos) = (2.0 * PI * i as f32 / count as f32).sin_cos();
+            Point {
+                x: center.x + radius * cos,
+                y: center.y + radius * sin,
+            }
+        })
+        .collect()
+}
+
+/// Get the polygons whose union is the stroke of a polyline.
+///
+/// All polygons have a positive orientation, so they can be filled together
+/// with `PathFillMode::Winding`.
+pub(crate) fn stroke_polyline(
+    points: &[Point<f32>],
+    closed: bool,
+    style: &StrokeStyle,
+    tolerance: f32,
+) -> Vec<Vec<Point<f32>>> {
+    let hw = style.width.abs() / 2.0;
+    let mut points = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let mut rings = vec![];
+    if hw == 0.0 || points.is_empty() {
+        return rings;
+    }
+    if points.len() == 1 {
+        // A dot only has caps.
+        let p = points[0];
+        match style.start_cap {
+            LineCap::Flat => {}
+            LineCap::Round => rings.push(circle(p, hw, tolerance)),
+            LineCap::Square => rings.push(vec![
+                Point {
+                    x: p.x - hw,
+                    y: p.y - hw,
+                },
+                Point {
+                    x: p.x + hw,
+                    y: p.y - hw,
+                },
+                Point {
+                    x: p.x + hw,
+                    y: p.y + hw,
+                },
+                Point {
+                    x: p.x - hw,
+                    y: p.y + hw,
+                },
+            ]),
+        }
+        return rings;
+    }
+
+    let count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    let segment = |i: usize| (points[i], points[(i + 1) % points.len()]);
+    for i in 0..count {
+        let (a, b) = segment(i);
+        let d = direction(a, b);
+        let n = normal(d);
+        let (mut a, mut b) = (a, b);
+        if !closed && i == 0 && style.start_cap == LineCap::Square {
+            a = offset(a, d, -hw);
+        }
+        if !closed && i == count - 1 && style.end_cap == LineCap::Square {
+            b = offset(b, d, hw);
+        }
+        rings.push(vec![
+            offset(a, n, -hw),
+            offset(b, n, -hw),
+            offset(b, n, hw),
+            offset(a, n, hw),
+        ]);
+    }
+
+    // Joins between consecutive segments.
+    let joins = if closed {
+        0..points.len()
+    } else {
+        1..points.len() - 1
+    };
+    for i in joins {
+        let prev = points[(i + points.len() - 1) % points.len()];
+        let v = points[i];
+        let next = points[(i + 1) % points.len()];
+        let (d0, d1) = (direction(prev, v), direction(v, next));
+        let turn = cross(d0, d1);
+        if turn.abs() < 1e-6 && d0.x * d1.x + d0.y * d1.y > 0.0 {
+            continue;
+        }
+        if style.join == LineJoin::Round {
+            rings.push(circle(v, hw, tolerance));
+            continue;
+        }
+        // The outer side is on the right of a left turn.
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let (n0, n1) = (normal(d0), normal(d1));
+        let p0 = offset(v, n0, side * hw);
+        let p1 = offset(v, n1, side * hw);
+        let mid = Point {
+            x: n0.x + n1.x,
+            y: n0.y + n1.y,
+        };
+        let len = mid.x.hypot(mid.y);
+        // `cos` of half of the angle between the normals.
+        let cos = len / 2.0;
+        if style.join == LineJoin::Miter && cos > 1e-6 && 1.0 / cos <= style.miter_limit {
+            let tip = offset(v, mid, side * hw / (cos * len));
+            rings.push(vec![v, p0, tip, p1]);
+        } else {
+            rings.push(vec![v, p0, p1]);
+        }
+    }
+
+    // Round caps of open polylines.
+    if !closed {
+        if style.start_cap == LineCap::Round {
+            rings.push(circle(points[0], hw, tolerance));
+        }
+        if style.end_cap == LineCap::Round {
+            rings.push(circle(points[points.len() - 1], hw, tolerance));
+        }
+    }
+
+    for ring in rings.iter_mut() {
+        let area: f32 = (0..ring.len())
+            .map(|i| cross(ring[i], ring[(i + 1) % ring.len()]))
+            .sum();
+        if area < 0.0 {
+            ring.reverse();
+        }
+    }
+    rings
+}
+
+/// The winding number of closed polylines around a point.
+fn winding(polylines: &[Vec<Point<f32>>], p: Point<f32>) -> i32 {
+    let mut w = 0;
+    for ring in polylines {
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            let side = cross(sub(b, a), sub(p, a));
+            if a.y <= p.y && b.y > p.y && side > 0.0 {
+                w += 1;
+            } else if b.y <= p.y && a.y > p.y && side < 0.0 {
+                w -= 1;
+            }
+        }
+    }
+    w
+}
+
+fn rings_to_path(rings: Vec<Vec<Point<f32>>>) -> PathData {
+    let mut data = PathData::new();
+    for ring in rings.into_iter().filter(|ring| ring.len() >= 3) {
+        data.push(PathSegment::MoveTo(ring[0]));
+        data.extend(ring[1..].iter().map(|&p| PathSegment::LineTo(p)));
+        data.push(PathSegment::Close);
+    }
+    data
+}
+
+impl PathData {
+    /// Flatten the path.
+    ///
+    /// # Parameters
+    /// - `tolerance`: The largest distance between a curve and the lines replacing it.
+    ///
+    /// # Note
+    /// All curves and arcs are replaced by straight lines. Unlike `Path::flatten`,
+    /// the result only depends on the parameters.
+    pub fn flatten(&mut self, tolerance: f32) {
+        let mut data = PathData::new();
+        for (points, closed) in self.polylines(tolerance) {
+            data.push(PathSegment::MoveTo(points[0]));
+            data.extend(points[1..].iter().map(|&p| PathSegment::LineTo(p)));
+            if closed {
+                data.push(PathSegment::Close);
+            }
+        }
+        *self = data;
+    }
+
+    /// Get the polygons covering the stroke of the path, before they are merged.
+    pub(crate) fn stroke_rings(&self, style: &StrokeStyle, tolerance: f32) -> Vec<Vec<Point<f32>>> {
+        let polylines = self.polylines(tolerance);
+        match &style.dash {
+            Some(dash) => dash
+                .apply(&polylines)
+                .iter()
+                .flat_map(|points| stroke_polyline(points, false, style, tolerance))
+                .collect(),
+            None => polylines
+                .iter()
+                .flat_map(|(points, closed)| stroke_polyline(points, *closed, style, tolerance))
+                .collect(),
+        }
+    }
+
+    /// Widen the path.
+    ///
+    /// # Parameters
+    /// - `style`: The width, caps, join, miter limit and dashes of the stroke.
+    /// - `tolerance`: The largest distance between a curve and the lines replacing it.
+    ///
+    /// # Note
+    /// The path is replaced by overlapping polygons covering the stroke. They all
+    /// have a positive orientation, so fill the result with `PathFillMode::Winding`;
+    /// other fill modes leave holes where the polygons overlap. Use `PathData::boolean`
+    /// to merge them into a single outline. Unlike `Path::widen`, the result only
+    /// depends on the parameters.
+    pub fn widen(&mut self, style: &StrokeStyle, tolerance: f32) {
+        *self = rings_to_path(self.stroke_rings(style, tolerance));
+    }
+
+    /// Is the point in the path?
+    ///
+    /// # Parameters
+    /// - `x`: The x-coordinate of the point.
+    /// - `y`: The y-coordinate of the point.
+    /// - `mode`: The fill mode. `PathFillMode::Default` is the same as `PathFillMode::Alternate`.
+    /// - `tolerance`: The largest distance between a curve and the lines replacing it.
+    ///
+    /// # Note
+    /// All subpaths are treated as closed.
+    pub fn inpath(&self, x: f32, y: f32, mode: PathFillMode, tolerance: f32) -> bool {
+        let polylines = self
+            .polylines(tolerance)
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect::<Vec<_>>();
+        let w = winding(&polylines, Point { x, y });
+        match mode {
+            PathFillMode::Winding => w != 0,
+            PathFillMode::Default | PathFillMode::Alternate => w % 2 != 0,
+        }
+    }
+
+    /// Is the point on the stroke of the path?
+    ///
+    /// # Parameters
+    /// - `x`: The x-coordinate of the point.
+    /// - `y`: The y-coordinate of the point.
+    /// - `style`: The width, caps, join, miter limit and dashes of the stroke.
+    /// - `tolerance`: The largest distance between a curve and the lines replacing it.
+    pub fn instroke(&self, x: f32, y: f32, style: &StrokeStyle, tolerance: f32) -> bool {
+        winding(&self.stroke_rings(style, tolerance), Point { x, y }) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f32, y: f32) -> Point<f32> {
+        Point { x, y }
+    }
+
+    fn polyline(points: &[(f32, f32)]) -> PathData {
+        let mut path = PathData::new();
+        path.move_to(points[0].0, points[0].1);
+        for &(x, y) in &points[1..] {
+            path.line_to(x, y);
+        }
+        path
+    }
+
+    fn dashes(
+        pattern: &[f32],
+        offset: f32,
+        points: &[(f32, f32)],
+        closed: bool,
+    ) -> Vec<Vec<Point<f32>>> {
+        let points = points.iter().map(|&(x, y)| p(x, y)).collect();
+        DashPattern::new(pattern, offset).apply(&[(points, closed)])
+    }
+
+    #[test]
+    fn dash_pattern() {
+        let line = [(0.0, 0.0), (10.0, 0.0)];
+        assert_eq!(
+            dashes(&[2.0, 3.0], 0.0, &line, false),
+            [
+                vec![p(0.0, 0.0), p(2.0, 0.0)],
+                vec![p(5.0, 0.0), p(7.0, 0.0)]
+            ]
+        );
+        // An odd pattern is repeated twice.
+        assert_eq!(
+            dashes(&[2.0], 0.0, &line, false),
+            [
+                vec![p(0.0, 0.0), p(2.0, 0.0)],
+                vec![p(4.0, 0.0), p(6.0, 0.0)],
+                vec![p(8.0, 0.0), p(10.0, 0.0)],
+            ]
+        );
+        assert_eq!(
+            dashes(&[2.0, 3.0], 1.0, &line, false),
+            [
+                vec![p(0.0, 0.0), p(1.0, 0.0)],
+                vec![p(4.0, 0.0), p(6.0, 0.0)],
+                vec![p(9.0, 0.0), p(10.0, 0.0)],
+            ]
+        );
+        // A negative offset starts in the previous period.
+        assert_eq!(
+            dashes(&[2.0, 3.0], -4.0, &line, false),
+            dashes(&[2.0, 3.0], 1.0, &line, false)
+        );
+    }
+
+    #[test]
+    fn dash_pattern_follows_corners() {
+        let corner = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)];
+        assert_eq!(
+            dashes(&[3.0, 10.0], 0.0, &corner, false),
+            [vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 1.0)]]
+        );
+        // A closed polyline is dashed along its closing segment too.
+        let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        assert_eq!(
+            dashes(&[1.0, 6.0], 0.0, &square, true),
+            [
+                vec![p(0.0, 0.0), p(1.0, 0.0)],
+                vec![p(0.0, 1.0), p(0.0, 0.0)]
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_dash_pattern() {
+        let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)];
+        let solid = vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0), p(0.0, 0.0)];
+        for pattern in [&[][..], &[0.0, 0.0], &[1.0, -1.0], &[f32::NAN, 1.0]] {
+            assert_eq!(
+                dashes(pattern, 0.0, &square, true),
+                std::slice::from_ref(&solid)
+            );
+        }
+    }
+
+    #[test]
+    fn caps() {
+        let line = polyline(&[(0.0, 0.0), (10.0, 0.0)]);
+        let mut style = StrokeStyle::new(2.0);
+        assert!(line.instroke(5.0, 0.9, &style, 0.01));
+        assert!(!line.instroke(5.0, 1.1, &style, 0.01));
+        assert!(!line.instroke(-0.5, 0.0, &style, 0.01));
+        assert!(!line.instroke(10.5, 0.0, &style, 0.01));
+
+        style.start_cap = LineCap::Square;
+        assert!(line.instroke(-0.9, 0.9, &style, 0.01));
+        assert!(!line.instroke(10.5, 0.0, &style, 0.01));
+
+        style.end_cap = LineCap::Round;
+        assert!(line.instroke(10.9, 0.0, &style, 0.01));
+        assert!(!line.instroke(10.8, 0.8, &style, 0.01));
+    }
+
+    #[test]
+    fn joins() {
+        let corner = polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let mut style = StrokeStyle::new(2.0);
+        // The outer corner of the right angle.
+        assert!(corner.instroke(10.9, -0.9, &style, 0.01));
+        style.join = LineJoin::Bevel;
+        assert!(!corner.instroke(10.9, -0.9, &style, 0.01));
+        assert!(corner.instroke(10.4, -0.4, &style, 0.01));
+        style.join = LineJoin::Round;
+        assert!(!corner.instroke(10.9, -0.9, &style, 0.01));
+        assert!(corner.instroke(10.6, -0.6, &style, 0.01));
+
+        // The miter of a right angle is sqrt(2) times the width.
+        style.join = LineJoin::Miter;
+        style.miter_limit = 1.4;
+        assert!(!corner.instroke(10.9, -0.9, &style, 0.01));
+    }
+
+    #[test]
+    fn dashed_stroke() {
+        let line = polyline(&[(0.0, 0.0), (10.0, 0.0)]);
+        let style = StrokeStyle {
+            dash: Some(DashPattern::new(&[2.0, 3.0], 0.0)),
+            ..StrokeStyle::new(2.0)
+        };
+        assert!(line.instroke(1.0, 0.5, &style, 0.01));
+        assert!(!line.instroke(3.0, 0.0, &style, 0.01));
+        assert!(line.instroke(6.0, -0.5, &style, 0.01));
+        assert!(!line.instroke(9.0, 0.0, &style, 0.01));
+    }
+
+    #[test]
+    fn widen() {
+        let mut path = polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        path.close();
+        let style = StrokeStyle::new(2.0);
+        let mut outline = path.clone();
+        outline.widen(&style, 0.01);
+        assert!(outline.iter().all(|segment| !matches!(
+            segment,
+            PathSegment::QuadTo(..) | PathSegment::CubicTo(..) | PathSegment::ArcTo { .. }
+        )));
+        for y in [-1.5, -0.5, 0.5, 5.0, 9.5, 10.5, 11.5] {
+            for x in [-1.5, -0.5, 0.5, 5.0, 9.5, 10.5, 11.5] {
+                assert_eq!(
+                    outline.inpath(x, y, PathFillMode::Winding, 0.01),
+                    path.instroke(x, y, &style, 0.01),
+                    "({x}, {y})"
+                );
+            }
+        }
+        assert!(outline.inpath(-0.9, -0.9, PathFillMode::Winding, 0.01));
+        assert!(!outline.inpath(5.0, 5.0, PathFillMode::Winding, 0.01));
+    }
+
+    #[test]
+    fn fill_modes() {
+        // A square drawn twice in the same direction.
+        let mut path = polyline(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        path.close();
+        path.extend(path.clone());
+        assert!(path.inpath(1.0, 1.0, PathFillMode::Winding, 0.01));
+        assert!(!path.inpath(1.0, 1.0, PathFillMode::Alternate, 0.01));
+        assert!(!path.inpath(1.0, 1.0, PathFillMode::Default, 0.01));
+        assert!(!path.inpath(3.0, 1.0, PathFillMode::Winding, 0.01));
+    }
+
+    #[test]
+    fn flatten() {
+        let mut path = PathData::new();
+        path.move_to(0.0, 0.0).quad_to(5.0, 5.0, 10.0, 0.0);
+        let mut coarse = path.clone();
+        coarse.flatten(1.0);
+        path.flatten(0.01);
+        assert!(path.len() > coarse.len());
+        assert!(
+            path.iter()
+                .skip(1)
+                .all(|segment| matches!(segment, PathSegment::LineTo(_)))
+        );
+        assert_eq!(path.current_point(), Some(p(10.0, 0.0)));
+    }
+}
@@ -0,0 +1,482 @@
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "ege")]
+use crate::Path;
+use crate::{PathData, PathSegment, Point};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SvgPathError {
+    #[error("Unexpected character `{1}` at offset {0}.")]
+    UnexpectedChar(usize, char),
+    #[error("Expected a number at offset {0}.")]
+    ExpectedNumber(usize),
+    #[error("Expected an arc flag at offset {0}.")]
+    ExpectedFlag(usize),
+    #[error("Path data must start with a move command.")]
+    MissingMoveTo,
+}
+
+/// Tokenizer of SVG path data.
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.data.get(self.pos) {
+            if c.is_ascii_whitespace() || *c == b',' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.data.get(self.pos).copied()
+    }
+
+    /// Checks if a number follows, which repeats the last command.
+    fn has_number(&mut self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    fn number(&mut self) -> Result<f32, SvgPathError> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while p.data.get(p.pos).is_some_and(u8::is_ascii_digit) {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+        if matches!(self.data.get(self.pos), Some(b'-' | b'+')) {
+            self.pos += 1;
+        }
+        let mut valid = digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            valid |= digits(self);
+        }
+        if !valid {
+            self.pos = start;
+            return Err(SvgPathError::ExpectedNumber(start));
+        }
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E')) {
+            let mark = self.pos;
+            self.pos += 1;
+            if matches!(self.data.get(self.pos), Some(b'-' | b'+')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mark;
+            }
+        }
+        let text = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+        text.parse()
+            .map_err(|_| SvgPathError::ExpectedNumber(start))
+    }
+
+    fn point(&mut self) -> Result<Point<f32>, SvgPathError> {
+        Ok(Point {
+            x: self.number()?,
+            y: self.number()?,
+        })
+    }
+
+    /// Arc flags are a single digit and may be written without separators.
+    fn flag(&mut self) -> Result<bool, SvgPathError> {
+        match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(SvgPathError::ExpectedFlag(self.pos)),
+        }
+    }
+}
+
+impl PathData {
+    /// Parse SVG path data.
+    ///
+    /// # Parameters
+    /// - `data`: The path data, i.e. the `d` attribute of an SVG `path` element.
+    ///
+    /// # Returns
+    /// The parsed path. Or an error.
+    ///
+    /// # Note
+    /// All commands (M/L/H/V/C/S/Q/T/A/Z) are supported, both absolute and relative.
+    /// Relative and shorthand commands are resolved, so the path contains only absolute
+    /// `MoveTo`, `LineTo`, `QuadTo`, `CubicTo`, `ArcTo` and `Close` segments.
+    pub fn from_svg_path_data(data: &str) -> Result<Self, SvgPathError> {
+        let mut parser = Parser {
+            data: data.as_bytes(),
+            pos: 0,
+        };
+        let mut path = PathData::new();
+        let mut current = Point { x: 0.0, y: 0.0 };
+        let mut start = current;
+        // The control point to reflect for S and T, with whether it is cubic.
+        let mut last_control: Option<(Point<f32>, bool)> = None;
+        let mut command = None;
+        loop {
+            let c = match parser.peek() {
+                None => break,
+                Some(c) if c.is_ascii_alphabetic() => {
+                    parser.pos += 1;
+                    c
+                }
+                Some(c) => match command {
+                    // Numbers after a command repeat it, a move repeats as a line.
+                    Some(b'M') if parser.has_number() => b'L',
+                    Some(b'm') if parser.has_number() => b'l',
+                    Some(command) if parser.has_number() && !matches!(command, b'Z' | b'z') => {
+                        command
+                    }
+                    _ => return Err(SvgPathError::UnexpectedChar(parser.pos, c as char)),
+                },
+            };
+            if command.is_none() && !matches!(c, b'M' | b'm') {
+                return Err(SvgPathError::MissingMoveTo);
+            }
+            command = Some(c);
+            let relative = c.is_ascii_lowercase();
+            let offset = |p: Point<f32>| match relative {
+                true => Point {
+                    x: p.x + current.x,
+                    y: p.y + current.y,
+                },
+                false => p,
+            };
+            let reflect = |cubic: bool| match last_control {
+                Some((p, c)) if c == cubic => Point {
+                    x: 2.0 * current.x - p.x,
+                    y: 2.0 * current.y - p.y,
+                },
+                _ => current,
+            };
+            let mut control = None;
+            match c.to_ascii_uppercase() {
+                b'M' => {
+                    current = offset(parser.point()?);
+                    start = current;
+                    path.push(PathSegment::MoveTo(current));
+                }
+                b'L' => {
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::LineTo(current));
+                }
+                b'H' => {
+                    let x = parser.number()?;
+                    current.x = if relative { current.x + x } else { x };
+                    path.push(PathSegment::LineTo(current));
+                }
+                b'V' => {
+                    let y = parser.number()?;
+                    current.y = if relative { current.y + y } else { y };
+                    path.push(PathSegment::LineTo(current));
+                }
+                b'C' => {
+                    let c1 = offset(parser.point()?);
+                    let c2 = offset(parser.point()?);
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::CubicTo(c1, c2, current));
+                    control = Some((c2, true));
+                }
+                b'S' => {
+                    let c1 = reflect(true);
+                    let c2 = offset(parser.point()?);
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::CubicTo(c1, c2, current));
+                    control = Some((c2, true));
+                }
+                b'Q' => {
+                    let c1 = offset(parser.point()?);
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::QuadTo(c1, current));
+                    control = Some((c1, false));
+                }
+                b'T' => {
+                    let c1 = reflect(false);
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::QuadTo(c1, current));
+                    control = Some((c1, false));
+                }
+                b'A' => {
+                    let rx = parser.number()?;
+                    let ry = parser.number()?;
+                    let rotation = parser.number()?;
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    current = offset(parser.point()?);
+                    path.push(PathSegment::ArcTo {
+                        rx,
+                        ry,
+                        rotation,
+                        large_arc,
+                        sweep,
+                        to: current,
+                    });
+                }
+                b'Z' => {
+                    current = start;
+                    path.push(PathSegment::Close);
+                }
+                _ => return Err(SvgPathError::UnexpectedChar(parser.pos - 1, c as char)),
+            }
+            last_control = control;
+        }
+        Ok(path)
+    }
+
+    /// Serialize the path to SVG path data.
+    ///
+    /// # Returns
+    /// The path data with absolute commands only.
+    ///
+    /// # Note
+    /// Segments are written as `M`, `L`, `Q`, `C`, `A` and `Z` commands, so the
+    /// result parses back to an equal path.
+    pub fn to_svg_path_data(&self) -> String {
+        let mut data = String::new();
+        for segment in self {
+            if !data.is_empty() {
+                data.push(' ');
+            }
+            // Writing to a `String` never fails.
+            let _ = match *segment {
+                PathSegment::MoveTo(p) => write!(data, "M{} {}", p.x, p.y),
+                PathSegment::LineTo(p) => write!(data, "L{} {}", p.x, p.y),
+                PathSegment::QuadTo(c, p) => write!(data, "Q{} {} {} {}", c.x, c.y, p.x, p.y),
+                PathSegment::CubicTo(c1, c2, p) => {
+                    write!(data, "C{} {} {} {} {} {}", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+                }
+                PathSegment::ArcTo {
+                    rx,
+                    ry,
+                    rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                } => write!(
+                    data,
+                    "A{} {} {} {} {} {} {}",
+                    rx, ry, rotation, large_arc as u8, sweep as u8, to.x, to.y
+                ),
+                PathSegment::Close => write!(data, "Z"),
+            };
+        }
+        data
+    }
+}
+
+impl FromStr for PathData {
+    type Err = SvgPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_svg_path_data(s)
+    }
+}
+
+impl Display for PathData {
+    /// Format the path as SVG path data.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_svg_path_data())
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Path {
+    /// Create a graphics path from SVG path data.
+    ///
+    /// # Parameters
+    /// - `data`: The path data, i.e. the `d` attribute of an SVG `path` element.
+    ///
+    /// # Returns
+    /// A new `Path` object. Or an error.
+    ///
+    /// # Note
+    /// See `PathData::from_svg_path_data` for the supported commands.
+    pub fn from_svg_path_data(data: &str) -> Result<Self, SvgPathError> {
+        PathData::from_svg_path_data(data).map(Path::from)
+    }
+
+    /// Serialize the graphics path to SVG path data.
+    ///
+    /// # Returns
+    /// The path data with absolute commands only.
+    ///
+    /// # Note
+    /// EGE stores lines and cubic bezier curves only, so the result contains `M`,
+    /// `L`, `C` and `Z` commands.
+    pub fn to_svg_path_data(&self) -> String {
+        PathData::from(self).to_svg_path_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f32, y: f32) -> Point<f32> {
+        Point { x, y }
+    }
+
+    fn parse(data: &str) -> Vec<PathSegment> {
+        PathData::from_svg_path_data(data)
+            .unwrap()
+            .segments()
+            .to_vec()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut path = PathData::new();
+        path.move_to(1.5, -2.0)
+            .line_to(3.0, 4.0)
+            .quad_to(5.0, 6.0, 7.0, 8.0)
+            .cubic_to(1.0, 2.0, 3.0, 4.0, 0.25, 1e-3)
+            .arc_to(5.0, 3.0, 30.0, true, false, -1.0, -2.0)
+            .close();
+        let data = path.to_svg_path_data();
+        assert_eq!(
+            data,
+            "M1.5 -2 L3 4 Q5 6 7 8 C1 2 3 4 0.25 0.001 A5 3 30 1 0 -1 -2 Z"
+        );
+        assert_eq!(data.parse::<PathData>().unwrap(), path);
+        assert_eq!(path.to_string(), data);
+    }
+
+    #[test]
+    fn relative_commands() {
+        assert_eq!(
+            parse("m1 1 l2 0 h1 v-3 H0 V1 z l1 1"),
+            [
+                PathSegment::MoveTo(p(1.0, 1.0)),
+                PathSegment::LineTo(p(3.0, 1.0)),
+                PathSegment::LineTo(p(4.0, 1.0)),
+                PathSegment::LineTo(p(4.0, -2.0)),
+                PathSegment::LineTo(p(0.0, -2.0)),
+                PathSegment::LineTo(p(0.0, 1.0)),
+                PathSegment::Close,
+                // After `z`, relative commands start at the start of the subpath.
+                PathSegment::LineTo(p(2.0, 2.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_repeats() {
+        // Numbers after a move repeat it as a line.
+        assert_eq!(
+            parse("M0 0 1 1 2,0 m1 1 1 1"),
+            [
+                PathSegment::MoveTo(p(0.0, 0.0)),
+                PathSegment::LineTo(p(1.0, 1.0)),
+                PathSegment::LineTo(p(2.0, 0.0)),
+                PathSegment::MoveTo(p(3.0, 1.0)),
+                PathSegment::LineTo(p(4.0, 2.0)),
+            ]
+        );
+        assert_eq!(
+            parse("M0 0q1 1 2 0 1-1 2 0"),
+            [
+                PathSegment::MoveTo(p(0.0, 0.0)),
+                PathSegment::QuadTo(p(1.0, 1.0), p(2.0, 0.0)),
+                PathSegment::QuadTo(p(3.0, -1.0), p(4.0, 0.0)),
+            ]
+        );
+        // Signs and dots separate numbers.
+        assert_eq!(
+            parse("M.5.5-1-1"),
+            [
+                PathSegment::MoveTo(p(0.5, 0.5)),
+                PathSegment::LineTo(p(-1.0, -1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn shorthand_curves() {
+        assert_eq!(
+            parse("M0 0 C0 1 2 1 2 0 S4 -1 4 0 Q5 1 6 0 T8 0"),
+            [
+                PathSegment::MoveTo(p(0.0, 0.0)),
+                PathSegment::CubicTo(p(0.0, 1.0), p(2.0, 1.0), p(2.0, 0.0)),
+                PathSegment::CubicTo(p(2.0, -1.0), p(4.0, -1.0), p(4.0, 0.0)),
+                PathSegment::QuadTo(p(5.0, 1.0), p(6.0, 0.0)),
+                PathSegment::QuadTo(p(7.0, -1.0), p(8.0, 0.0)),
+            ]
+        );
+        // Without a previous curve of the same kind, the control point is the current point.
+        assert_eq!(
+            parse("M1 1 Q2 2 3 1 S4 0 5 1")[2],
+            PathSegment::CubicTo(p(3.0, 1.0), p(4.0, 0.0), p(5.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn compact_arc_flags() {
+        let arc = |large_arc, sweep, to| PathSegment::ArcTo {
+            rx: 1.0,
+            ry: 1.0,
+            rotation: 0.0,
+            large_arc,
+            sweep,
+            to,
+        };
+        assert_eq!(
+            parse("M0 0a1 1 0 00 10 10"),
+            [
+                PathSegment::MoveTo(p(0.0, 0.0)),
+                arc(false, false, p(10.0, 10.0))
+            ]
+        );
+        assert_eq!(
+            parse("M0 0 A1,1,0,1,1,2,0 1 1 0 0110 0"),
+            [
+                PathSegment::MoveTo(p(0.0, 0.0)),
+                arc(true, true, p(2.0, 0.0)),
+                arc(false, true, p(10.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            PathData::from_svg_path_data("L1 1"),
+            Err(SvgPathError::MissingMoveTo)
+        );
+        assert_eq!(
+            PathData::from_svg_path_data("1 1"),
+            Err(SvgPathError::UnexpectedChar(0, '1'))
+        );
+        assert_eq!(
+            PathData::from_svg_path_data("M0 0 L1"),
+            Err(SvgPathError::ExpectedNumber(7))
+        );
+        assert_eq!(
+            PathData::from_svg_path_data("M0 0 A1 1 0 2 0 1 1"),
+            Err(SvgPathError::ExpectedFlag(12))
+        );
+        assert_eq!(
+            PathData::from_svg_path_data("M0 0 Z 1"),
+            Err(SvgPathError::UnexpectedChar(7, '1'))
+        );
+        assert_eq!(
+            PathData::from_svg_path_data("M0 0 X"),
+            Err(SvgPathError::UnexpectedChar(5, 'X'))
+        );
+        assert_eq!(PathData::from_svg_path_data(" ").unwrap(), PathData::new());
+    }
+}
@@ -0,0 +1,326 @@
+// The constants of EGE the enums are defined with. Without the `ege` feature the
+// library is not linked, so the values of the bindings are repeated here.
+#[cfg(not(feature = "ege"))]
+pub(crate) use consts::*;
+#[cfg(feature = "ege")]
+pub(crate) use xege_ffi::*;
+
+#[cfg(any(not(feature = "ege"), test))]
+#[allow(non_upper_case_globals, dead_code)]
+mod consts {
+    pub const ANSI_CHARSET: u32 = 0;
+    pub const ANTIALIASED_QUALITY: u32 = 4;
+    pub const BALTIC_CHARSET: u32 = 186;
+    pub const CHINESEBIG5_CHARSET: u32 = 136;
+    pub const CLIP_DEFAULT_PRECIS: u32 = 0;
+    pub const CLIP_EMBEDDED: u32 = 128;
+    pub const CLIP_LH_ANGLES: u32 = 16;
+    pub const CLIP_STROKE_PRECIS: u32 = 2;
+    pub const DEFAULT_CHARSET: u32 = 1;
+    pub const DEFAULT_PITCH: u32 = 0;
+    pub const DEFAULT_QUALITY: u32 = 0;
+    pub const DRAFT_QUALITY: u32 = 1;
+    pub const EASTEUROPE_CHARSET: u32 = 238;
+    pub const FF_DECORATIVE: u32 = 80;
+    pub const FF_DONTCARE: u32 = 0;
+    pub const FF_MODERN: u32 = 48;
+    pub const FF_ROMAN: u32 = 16;
+    pub const FF_SCRIPT: u32 = 64;
+    pub const FF_SWISS: u32 = 32;
+    pub const FIXED_PITCH: u32 = 1;
+    pub const GB2312_CHARSET: u32 = 134;
+    pub const GREEK_CHARSET: u32 = 161;
+    pub const HANGUL_CHARSET: u32 = 129;
+    pub const MAC_CHARSET: u32 = 77;
+    pub const NONANTIALIASED_QUALITY: u32 = 3;
+    pub const OEM_CHARSET: u32 = 255;
+    pub const OPAQUE: u32 = 2;
+    pub const OUT_DEFAULT_PRECIS: u32 = 0;
+    pub const OUT_DEVICE_PRECIS: u32 = 5;
+    pub const OUT_OUTLINE_PRECIS: u32 = 8;
+    pub const OUT_RASTER_PRECIS: u32 = 6;
+    pub const OUT_STRING_PRECIS: u32 = 1;
+    pub const OUT_STROKE_PRECIS: u32 = 3;
+    pub const OUT_TT_ONLY_PRECIS: u32 = 7;
+    pub const OUT_TT_PRECIS: u32 = 4;
+    pub const PROOF_QUALITY: u32 = 2;
+    pub const RUSSIAN_CHARSET: u32 = 204;
+    pub const SHIFTJIS_CHARSET: u32 = 128;
+    pub const SYMBOL_CHARSET: u32 = 2;
+    pub const TRANSPARENT: u32 = 1;
+    pub const TURKISH_CHARSET: u32 = 162;
+    pub const VARIABLE_PITCH: u32 = 2;
+    pub const VK_ADD: u32 = 107;
+    pub const VK_BACK: u32 = 8;
+    pub const VK_CAPITAL: u32 = 20;
+    pub const VK_CLEAR: u32 = 12;
+    pub const VK_CONTROL: u32 = 17;
+    pub const VK_DECIMAL: u32 = 110;
+    pub const VK_DELETE: u32 = 46;
+    pub const VK_DIVIDE: u32 = 111;
+    pub const VK_DOWN: u32 = 40;
+    pub const VK_END: u32 = 35;
+    pub const VK_ESCAPE: u32 = 27;
+    pub const VK_F10: u32 = 121;
+    pub const VK_F11: u32 = 122;
+    pub const VK_F12: u32 = 123;
+    pub const VK_F1: u32 = 112;
+    pub const VK_F2: u32 = 113;
+    pub const VK_F3: u32 = 114;
+    pub const VK_F4: u32 = 115;
+    pub const VK_F5: u32 = 116;
+    pub const VK_F6: u32 = 117;
+    pub const VK_F7: u32 = 118;
+    pub const VK_F8: u32 = 119;
+    pub const VK_F9: u32 = 120;
+    pub const VK_HOME: u32 = 36;
+    pub const VK_INSERT: u32 = 45;
+    pub const VK_LBUTTON: u32 = 1;
+    pub const VK_LCONTROL: u32 = 162;
+    pub const VK_LEFT: u32 = 37;
+    pub const VK_LMENU: u32 = 164;
+    pub const VK_LSHIFT: u32 = 160;
+    pub const VK_MBUTTON: u32 = 4;
+    pub const VK_MENU: u32 = 18;
+    pub const VK_MULTIPLY: u32 = 106;
+    pub const VK_NEXT: u32 = 34;
+    pub const VK_NUMLOCK: u32 = 144;
+    pub const VK_NUMPAD0: u32 = 96;
+    pub const VK_NUMPAD1: u32 = 97;
+    pub const VK_NUMPAD2: u32 = 98;
+    pub const VK_NUMPAD3: u32 = 99;
+    pub const VK_NUMPAD4: u32 = 100;
+    pub const VK_NUMPAD5: u32 = 101;
+    pub const VK_NUMPAD6: u32 = 102;
+    pub const VK_NUMPAD7: u32 = 103;
+    pub const VK_NUMPAD8: u32 = 104;
+    pub const VK_NUMPAD9: u32 = 105;
+    pub const VK_OEM_1: u32 = 186;
+    pub const VK_OEM_2: u32 = 191;
+    pub const VK_OEM_3: u32 = 192;
+    pub const VK_OEM_4: u32 = 219;
+    pub const VK_OEM_5: u32 = 220;
+    pub const VK_OEM_6: u32 = 221;
+    pub const VK_OEM_COMMA: u32 = 188;
+    pub const VK_OEM_MINUS: u32 = 189;
+    pub const VK_OEM_PERIOD: u32 = 190;
+    pub const VK_OEM_PLUS: u32 = 187;
+    pub const VK_PRIOR: u32 = 33;
+    pub const VK_RBUTTON: u32 = 2;
+    pub const VK_RCONTROL: u32 = 163;
+    pub const VK_RETURN: u32 = 13;
+    pub const VK_RIGHT: u32 = 39;
+    pub const VK_RMENU: u32 = 165;
+    pub const VK_RSHIFT: u32 = 161;
+    pub const VK_SCROLL: u32 = 145;
+    pub const VK_SHIFT: u32 = 16;
+    pub const VK_SPACE: u32 = 32;
+    pub const VK_SUBTRACT: u32 = 109;
+    pub const VK_TAB: u32 = 9;
+    pub const VK_UP: u32 = 38;
+    pub const VK_XBUTTON1: u32 = 5;
+    pub const VK_XBUTTON2: u32 = 6;
+    pub const ege_alpha_type_ALPHATYPE_PREMULTIPLIED: i32 = 1;
+    pub const ege_alpha_type_ALPHATYPE_STRAIGHT: i32 = 0;
+    pub const ege_fill_patterns_BKSLASH_FILL: i32 = 5;
+    pub const ege_fill_patterns_CLOSE_DOT_FILL: i32 = 11;
+    pub const ege_fill_patterns_EMPTY_FILL: i32 = 0;
+    pub const ege_fill_patterns_HATCH_FILL: i32 = 7;
+    pub const ege_fill_patterns_INTERLEAVE_FILL: i32 = 9;
+    pub const ege_fill_patterns_LINE_FILL: i32 = 2;
+    pub const ege_fill_patterns_LTBKSLASH_FILL: i32 = 6;
+    pub const ege_fill_patterns_LTSLASH_FILL: i32 = 3;
+    pub const ege_fill_patterns_SLASH_FILL: i32 = 4;
+    pub const ege_fill_patterns_SOLID_FILL: i32 = 1;
+    pub const ege_fill_patterns_USER_FILL: i32 = 12;
+    pub const ege_fill_patterns_WIDE_DOT_FILL: i32 = 10;
+    pub const ege_fill_patterns_XHATCH_FILL: i32 = 8;
+    pub const ege_line_cap_type_LINECAP_FLAT: i32 = 0;
+    pub const ege_line_cap_type_LINECAP_ROUND: i32 = 2;
+    pub const ege_line_cap_type_LINECAP_SQUARE: i32 = 1;
+    pub const ege_line_join_type_LINEJOIN_BEVEL: i32 = 1;
+    pub const ege_line_join_type_LINEJOIN_MITER: i32 = 0;
+    pub const ege_line_join_type_LINEJOIN_ROUND: i32 = 2;
+    pub const ege_line_styles_CENTER_LINE: i32 = 1;
+    pub const ege_line_styles_DASHED_LINE: i32 = 3;
+    pub const ege_line_styles_DOTTED_LINE: i32 = 2;
+    pub const ege_line_styles_NULL_LINE: i32 = 5;
+    pub const ege_line_styles_SOLID_LINE: i32 = 0;
+    pub const ege_line_styles_USERBIT_LINE: i32 = 7;
+    pub const ege_fill_mode_FILLMODE_DEFAULT: i32 = 0;
+    pub const ege_fill_mode_FILLMODE_ALTERNATE: i32 = 1;
+    pub const ege_fill_mode_FILLMODE_WINDING: i32 = 2;
+    pub const ege_rendermode_e_RENDER_AUTO: i32 = 0;
+    pub const ege_rendermode_e_RENDER_MANUAL: i32 = 1;
+    pub const ege_text_just_BOTTOM_TEXT: i32 = 2;
+    pub const ege_text_just_CENTER_TEXT: i32 = 1;
+    pub const ege_text_just_LEFT_TEXT: i32 = 0;
+    pub const ege_text_just_RIGHT_TEXT: i32 = 2;
+    pub const ege_text_just_TOP_TEXT: i32 = 0;
+}
+
+#[cfg(all(test, feature = "ege"))]
+mod tests {
+    use super::consts;
+
+    macro_rules! assert_same {
+        ($($name:ident),* $(,)?) => {
+            $(assert_eq!(consts::$name, xege_ffi::$name, stringify!($name));)*
+        };
+    }
+
+    #[test]
+    fn constants_match_the_bindings() {
+        assert_same!(
+            ANSI_CHARSET,
+            ANTIALIASED_QUALITY,
+            BALTIC_CHARSET,
+            CHINESEBIG5_CHARSET,
+            CLIP_DEFAULT_PRECIS,
+            CLIP_EMBEDDED,
+            CLIP_LH_ANGLES,
+            CLIP_STROKE_PRECIS,
+            DEFAULT_CHARSET,
+            DEFAULT_PITCH,
+            DEFAULT_QUALITY,
+            DRAFT_QUALITY,
+            EASTEUROPE_CHARSET,
+            FF_DECORATIVE,
+            FF_DONTCARE,
+            FF_MODERN,
+            FF_ROMAN,
+            FF_SCRIPT,
+            FF_SWISS,
+            FIXED_PITCH,
+            GB2312_CHARSET,
+            GREEK_CHARSET,
+            HANGUL_CHARSET,
+            MAC_CHARSET,
+            NONANTIALIASED_QUALITY,
+            OEM_CHARSET,
+            OPAQUE,
+            OUT_DEFAULT_PRECIS,
+            OUT_DEVICE_PRECIS,
+            OUT_OUTLINE_PRECIS,
+            OUT_RASTER_PRECIS,
+            OUT_STRING_PRECIS,
+            OUT_STROKE_PRECIS,
+            OUT_TT_ONLY_PRECIS,
+            OUT_TT_PRECIS,
+            PROOF_QUALITY,
+            RUSSIAN_CHARSET,
+            SHIFTJIS_CHARSET,
+            SYMBOL_CHARSET,
+            TRANSPARENT,
+            TURKISH_CHARSET,
+            VARIABLE_PITCH,
+            VK_ADD,
+            VK_BACK,
+            VK_CAPITAL,
+            VK_CLEAR,
+            VK_CONTROL,
+            VK_DECIMAL,
+            VK_DELETE,
+            VK_DIVIDE,
+            VK_DOWN,
+            VK_END,
+            VK_ESCAPE,
+            VK_F10,
+            VK_F11,
+            VK_F12,
+            VK_F1,
+            VK_F2,
+            VK_F3,
+            VK_F4,
+            VK_F5,
+            VK_F6,
+            VK_F7,
+            VK_F8,
+            VK_F9,
+            VK_HOME,
+            VK_INSERT,
+            VK_LBUTTON,
+            VK_LCONTROL,
+            VK_LEFT,
+            VK_LMENU,
+            VK_LSHIFT,
+            VK_MBUTTON,
+            VK_MENU,
+            VK_MULTIPLY,
+            VK_NEXT,
+            VK_NUMLOCK,
+            VK_NUMPAD0,
+            VK_NUMPAD1,
+            VK_NUMPAD2,
+            VK_NUMPAD3,
+            VK_NUMPAD4,
+            VK_NUMPAD5,
+            VK_NUMPAD6,
+            VK_NUMPAD7,
+            VK_NUMPAD8,
+            VK_NUMPAD9,
+            VK_OEM_1,
+            VK_OEM_2,
+            VK_OEM_3,
+            VK_OEM_4,
+            VK_OEM_5,
+            VK_OEM_6,
+            VK_OEM_COMMA,
+            VK_OEM_MINUS,
+            VK_OEM_PERIOD,
+            VK_OEM_PLUS,
+            VK_PRIOR,
+            VK_RBUTTON,
+            VK_RCONTROL,
+            VK_RETURN,
+            VK_RIGHT,
+            VK_RMENU,
+            VK_RSHIFT,
+            VK_SCROLL,
+            VK_SHIFT,
+            VK_SPACE,
+            VK_SUBTRACT,
+            VK_TAB,
+            VK_UP,
+            VK_XBUTTON1,
+            VK_XBUTTON2,
+            ege_alpha_type_ALPHATYPE_PREMULTIPLIED,
+            ege_alpha_type_ALPHATYPE_STRAIGHT,
+            ege_fill_patterns_BKSLASH_FILL,
+            ege_fill_patterns_CLOSE_DOT_FILL,
+            ege_fill_patterns_EMPTY_FILL,
+            ege_fill_patterns_HATCH_FILL,
+            ege_fill_patterns_INTERLEAVE_FILL,
+            ege_fill_patterns_LINE_FILL,
+            ege_fill_patterns_LTBKSLASH_FILL,
+            ege_fill_patterns_LTSLASH_FILL,
+            ege_fill_patterns_SLASH_FILL,
+            ege_fill_patterns_SOLID_FILL,
+            ege_fill_patterns_USER_FILL,
+            ege_fill_patterns_WIDE_DOT_FILL,
+            ege_fill_patterns_XHATCH_FILL,
+            ege_line_cap_type_LINECAP_FLAT,
+            ege_line_cap_type_LINECAP_ROUND,
+            ege_line_cap_type_LINECAP_SQUARE,
+            ege_line_join_type_LINEJOIN_BEVEL,
+            ege_line_join_type_LINEJOIN_MITER,
+            ege_line_join_type_LINEJOIN_ROUND,
+            ege_line_styles_CENTER_LINE,
+            ege_line_styles_DASHED_LINE,
+            ege_line_styles_DOTTED_LINE,
+            ege_line_styles_NULL_LINE,
+            ege_line_styles_SOLID_LINE,
+            ege_line_styles_USERBIT_LINE,
+            ege_fill_mode_FILLMODE_DEFAULT,
+            ege_fill_mode_FILLMODE_ALTERNATE,
+            ege_fill_mode_FILLMODE_WINDING,
+            ege_rendermode_e_RENDER_AUTO,
+            ege_rendermode_e_RENDER_MANUAL,
+            ege_text_just_BOTTOM_TEXT,
+            ege_text_just_CENTER_TEXT,
+            ege_text_just_LEFT_TEXT,
+            ege_text_just_RIGHT_TEXT,
+            ege_text_just_TOP_TEXT,
+        );
+    }
+}
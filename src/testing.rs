@@ -0,0 +1,322 @@
+//! Golden-image snapshot testing.
+//!
+//! Drawing code is rendered into an offscreen device and its pixels are compared
+//! with a PNG stored next to the tests. When the pixels differ, a diff image is
+//! written so the regression can be inspected.
+//!
+//! Set the environment variable `XEGE_UPDATE_SNAPSHOTS` to `1` or `true` to (re)create
+//! the stored PNGs from the current output instead of comparing against them.
+//!
+//! ```no_run
+//! # #[cfg(feature = "ege")] {
+//! use xege::*;
+//!
+//! let image = testing::render(64, 64, |image| {
+//!     image.setcolor(color::WHITE);
+//!     image.circle(32, 32, 20);
+//! });
+//! testing::assert_snapshot(&image, "tests/snapshots/circle.png", 0);
+//! # }
+//! ```
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "software")]
+use crate::Canvas;
+#[cfg(feature = "ege")]
+use crate::Image;
+use crate::image::{argb_to_rgba, rgba_to_argb};
+use crate::{ARGB, GraphicsEnvironment};
+
+/// The environment variable which switches the harness to update mode.
+///
+/// Only the values `1` and `true` switch it on, e.g. `XEGE_UPDATE_SNAPSHOTS=0` compares.
+pub const UPDATE_ENV: &str = "XEGE_UPDATE_SNAPSHOTS";
+
+/// Checks if a value of `UPDATE_ENV` switches the harness to update mode.
+fn is_update(value: &str) -> bool {
+    matches!(value, "1" | "true")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("The snapshot `{0}` does not exist. Set `XEGE_UPDATE_SNAPSHOTS=1` to create it.")]
+    Missing(PathBuf),
+    #[error("The size {actual:?} differs from the size {expected:?} of the snapshot.")]
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    #[error("{count} pixels differ by up to {max_delta} from the snapshot, see `{diff}`.")]
+    Mismatch {
+        count: usize,
+        max_delta: u8,
+        diff: PathBuf,
+    },
+    #[error("Failed to read or write the snapshot: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// The result of comparing two pixel buffers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    /// The number of pixels with a channel difference above the tolerance.
+    pub count: usize,
+    /// The largest channel difference found.
+    pub max_delta: u8,
+    /// The diff image. Differing pixels are red, others are a faded copy of the expected pixels.
+    pub diff: Vec<ARGB>,
+}
+
+impl Comparison {
+    /// Checks if the buffers matched within the tolerance.
+    pub fn is_match(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Render into a new offscreen `Image`.
+///
+/// # Parameters
+/// * `width` - The width of the image.
+/// * `height` - The height of the image.
+/// * `draw` - The drawing code.
+///
+/// # Returns
+/// The rendered image.
+#[cfg(feature = "ege")]
+pub fn render(width: u32, height: u32, draw: impl FnOnce(&mut Image)) -> Image {
+    let mut image = Image::new(width, height);
+    draw(&mut image);
+    image
+}
+
+/// Render into a new software `Canvas`.
+///
+/// # Parameters
+/// * `width` - The width of the canvas.
+/// * `height` - The height of the canvas.
+/// * `draw` - The drawing code.
+///
+/// # Returns
+/// The rendered canvas.
+///
+/// # Note
+/// Unlike `render`, this does not require EGE and also works on build servers
+/// without a display.
+#[cfg(feature = "software")]
+pub fn render_canvas(width: u32, height: u32, draw: impl FnOnce(&mut Canvas)) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    draw(&mut canvas);
+    canvas
+}
+
+/// Compare two ARGB buffers of the same size.
+///
+/// # Parameters
+/// * `actual` - The rendered pixels.
+/// * `expected` - The stored pixels.
+/// * `tolerance` - The largest accepted difference of each channel, including alpha.
+///
+/// # Returns
+/// The comparison result, see `Comparison`.
+///
+/// # Panics
+/// Panics if the buffers have different lengths.
+pub fn compare(actual: &[ARGB], expected: &[ARGB], tolerance: u8) -> Comparison {
+    assert_eq!(actual.len(), expected.len(), "The buffer sizes differ.");
+    let mut count = 0;
+    let mut max_delta = 0;
+    let diff = actual
+        .iter()
+        .zip(expected)
+        .map(|(&a, &e)| {
+            let delta = a
+                .to_le_bytes()
+                .iter()
+                .zip(e.to_le_bytes())
+                .map(|(&a, e)| a.abs_diff(e))
+                .max()
+                .unwrap_or(0);
+            max_delta = max_delta.max(delta);
+            if delta > tolerance {
+                count += 1;
+                0xFFFF0000
+            } else {
+                let [b, g, r, _] = e.to_le_bytes();
+                let gray = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+                let faded = 0xC0 + gray / 4;
+                0xFF000000 | (faded << 16) | (faded << 8) | faded
+            }
+        })
+        .collect();
+    Comparison {
+        count,
+        max_delta,
+        diff,
+    }
+}
+
+/// The path of the diff image written for a snapshot.
+fn diff_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.diff.png"))
+}
+
+/// Compare the pixels of a device with a stored snapshot.
+///
+/// # Parameters
+/// * `device` - The rendered device, e.g. an `Image` or a `Canvas`.
+/// * `path` - The PNG file of the snapshot.
+/// * `tolerance` - The largest accepted difference of each channel, including alpha.
+///
+/// # Errors
+/// Returns an error if the snapshot does not exist, has another size or differs.
+/// When the pixels differ, a diff image named `<name>.diff.png` is written next
+/// to the snapshot. A stale diff image is removed when the pixels match.
+///
+/// # Note
+/// If `XEGE_UPDATE_SNAPSHOTS` is `1` or `true`, the snapshot is written instead.
+pub fn check_snapshot<P: AsRef<Path>>(
+    device: &impl GraphicsEnvironment,
+    path: P,
+    tolerance: u8,
+) -> Result<(), SnapshotError> {
+    let update = std::env::var(UPDATE_ENV).is_ok_and(|value| is_update(&value));
+    check(device, path.as_ref(), tolerance, update)
+}
+
+/// Compare the pixels of a device with a stored snapshot, or write the snapshot if `update`.
+fn check(
+    device: &impl GraphicsEnvironment,
+    path: &Path,
+    tolerance: u8,
+    update: bool,
+) -> Result<(), SnapshotError> {
+    let (width, height) = (device.getwidth(), device.getheight());
+    let actual = device.getbuffer();
+    if update {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(image::ImageError::IoError)?;
+        }
+        argb_to_rgba(actual, width, height).save(path)?;
+        return Ok(());
+    }
+    if !path.exists() {
+        return Err(SnapshotError::Missing(path.to_path_buf()));
+    }
+    let expected = image::open(path)?.to_rgba8();
+    if expected.dimensions() != (width, height) {
+        return Err(SnapshotError::SizeMismatch {
+            expected: expected.dimensions(),
+            actual: (width, height),
+        });
+    }
+    let comparison = compare(actual, &rgba_to_argb(&expected), tolerance);
+    let diff = diff_path(path);
+    if comparison.is_match() {
+        if diff.exists() {
+            std::fs::remove_file(&diff).map_err(image::ImageError::IoError)?;
+        }
+        return Ok(());
+    }
+    argb_to_rgba(&comparison.diff, width, height).save(&diff)?;
+    Err(SnapshotError::Mismatch {
+        count: comparison.count,
+        max_delta: comparison.max_delta,
+        diff,
+    })
+}
+
+/// Assert that the pixels of a device match a stored snapshot.
+///
+/// # Parameters
+/// * `device` - The rendered device, e.g. an `Image` or a `Canvas`.
+/// * `path` - The PNG file of the snapshot.
+/// * `tolerance` - The largest accepted difference of each channel, including alpha.
+///
+/// # Panics
+/// Panics if `check_snapshot` returns an error.
+#[track_caller]
+pub fn assert_snapshot<P: AsRef<Path>>(device: &impl GraphicsEnvironment, path: P, tolerance: u8) {
+    if let Err(e) = check_snapshot(device, path, tolerance) {
+        panic!("{e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_values() {
+        assert!(is_update("1") && is_update("true"));
+        for value in ["", "0", "false", "yes", "TRUE", " 1"] {
+            assert!(!is_update(value), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn compare_buffers() {
+        let comparison = compare(&[0xFF000000, 0x80FFFFFF], &[0xFF000002, 0x80FFFFFF], 1);
+        assert_eq!((comparison.count, comparison.max_delta), (1, 2));
+        assert!(!comparison.is_match());
+        assert_eq!(comparison.diff[0], 0xFFFF0000);
+        assert_eq!(comparison.diff[1], 0xFFFFFFFF);
+        assert!(compare(&[0xFF000000], &[0xFF000002], 2).is_match());
+    }
+
+    #[test]
+    #[should_panic(expected = "The buffer sizes differ.")]
+    fn compare_lengths() {
+        compare(&[0; 2], &[0; 3], 0);
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn canvas_snapshots() {
+        use crate::Draw;
+
+        let dir = std::env::temp_dir().join(format!("xege-snapshots-{}", std::process::id()));
+        let path = dir.join("pixels.png");
+        let diff = dir.join("pixels.diff.png");
+        let draw = |color| {
+            render_canvas(4, 3, |canvas| {
+                canvas.putpixel(1, 1, color);
+                canvas.putpixel(2, 1, 0xFF00FF00);
+            })
+        };
+        assert!(matches!(
+            check(&draw(0xFFFF0000), &path, 0, false),
+            Err(SnapshotError::Missing(p)) if p == path
+        ));
+        check(&draw(0xFFFF0000), &path, 0, true).unwrap();
+        check(&draw(0xFFFF0000), &path, 0, false).unwrap();
+        // Within the tolerance.
+        check(&draw(0xFFFC0000), &path, 3, false).unwrap();
+        assert!(!diff.exists());
+        // A mismatch writes the diff image.
+        match check(&draw(0xFFFC0000), &path, 2, false) {
+            Err(SnapshotError::Mismatch {
+                count: 1,
+                max_delta: 3,
+                diff: written,
+            }) => assert_eq!(written, diff),
+            result => panic!("{result:?}"),
+        }
+        let image = image::open(&diff).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(1, 1).0, [0xFF, 0, 0, 0xFF]);
+        assert_ne!(image.get_pixel(2, 1).0, [0xFF, 0, 0, 0xFF]);
+        // A match removes the stale diff image.
+        check(&draw(0xFFFF0000), &path, 0, false).unwrap();
+        assert!(!diff.exists());
+        assert!(matches!(
+            check(&render_canvas(3, 4, |_| {}), &path, 0, false),
+            Err(SnapshotError::SizeMismatch {
+                expected: (4, 3),
+                actual: (3, 4),
+            })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
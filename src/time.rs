@@ -3,22 +3,34 @@
 /// 
 /// # Parameters
 /// * `ms` - The number of milliseconds to delay.
+/// 
+/// # Note
+/// A frame of a recording or replay ends, see `Window::next_frame`.
 pub fn delay_ms(ms: i32) {
     unsafe { xege_ffi::ege_delay_ms(ms) };
+    crate::record::next_frame();
 }
 
 /// Delay by frame rate.
 /// 
 /// # Parameters
 /// * `fps` - The frame rate to delay.
+/// 
+/// # Note
+/// A frame of a recording or replay ends, see `Window::next_frame`.
 pub fn delay_fps(fps: f64) {
     unsafe { xege_ffi::ege_delay_fps2(fps) };
+    crate::record::next_frame();
 }
 
 /// Delay by jitter frame rate.
 /// 
 /// # Parameters
 /// * `fps` - The jitter frame rate to delay.
+/// 
+/// # Note
+/// A frame of a recording or replay ends, see `Window::next_frame`.
 pub fn delay_jfps(fps: f64) {
     unsafe { xege_ffi::ege_delay_jfps2(fps) };
+    crate::record::next_frame();
 }
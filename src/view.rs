@@ -0,0 +1,497 @@
+use mats::Mat;
+
+use crate::image::template_pixel;
+use crate::{ARGB, ApplyMask, Rect, TemplateMode};
+#[cfg(feature = "ege")]
+use crate::{GraphicsEnvironment, Image};
+
+/// The range of a buffer covered by a region, with the size of the region.
+fn region(
+    len: usize,
+    stride: usize,
+    width: u32,
+    height: u32,
+    rect: Rect,
+) -> Option<(std::ops::Range<usize>, u32, u32)> {
+    if rect.x < 0
+        || rect.y < 0
+        || rect.width < 0
+        || rect.height < 0
+        || rect.x as u64 + rect.width as u64 > width as u64
+        || rect.y as u64 + rect.height as u64 > height as u64
+    {
+        return None;
+    }
+    let (w, h) = (rect.width as u32, rect.height as u32);
+    if h == 0 {
+        // No row is ever indexed, and the start may be past the end of the buffer.
+        return Some((0..0, w, h));
+    }
+    let start = rect.y as usize * stride + rect.x as usize;
+    let end = start + (h as usize - 1) * stride + w as usize;
+    (end <= len).then_some((start..end, w, h))
+}
+
+/// A borrowed rectangular region of pixels.
+///
+/// Rows of the region are `stride` pixels apart in the underlying buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+    pixels: &'a [ARGB],
+    stride: usize,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> ImageView<'a> {
+    /// Create a view of a pixel buffer.
+    ///
+    /// # Parameters
+    /// - `pixels`: The pixels, starting at the top-left pixel of the region.
+    /// - `stride`: The distance between two rows in pixels.
+    /// - `width`: The width of the region.
+    /// - `height`: The height of the region.
+    ///
+    /// # Panics
+    /// Panics if the buffer is too small for the region.
+    pub fn new(pixels: &'a [ARGB], stride: usize, width: u32, height: u32) -> Self {
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width: width as i32,
+            height: height as i32,
+        };
+        let (range, ..) = region(pixels.len(), stride, width, height, rect)
+            .filter(|_| stride >= width as usize)
+            .expect("The buffer is too small for the region.");
+        Self {
+            pixels: &pixels[range],
+            stride,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The distance between two rows in the underlying buffer, in pixels.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Get the pixel at `(x, y)`, or `None` if it is outside of the view.
+    pub fn get(&self, x: u32, y: u32) -> Option<ARGB> {
+        (x < self.width && y < self.height)
+            .then(|| self.pixels[y as usize * self.stride + x as usize])
+    }
+
+    /// Get the row `y`, or `None` if it is outside of the view.
+    pub fn row(&self, y: u32) -> Option<&'a [ARGB]> {
+        let start = y as usize * self.stride;
+        (y < self.height).then(|| &self.pixels[start..start + self.width as usize])
+    }
+
+    /// Iterate over the rows from top to bottom.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &'a [ARGB]> + use<'a> {
+        let (pixels, stride, width) = (self.pixels, self.stride, self.width as usize);
+        (0..self.height as usize).map(move |y| &pixels[y * stride..y * stride + width])
+    }
+
+    /// Get a region of this view.
+    ///
+    /// # Parameters
+    /// - `rect`: The region, relative to the top-left corner of this view.
+    ///
+    /// # Returns
+    /// The view of the region, or `None` if it is not inside of this view.
+    pub fn view(&self, rect: Rect) -> Option<ImageView<'a>> {
+        let (range, width, height) = region(
+            self.pixels.len(),
+            self.stride,
+            self.width,
+            self.height,
+            rect,
+        )?;
+        Some(ImageView {
+            pixels: &self.pixels[range],
+            stride: self.stride,
+            width,
+            height,
+        })
+    }
+
+    /// Copy the pixels into a tightly packed buffer.
+    pub fn to_vec(&self) -> Vec<ARGB> {
+        self.rows().flatten().copied().collect()
+    }
+
+    /// Copy the pixels into a new image.
+    #[cfg(feature = "ege")]
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        if self.width > 0 && self.height > 0 {
+            image.getbuffer_mut().copy_from_slice(&self.to_vec());
+        }
+        image
+    }
+}
+
+/// A mutably borrowed rectangular region of pixels.
+///
+/// Rows of the region are `stride` pixels apart in the underlying buffer.
+#[derive(Debug)]
+pub struct ImageViewMut<'a> {
+    pixels: &'a mut [ARGB],
+    stride: usize,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> ImageViewMut<'a> {
+    /// Create a mutable view of a pixel buffer.
+    ///
+    /// # Note
+    /// See `ImageView::new`.
+    pub fn new(pixels: &'a mut [ARGB], stride: usize, width: u32, height: u32) -> Self {
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width: width as i32,
+            height: height as i32,
+        };
+        let (range, ..) = region(pixels.len(), stride, width, height, rect)
+            .filter(|_| stride >= width as usize)
+            .expect("The buffer is too small for the region.");
+        Self {
+            pixels: &mut pixels[range],
+            stride,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The distance between two rows in the underlying buffer, in pixels.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Borrow this view as an immutable view.
+    pub fn as_view(&self) -> ImageView<'_> {
+        ImageView {
+            pixels: self.pixels,
+            stride: self.stride,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Get the pixel at `(x, y)`, or `None` if it is outside of the view.
+    pub fn get(&self, x: u32, y: u32) -> Option<ARGB> {
+        self.as_view().get(x, y)
+    }
+
+    /// Get a mutable reference to the pixel at `(x, y)`, or `None` if it is outside of the view.
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut ARGB> {
+        (x < self.width && y < self.height)
+            .then(|| &mut self.pixels[y as usize * self.stride + x as usize])
+    }
+
+    /// Set the pixel at `(x, y)`.
+    ///
+    /// # Returns
+    /// `false` if the pixel is outside of the view and nothing was written.
+    pub fn set(&mut self, x: u32, y: u32, color: ARGB) -> bool {
+        self.get_mut(x, y).map(|pixel| *pixel = color).is_some()
+    }
+
+    /// Get the row `y`, or `None` if it is outside of the view.
+    pub fn row(&self, y: u32) -> Option<&[ARGB]> {
+        self.as_view().row(y)
+    }
+
+    /// Get the mutable row `y`, or `None` if it is outside of the view.
+    pub fn row_mut(&mut self, y: u32) -> Option<&mut [ARGB]> {
+        let start = y as usize * self.stride;
+        (y < self.height).then(|| &mut self.pixels[start..start + self.width as usize])
+    }
+
+    /// Iterate over the rows from top to bottom.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[ARGB]> {
+        self.as_view().rows()
+    }
+
+    /// Iterate over the mutable rows from top to bottom.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [ARGB]> {
+        let width = self.width as usize;
+        self.pixels
+            .chunks_mut(self.stride.max(1))
+            .take(self.height as usize)
+            .map(move |row| &mut row[..width])
+    }
+
+    /// Get a region of this view.
+    ///
+    /// # Note
+    /// See `ImageView::view`.
+    pub fn view(&self, rect: Rect) -> Option<ImageView<'_>> {
+        self.as_view().view(rect)
+    }
+
+    /// Get a mutable region of this view.
+    ///
+    /// # Parameters
+    /// - `rect`: The region, relative to the top-left corner of this view.
+    ///
+    /// # Returns
+    /// The view of the region, or `None` if it is not inside of this view.
+    pub fn view_mut(&mut self, rect: Rect) -> Option<ImageViewMut<'_>> {
+        let (range, width, height) = region(
+            self.pixels.len(),
+            self.stride,
+            self.width,
+            self.height,
+            rect,
+        )?;
+        Some(ImageViewMut {
+            pixels: &mut self.pixels[range],
+            stride: self.stride,
+            width,
+            height,
+        })
+    }
+
+    /// Fill the view with a color.
+    pub fn fill(&mut self, color: ARGB) {
+        self.rows_mut().for_each(|row| row.fill(color));
+    }
+
+    /// Copy the pixels of another view of the same size.
+    ///
+    /// # Panics
+    /// Panics if the sizes differ.
+    pub fn copy_from(&mut self, src: &ImageView) {
+        assert_eq!(
+            (self.width, self.height),
+            (src.width(), src.height()),
+            "The view sizes differ."
+        );
+        for (dst, src) in self.rows_mut().zip(src.rows()) {
+            dst.copy_from_slice(src);
+        }
+    }
+
+    /// Apply a transformation to the pixels of the view.
+    ///
+    /// # Note
+    /// See `Image::transform`.
+    pub fn transform(&mut self, trans: impl Fn(ARGB) -> ARGB) {
+        for pixel in self.rows_mut().flatten() {
+            *pixel = trans(*pixel);
+        }
+    }
+
+    /// Apply a template to the pixels of the view in place.
+    ///
+    /// # Note
+    /// See `Image::template`. Pixels outside of the view are treated as non-existent,
+    /// and pixels not matched in `TemplateMode::DotCare` are left unchanged.
+    pub fn template<const N: usize>(
+        &mut self,
+        mask: Mat<f32, N, N>,
+        apply: ApplyMask,
+        mode: TemplateMode,
+    ) {
+        #[cfg(debug_assertions)]
+        assert!(N % 2 == 1, "The template size must be odd.");
+        let copy = self.as_view().to_vec();
+        let src = ImageView::new(&copy, self.width as usize, self.width, self.height);
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if let Some(c) = template_pixel(&src, i, j, &mask, apply, &mode) {
+                    self.set(j, i, c);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ege")]
+impl Image {
+    /// Borrow the whole image.
+    pub fn as_view(&self) -> ImageView<'_> {
+        let (width, height) = (self.getwidth(), self.getheight());
+        ImageView::new(self.getbuffer(), width as usize, width, height)
+    }
+
+    /// Mutably borrow the whole image.
+    pub fn as_view_mut(&mut self) -> ImageViewMut<'_> {
+        let (width, height) = (self.getwidth(), self.getheight());
+        ImageViewMut::new(self.getbuffer_mut(), width as usize, width, height)
+    }
+
+    /// Borrow a region of the image.
+    ///
+    /// # Parameters
+    /// - `rect`: The region.
+    ///
+    /// # Returns
+    /// The view of the region, or `None` if it is not inside of the image.
+    pub fn view(&self, rect: Rect) -> Option<ImageView<'_>> {
+        let (width, height) = (self.getwidth(), self.getheight());
+        let (range, w, h) = region(self.getbuffer().len(), width as usize, width, height, rect)?;
+        Some(ImageView {
+            pixels: &self.getbuffer()[range],
+            stride: width as usize,
+            width: w,
+            height: h,
+        })
+    }
+
+    /// Mutably borrow a region of the image.
+    ///
+    /// # Parameters
+    /// - `rect`: The region.
+    ///
+    /// # Returns
+    /// The view of the region, or `None` if it is not inside of the image.
+    pub fn view_mut(&mut self, rect: Rect) -> Option<ImageViewMut<'_>> {
+        let (width, height) = (self.getwidth(), self.getheight());
+        let buffer = self.getbuffer_mut();
+        let (range, w, h) = region(buffer.len(), width as usize, width, height, rect)?;
+        Some(ImageViewMut {
+            pixels: &mut buffer[range],
+            stride: width as usize,
+            width: w,
+            height: h,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// A 4x3 region with a stride of 5, the pixels are `10 * y + x`.
+    fn buffer() -> Vec<ARGB> {
+        (0..3)
+            .flat_map(|y| (0..5).map(move |x| 10 * y + x))
+            .collect()
+    }
+
+    #[test]
+    fn stride() {
+        let buffer = buffer();
+        let view = ImageView::new(&buffer, 5, 4, 3);
+        assert_eq!((view.width(), view.height(), view.stride()), (4, 3, 5));
+        assert_eq!(view.get(3, 2), Some(23));
+        assert_eq!(view.row(1), Some(&[10, 11, 12, 13][..]));
+        assert_eq!(view.rows().len(), 3);
+        assert_eq!(view.to_vec(), [0, 1, 2, 3, 10, 11, 12, 13, 20, 21, 22, 23]);
+        // The buffer may end right after the last pixel of the region.
+        let view = ImageView::new(&buffer[..14], 5, 4, 3);
+        assert_eq!(view.get(3, 2), Some(23));
+    }
+
+    #[test]
+    #[should_panic(expected = "The buffer is too small for the region.")]
+    fn buffer_too_small() {
+        let buffer = buffer();
+        ImageView::new(&buffer[..13], 5, 4, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "The buffer is too small for the region.")]
+    fn stride_too_small() {
+        let buffer = buffer();
+        ImageView::new(&buffer, 3, 4, 3);
+    }
+
+    #[test]
+    fn regions() {
+        let buffer = buffer();
+        let view = ImageView::new(&buffer, 5, 4, 3);
+        let region = view.view(rect(1, 1, 3, 2)).unwrap();
+        assert_eq!(
+            (region.width(), region.height(), region.stride()),
+            (3, 2, 5)
+        );
+        assert_eq!(region.to_vec(), [11, 12, 13, 21, 22, 23]);
+        let nested = region.view(rect(1, 1, 2, 1)).unwrap();
+        assert_eq!(nested.to_vec(), [22, 23]);
+        for empty in [rect(4, 3, 0, 0), rect(0, 3, 4, 0), rect(4, 0, 0, 3)] {
+            let empty = view.view(empty).unwrap();
+            assert!(empty.to_vec().is_empty());
+            assert_eq!(empty.get(0, 0), None);
+        }
+        for rect in [
+            rect(-1, 0, 2, 2),
+            rect(0, -1, 2, 2),
+            rect(3, 0, 2, 1),
+            rect(0, 2, 1, 2),
+            rect(0, 0, -1, 1),
+            rect(i32::MAX, 0, i32::MAX, 1),
+        ] {
+            assert!(view.view(rect).is_none(), "{rect:?}");
+        }
+        assert!(region.view(rect(0, 0, 4, 1)).is_none());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut buffer = buffer();
+        let mut view = ImageViewMut::new(&mut buffer, 5, 4, 3);
+        assert_eq!(view.get(4, 0), None);
+        assert_eq!(view.get(0, 3), None);
+        assert_eq!(view.get_mut(4, 0), None);
+        assert_eq!(view.get_mut(0, 3), None);
+        assert!(view.row(3).is_none() && view.row_mut(3).is_none());
+        assert!(!view.set(4, 2, 0xFF));
+        *view.get_mut(3, 2).unwrap() = 0xFF;
+        assert!(view.set(0, 0, 0xEE));
+        // The pixel of the stride after a row is not part of the view.
+        assert_eq!(buffer[..5], [0xEE, 1, 2, 3, 4]);
+        assert_eq!(buffer[13], 0xFF);
+    }
+
+    #[test]
+    fn rows_mut() {
+        let mut buffer = buffer();
+        let mut view = ImageViewMut::new(&mut buffer, 5, 4, 3);
+        let mut region = view.view_mut(rect(1, 1, 2, 2)).unwrap();
+        assert_eq!(region.rows_mut().count(), 2);
+        for (y, row) in region.rows_mut().enumerate() {
+            assert_eq!(row.len(), 2);
+            row.fill(100 + y as ARGB);
+        }
+        region.row_mut(0).unwrap()[1] = 0xAA;
+        assert_eq!(
+            view.as_view().to_vec(),
+            [0, 1, 2, 3, 10, 100, 0xAA, 13, 20, 101, 101, 23]
+        );
+        view.fill(7);
+        assert_eq!(buffer, [7, 7, 7, 7, 4, 7, 7, 7, 7, 14, 7, 7, 7, 7, 24]);
+    }
+}
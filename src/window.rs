@@ -1,10 +1,16 @@
+#[cfg(feature = "ege")]
 use xege_ffi::*;
 
+use crate::record::{RecordedMsg, record, replaying};
 use crate::{Key, KeyFlags, KeyMsg, MouseMsg, Point};
 
 /// Window handle.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Window(pub(crate) *mut ());
+pub struct Window(
+    pub(crate) *mut (),
+    /// Whether the window belongs to a headless environment, which has no window.
+    pub(crate) bool,
+);
 
 impl Window {
     /// Move Window to (x, y) relative the screen.
@@ -13,8 +19,11 @@ impl Window {
     /// * `x` - The x coordinate of the new position.
     /// * `y` - The y coordinate of the new position.
     /// * `redraw` - If true, the window will be redrawn.
+    #[cfg(feature = "ege")]
     pub fn move_to(&mut self, x: i32, y: i32, redraw: bool) {
-        unsafe { ege_movewindow(x, y, redraw) };
+        if self.has_window() {
+            unsafe { ege_movewindow(x, y, redraw) };
+        }
     }
 
     /// Resize the window to (width, height).
@@ -22,13 +31,19 @@ impl Window {
     /// # Parameters
     /// * `width` - The new width of the window.
     /// * `height` - The new height of the window.
+    #[cfg(feature = "ege")]
     pub fn resize(&mut self, width: i32, height: i32) {
-        unsafe { ege_resizewindow(width, height) };
+        if self.has_window() {
+            unsafe { ege_resizewindow(width, height) };
+        }
     }
 
     /// Flushes the window.
+    #[cfg(feature = "ege")]
     pub fn flush(&mut self) {
-        unsafe { ege_flushwindow() };
+        if self.has_window() {
+            unsafe { ege_flushwindow() };
+        }
     }
 
     /// Show the window.
@@ -36,13 +51,24 @@ impl Window {
     /// # Note
     /// If the initialization mode contains `Init::Hide`,
     /// then this function needs to be called for the window to display.
+    #[cfg(feature = "ege")]
     pub fn show(&mut self) {
-        unsafe { ege_showwindow() };
+        if self.has_window() {
+            unsafe { ege_showwindow() };
+        }
     }
 
     /// Hide the window.
+    #[cfg(feature = "ege")]
     pub fn hide(&mut self) {
-        unsafe { ege_hidewindow() };
+        if self.has_window() {
+            unsafe { ege_hidewindow() };
+        }
+    }
+
+    /// Checks if there is a window, which a headless environment has not.
+    pub(crate) fn has_window(&self) -> bool {
+        !self.1
     }
 
     /// Get the state of specified key.
@@ -53,7 +79,12 @@ impl Window {
     /// # Returns
     /// * `true` if the key is pressed, `false` otherwise.
     pub fn keystate(&self, key: Key) -> bool {
-        unsafe { ege_keystate(<Key as Into<u32>>::into(key) as i32) }
+        if let Some(down) = replaying(|player| player.held.down.contains(&key)) {
+            return down;
+        }
+        let down = self.has_window() && live::keystate(key);
+        record(RecordedMsg::KeyState(key, down));
+        down
     }
 
     /// Get next character message.
@@ -65,8 +96,9 @@ impl Window {
     /// When inputting through an input method, this function produces unexpected character capture.
     /// The `KeyMsg::Char` variant returned by the `getmsg` function should be used instead.
     #[deprecated(since = "0.1.0", note = "Use `KeyMsg::Char` and `getmsg` instead.")]
+    #[cfg(feature = "ege")]
     pub fn getchar(&self) -> Option<char> {
-        if unsafe { ege_kbhit() != 0 } {
+        if self.has_window() && unsafe { ege_kbhit() != 0 } {
             char::from_u32(unsafe { ege_getch() as u32 })
         } else {
             None
@@ -78,6 +110,92 @@ impl Window {
     /// # Returns
     /// * `Some((KeyMsg, KeyFlags))` if has key message, `None` otherwise.
     pub fn getmsg(&self) -> Option<(KeyMsg, KeyFlags)> {
+        if let Some(msg) = replaying(|player| player.keys.pop_front()) {
+            return msg;
+        }
+        let msg = self.has_window().then(live::getmsg).flatten();
+        if let Some((keymsg, flags)) = msg {
+            record(RecordedMsg::Key(keymsg, flags));
+        }
+        msg
+    }
+
+    /// Flush the key message buffer.
+    pub fn flushkey(&self) {
+        if replaying(|player| player.keys.clear()).is_some() {
+            return;
+        }
+        if self.has_window() {
+            live::flushkey();
+        }
+    }
+
+    /// Get next mouse message.
+    ///
+    /// # Returns
+    /// * `Some(MouseMsg)` if has mouse message, `None` otherwise.
+    pub fn getmouse(&self) -> Option<MouseMsg> {
+        if let Some(msg) = replaying(|player| player.mice.pop_front()) {
+            return msg;
+        }
+        let msg = self.has_window().then(live::getmouse).flatten();
+        if let Some(msg) = msg {
+            record(RecordedMsg::Mouse(msg));
+        }
+        msg
+    }
+
+    /// Get the current mouse position.
+    ///
+    /// # Returns
+    /// The current mouse position.
+    pub fn mousepos(&self) -> Point {
+        if let Some(pos) = replaying(|player| player.held.pos) {
+            return pos;
+        }
+        let pos = if self.has_window() {
+            live::mousepos()
+        } else {
+            Point { x: 0, y: 0 }
+        };
+        record(RecordedMsg::MousePos(pos));
+        pos
+    }
+
+    /// Set the mouse cursor visible or not.
+    ///
+    /// # Parameters
+    /// * `visible` - If true, the mouse cursor will be visible.
+    #[cfg(feature = "ege")]
+    pub fn showmouse(&self, show: bool) {
+        if self.has_window() {
+            unsafe { ege_showmouse(show as i32) };
+        }
+    }
+
+    /// Flush the mouse message buffer.
+    pub fn flushmouse(&self) {
+        if replaying(|player| player.mice.clear()).is_some() {
+            return;
+        }
+        if self.has_window() {
+            live::flushmouse();
+        }
+    }
+}
+
+/// The input of the window of EGE.
+#[cfg(feature = "ege")]
+mod live {
+    use xege_ffi::*;
+
+    use crate::{Key, KeyFlags, KeyMsg, MouseMsg, Point};
+
+    pub(super) fn keystate(key: Key) -> bool {
+        unsafe { ege_keystate(<Key as Into<u32>>::into(key) as i32) }
+    }
+
+    pub(super) fn getmsg() -> Option<(KeyMsg, KeyFlags)> {
         if unsafe { ege_kbmsg() != 0 } {
             let msg = unsafe { ege_getkey() };
             let keymsg = match msg.msg {
@@ -104,44 +222,51 @@ impl Window {
         }
     }
 
-    /// Flush the key message buffer.
-    pub fn flushkey(&self) {
+    pub(super) fn flushkey() {
         unsafe { ege_flushkey() };
     }
 
-    /// Get next mouse message.
-    ///
-    /// # Returns
-    /// * `Some(MouseMsg)` if has mouse message, `None` otherwise.
-    pub fn getmouse(&self) -> Option<MouseMsg> {
+    pub(super) fn getmouse() -> Option<MouseMsg> {
         if unsafe { ege_mousemsg() != 0 } {
-            let msg = unsafe { ege_getmouse() };
-            Some(MouseMsg { msg })
+            Some(MouseMsg::from(unsafe { ege_getmouse() }))
         } else {
             None
         }
     }
 
-    /// Get the current mouse position.
-    ///
-    /// # Returns
-    /// The current mouse position.
-    pub fn mousepos(&self) -> Point {
+    pub(super) fn mousepos() -> Point {
         let mut p = Point { x: 0, y: 0 };
         unsafe { ege_mousepos(&mut p.x, &mut p.y) };
         p
     }
 
-    /// Set the mouse cursor visible or not.
-    ///
-    /// # Parameters
-    /// * `visible` - If true, the mouse cursor will be visible.
-    pub fn showmouse(&self, show: bool) {
-        unsafe { ege_showmouse(show as i32) };
+    pub(super) fn flushmouse() {
+        unsafe { ege_flushmouse() };
     }
+}
 
-    /// Flush the mouse message buffer.
-    pub fn flushmouse(&self) {
-        unsafe { ege_flushmouse() };
+/// Without EGE there is no window, and so no input.
+#[cfg(not(feature = "ege"))]
+mod live {
+    use crate::{Key, KeyFlags, KeyMsg, MouseMsg, Point};
+
+    pub(super) fn keystate(_: Key) -> bool {
+        false
     }
+
+    pub(super) fn getmsg() -> Option<(KeyMsg, KeyFlags)> {
+        None
+    }
+
+    pub(super) fn flushkey() {}
+
+    pub(super) fn getmouse() -> Option<MouseMsg> {
+        None
+    }
+
+    pub(super) fn mousepos() -> Point {
+        Point { x: 0, y: 0 }
+    }
+
+    pub(super) fn flushmouse() {}
 }